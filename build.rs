@@ -1,12 +1,9 @@
 fn main() {
-    let output_dir = "src/grpc/proto";
-    if !std::path::Path::new(output_dir).is_dir() {
-        std::fs::create_dir(output_dir).unwrap();
-    }
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
     tonic_build::configure()
-        .out_dir(output_dir)
-        .build_server(false)
+        .build_server(std::env::var_os("CARGO_FEATURE_SERVER").is_some())
         .build_client(true)
+        .file_descriptor_set_path(out_dir.join("descriptors.bin"))
         .compile(
             &[
                 "protobuf/grpc_service.proto",
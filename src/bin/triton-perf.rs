@@ -0,0 +1,208 @@
+use clap::{Parser, ValueEnum};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tritonclient::grpc::client::{InferenceServerClient, InferenceServerClientConfig};
+use tritonclient::grpc::perf::{
+    InputData, LoadMode, PerfMeasurement, PerfOptions, PerfRunner, SyntheticInputs, PERF_CSV_HEADER,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+/// Measure throughput and latency of a Triton model over the gRPC API.
+#[derive(Debug, Parser)]
+#[command(name = "triton-perf", version)]
+struct Args {
+    /// gRPC endpoint of the server
+    #[arg(short, long, default_value = "http://localhost:8001")]
+    url: String,
+    #[arg(short, long)]
+    model: String,
+    /// Model version, the server picks one by default
+    #[arg(short = 'x', long)]
+    model_version: Option<String>,
+    /// Rows per request, for models with batching
+    #[arg(short, long, default_value_t = 1)]
+    batch_size: usize,
+    /// Concurrency levels as `start[:end[:step]]`
+    #[arg(long, value_parser = parse_concurrency, conflicts_with = "request_rate_range")]
+    concurrency_range: Option<Levels<usize>>,
+    /// Request rates per second as `start[:end[:step]]`
+    #[arg(long, value_parser = parse_request_rate)]
+    request_rate_range: Option<Levels<f64>>,
+    /// Length of each measurement in milliseconds
+    #[arg(short = 'p', long, default_value_t = 5000)]
+    measurement_interval: u64,
+    /// Load before each measurement in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    warmup_interval: u64,
+    /// `zero`, `random` or a directory with one raw file per input
+    #[arg(long, default_value = "random")]
+    input_data: String,
+    /// Shape of an input with dynamic dims, without the batch dimension, as `NAME:d1,d2,..`
+    #[arg(long = "shape", value_parser = parse_shape)]
+    shapes: Vec<(String, Vec<i64>)>,
+    /// Report the server side queue and compute times from the model statistics
+    #[arg(long)]
+    server_stats: bool,
+    #[arg(long, value_enum, default_value = "csv")]
+    format: Format,
+    /// Write the report to a file instead of stdout
+    #[arg(short = 'f', long)]
+    output: Option<PathBuf>,
+}
+
+// Load levels of a sweep, one argument rather than a list of them for clap.
+#[derive(Clone, Debug)]
+struct Levels<T>(Vec<T>);
+
+fn parse_range<T>(s: &str, add: impl Fn(T, T) -> T) -> Result<Vec<T>, String>
+where
+    T: std::str::FromStr + PartialOrd + PartialEq + Copy + Default,
+{
+    let parse = |value: &str| {
+        value
+            .parse::<T>()
+            .map_err(|_| format!("invalid value `{value}` in range `{s}`"))
+    };
+    let parts = s.split(':').collect::<Vec<_>>();
+    let (start, end, step) = match parts[..] {
+        [start] => (parse(start)?, parse(start)?, None),
+        [start, end] => (parse(start)?, parse(end)?, None),
+        [start, end, step] => (parse(start)?, parse(end)?, Some(parse(step)?)),
+        _ => return Err(format!("range `{s}` is not `start[:end[:step]]`")),
+    };
+    let step = match step {
+        Some(step) if step <= T::default() => return Err(format!("step of `{s}` must be > 0")),
+        Some(step) => step,
+        None => parse("1")?,
+    };
+    if end < start {
+        return Err(format!("range `{s}` ends before it starts"));
+    }
+    let mut values = vec![];
+    let mut value = start;
+    while value <= end {
+        values.push(value);
+        value = add(value, step);
+    }
+    Ok(values)
+}
+
+fn parse_concurrency(s: &str) -> Result<Levels<usize>, String> {
+    let values = parse_range(s, |a, b| a + b)?;
+    match values.contains(&0) {
+        true => Err("concurrency must be > 0".to_string()),
+        false => Ok(Levels(values)),
+    }
+}
+
+fn parse_request_rate(s: &str) -> Result<Levels<f64>, String> {
+    let values = parse_range(s, |a, b| a + b)?;
+    match values.iter().any(|rate| *rate <= 0.0) {
+        true => Err("request rate must be > 0".to_string()),
+        false => Ok(Levels(values)),
+    }
+}
+
+fn parse_shape(s: &str) -> Result<(String, Vec<i64>), String> {
+    let (name, dims) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("shape `{s}` is not `NAME:d1,d2,..`"))?;
+    let dims = dims
+        .split(',')
+        .map(|dim| dim.trim().parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid dims in `{s}`: {e}"))?;
+    Ok((name.to_string(), dims))
+}
+
+fn report(format: Format, measurements: &[PerfMeasurement], out: &mut dyn Write) -> io::Result<()> {
+    match format {
+        Format::Csv => {
+            writeln!(out, "{PERF_CSV_HEADER}")?;
+            for measurement in measurements {
+                writeln!(out, "{}", measurement.csv_row())?;
+            }
+        }
+        Format::Json => {
+            let measurements = measurements
+                .iter()
+                .map(PerfMeasurement::to_json)
+                .collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut *out, &measurements)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let config = InferenceServerClientConfig::from_uri(&args.url)?;
+    let client = Arc::new(InferenceServerClient::new(config));
+    let version = args.model_version.as_deref();
+    let metadata = client.model_metadata_view(&args.model, version).await?;
+    let config = client.model_config_view(&args.model, version).await?;
+
+    let mut inputs = SyntheticInputs::new()
+        .batch_size(args.batch_size)
+        .data(args.input_data.parse::<InputData>()?);
+    for (name, shape) in args.shapes {
+        inputs.set_shape(name, shape);
+    }
+    let request = inputs.request(&metadata, version.unwrap_or(""), config.max_batch_size())?;
+    let batch_size = if config.supports_batching() {
+        inputs.batch_size
+    } else {
+        1
+    };
+
+    let options = PerfOptions::new()
+        .measurement(Duration::from_millis(args.measurement_interval))
+        .warmup(Duration::from_millis(args.warmup_interval))
+        .server_stats(args.server_stats);
+    let runner = PerfRunner::new(client, request, batch_size).options(options);
+
+    let modes = match (args.concurrency_range, args.request_rate_range) {
+        (_, Some(Levels(rates))) => rates.into_iter().map(LoadMode::RequestRate).collect(),
+        (Some(Levels(levels)), None) => levels.into_iter().map(LoadMode::Concurrency).collect(),
+        (None, None) => vec![LoadMode::Concurrency(1)],
+    };
+    let mut measurements = vec![];
+    for mode in modes {
+        let measurement = runner.measure(mode).await?;
+        let latency = measurement.latency.map(|latency| latency.p99);
+        eprintln!(
+            "{mode}: {:.1} infer/s, p99 latency {:?}, {} errors",
+            measurement.throughput(),
+            latency.unwrap_or_default(),
+            measurement.errors
+        );
+        measurements.push(measurement);
+    }
+
+    match args.output {
+        Some(path) => report(args.format, &measurements, &mut File::create(path)?)?,
+        None => report(args.format, &measurements, &mut io::stdout().lock())?,
+    }
+    Ok(())
+}
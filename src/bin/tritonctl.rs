@@ -0,0 +1,924 @@
+use clap::{Args, Parser, Subcommand};
+use ndarray::ArrayD;
+use prost::Message;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tritonclient::grpc::client::{
+    Error as ClientError, InferenceServerClient, InferenceServerClientConfig, WaitOptions,
+};
+use tritonclient::grpc::health::ServingStatus;
+use tritonclient::grpc::npy::{read_npy, read_npz};
+use tritonclient::grpc::output::{ArrayOutputOneOf, ModelOutput};
+use tritonclient::grpc::pb::{self, InferInputTensor, InferRequestedOutputTensor};
+use tritonclient::grpc::pbtxt::{
+    message_to_json, message_to_pbtxt, model_config_from_json, model_config_to_json,
+    model_config_to_pbtxt, parse_model_config,
+};
+use tritonclient::grpc::repository::ModelLoadRequest;
+use tritonclient::grpc::settings::{
+    LogFormat, LogSettingsUpdate, TraceLevel, TraceMode, TraceSettingsUpdate,
+};
+use tritonclient::types::{Bytes, TritonDataTypes};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Administer a Triton Inference Server over its gRPC API.
+#[derive(Debug, Parser)]
+#[command(name = "tritonctl", version)]
+struct Cli {
+    /// gRPC endpoint of the server
+    #[arg(short, long, global = true, default_value = "http://localhost:8001")]
+    url: String,
+    /// Print JSON instead of protobuf text
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Args)]
+struct ModelArgs {
+    model: String,
+    /// Model version, the server picks one by default
+    #[arg(short = 'x', long)]
+    model_version: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct OptionalModelArgs {
+    /// Model name, the whole server by default
+    model: Option<String>,
+    #[arg(short = 'x', long, requires = "model")]
+    model_version: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// gRPC health of the server or one of its services, exits with 1 when not serving
+    Health {
+        /// e.g. `inference.GRPCInferenceService`, the whole server by default
+        service: Option<String>,
+        /// Print every status change until interrupted
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Whether the server is live, exits with 1 when not
+    Live,
+    /// Whether the server, or a model, is ready, exits with 1 when not
+    Ready(OptionalModelArgs),
+    /// Wait until the server, or the given models, are ready, exits with 1 on timeout
+    Wait {
+        /// Model to wait for as `NAME[:VERSION]`, the server itself by default
+        #[arg(short, long = "model")]
+        models: Vec<String>,
+        /// Overall timeout in seconds
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// Server or model metadata
+    Metadata(OptionalModelArgs),
+    /// Model config, as `config.pbtxt` without `--json`
+    Config(ModelArgs),
+    /// Inference statistics of one or every model
+    Stats(OptionalModelArgs),
+    /// Models of the repository and their state
+    Index {
+        #[arg(long, default_value = "")]
+        repository: String,
+        /// Only list ready models
+        #[arg(long)]
+        ready: bool,
+    },
+    /// Load or reload a model
+    Load {
+        model: String,
+        #[arg(long, default_value = "")]
+        repository: String,
+        /// Config override, `config.pbtxt` or JSON
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Model directory (`<version>/<file>`) sent along the request, needs `--config`
+        #[arg(long, requires = "config")]
+        files: Option<PathBuf>,
+    },
+    /// Unload a model
+    Unload {
+        model: String,
+        #[arg(long, default_value = "")]
+        repository: String,
+        /// Also unload the models it depends on, e.g. ensemble steps
+        #[arg(long)]
+        unload_dependents: bool,
+    },
+    /// System and CUDA shared memory regions
+    Shm {
+        #[command(subcommand)]
+        command: ShmCommand,
+    },
+    /// Show or change the trace settings, of the server or of one model
+    Trace(TraceArgs),
+    /// Show or change the log settings
+    Log(LogArgs),
+    /// Run one inference with inputs from `.npy`/`.npz` files or a JSON request
+    Infer(InferArgs),
+}
+
+#[derive(Debug, Subcommand)]
+enum ShmCommand {
+    /// Registered regions, all of them without a name
+    Status {
+        name: Option<String>,
+        #[arg(long)]
+        cuda: bool,
+    },
+    /// Register a system shared memory region
+    Register {
+        name: String,
+        /// Key of the shared memory object, e.g. `/input_data`
+        #[arg(long)]
+        key: String,
+        #[arg(long)]
+        byte_size: u64,
+        #[arg(long, default_value_t = 0)]
+        offset: u64,
+    },
+    /// Unregister a region, all of them without a name
+    Unregister {
+        name: Option<String>,
+        #[arg(long)]
+        cuda: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct TraceArgs {
+    /// Scope the settings to this model
+    #[arg(short, long)]
+    model: Option<String>,
+    /// `OFF`, `TIMESTAMPS` or `TENSORS`, repeat for several
+    #[arg(long)]
+    level: Vec<TraceLevel>,
+    #[arg(long)]
+    rate: Option<u32>,
+    #[arg(long)]
+    count: Option<i32>,
+    #[arg(long)]
+    log_frequency: Option<u32>,
+    #[arg(long)]
+    file: Option<String>,
+    /// `triton` or `opentelemetry`
+    #[arg(long)]
+    mode: Option<TraceMode>,
+    /// Drop every override of the model, back to the global settings
+    #[arg(long, requires = "model")]
+    clear: bool,
+}
+
+#[derive(Debug, Args)]
+struct LogArgs {
+    #[arg(long)]
+    file: Option<String>,
+    #[arg(long)]
+    info: Option<bool>,
+    #[arg(long)]
+    warning: Option<bool>,
+    #[arg(long)]
+    error: Option<bool>,
+    #[arg(long)]
+    verbose_level: Option<u32>,
+    /// `default` or `ISO8601`
+    #[arg(long)]
+    format: Option<LogFormat>,
+}
+
+#[derive(Debug, Args)]
+struct InferArgs {
+    #[command(flatten)]
+    model: ModelArgs,
+    /// Input from a `.npy` file, as `NAME=PATH`
+    #[arg(short, long = "input", value_parser = parse_input)]
+    inputs: Vec<(String, PathBuf)>,
+    /// Inputs from every array of a `.npz` archive, named after the arrays
+    #[arg(long)]
+    npz: Option<PathBuf>,
+    /// Request in the KServe v2 JSON form, `{"inputs": [{"name", "datatype", "shape", "data"}]}`
+    #[arg(long = "request")]
+    request: Option<PathBuf>,
+    /// Outputs to return, all of them by default
+    #[arg(short, long = "output")]
+    outputs: Vec<String>,
+    #[arg(long, default_value = "")]
+    id: String,
+    /// Also write the outputs to a `.npz` archive, or to a directory of `.npy` files
+    #[arg(long)]
+    save: Option<PathBuf>,
+}
+
+fn parse_input(s: &str) -> Result<(String, PathBuf), String> {
+    s.split_once('=')
+        .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+        .ok_or_else(|| format!("input `{s}` is not `NAME=PATH`"))
+}
+
+// Print a response as protobuf text, or JSON with `--json`. `full_name` is its protobuf type.
+fn print_message<M: Message>(json: bool, message: &M, full_name: &str) -> CliResult<()> {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&message_to_json(message, full_name)?)?
+        );
+    } else {
+        print!("{}", message_to_pbtxt(message, full_name)?);
+    }
+    Ok(())
+}
+
+// Print a yes/no answer, `Ok(false)` ends the process with status 1.
+fn print_flag(json: bool, key: &str, value: bool) -> bool {
+    println!("{}", format_flag(json, key, value));
+    value
+}
+
+fn format_flag(json: bool, key: &str, value: bool) -> String {
+    if json {
+        json!({ key: value }).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_status(json: bool, service: &str, status: ServingStatus) {
+    println!("{}", format_status(json, service, status));
+}
+
+fn format_status(json: bool, service: &str, status: ServingStatus) -> String {
+    if json {
+        json!({ "service": service, "status": status.to_string() }).to_string()
+    } else {
+        status.to_string()
+    }
+}
+
+fn print_done(json: bool, message: impl Display) {
+    println!("{}", format_done(json, message));
+}
+
+fn format_done(json: bool, message: impl Display) -> String {
+    if json {
+        json!({ "status": "ok", "message": message.to_string() }).to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+fn read_config(path: &Path) -> CliResult<pb::ModelConfig> {
+    let text = fs::read_to_string(path)?;
+    Ok(match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => model_config_from_json(&text)?,
+        _ => parse_model_config(&text)?,
+    })
+}
+
+async fn trace(client: &InferenceServerClient, json: bool, args: TraceArgs) -> CliResult<()> {
+    let mut update = match &args.model {
+        Some(model) => TraceSettingsUpdate::for_model(model),
+        None => TraceSettingsUpdate::global(),
+    };
+    if args.clear {
+        update = update.clear_all();
+    }
+    if !args.level.is_empty() {
+        update.set_level(&args.level);
+    }
+    if let Some(rate) = args.rate {
+        update.set_rate(rate);
+    }
+    if let Some(count) = args.count {
+        update.set_count(count);
+    }
+    if let Some(log_frequency) = args.log_frequency {
+        update.set_log_frequency(log_frequency);
+    }
+    if let Some(file) = args.file {
+        update.set_file(file);
+    }
+    if let Some(mode) = args.mode {
+        update.set_mode(mode);
+    }
+    // Without changes this only reads the settings
+    let request = pb::TraceSettingRequest::from(update);
+    let settings = Some(&request.settings).filter(|settings| !settings.is_empty());
+    let response = client.trace_setting(&request.model_name, settings).await?;
+    print_message(json, &response, "inference.TraceSettingResponse")
+}
+
+async fn log(client: &InferenceServerClient, json: bool, args: LogArgs) -> CliResult<()> {
+    let mut update = LogSettingsUpdate::new();
+    if let Some(file) = args.file {
+        update.set_file(file);
+    }
+    if let Some(info) = args.info {
+        update.set_info(info);
+    }
+    if let Some(warning) = args.warning {
+        update.set_warning(warning);
+    }
+    if let Some(error) = args.error {
+        update.set_error(error);
+    }
+    if let Some(verbose_level) = args.verbose_level {
+        update.set_verbose_level(verbose_level);
+    }
+    if let Some(format) = args.format {
+        update.set_format(format);
+    }
+    let request = pb::LogSettingsRequest::from(update);
+    let settings = Some(&request.settings).filter(|settings| !settings.is_empty());
+    let response = client.log_settings(settings).await?;
+    print_message(json, &response, "inference.LogSettingsResponse")
+}
+
+// IEEE half precision bits of `value`, rounded to nearest.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = (mantissa | 0x80_0000) >> (1 - exponent);
+        return sign | ((mantissa + 0x1000) >> 13) as u16;
+    }
+    // a mantissa rounded up to 0x400 carries into the exponent
+    sign | (((exponent as u32) << 10) + ((mantissa + 0x1000) >> 13)) as u16
+}
+
+// Append one JSON element to raw tensor contents.
+fn encode_element(datatype: &TritonDataTypes, value: &Value, data: &mut Bytes) -> CliResult<()> {
+    let invalid = || format!("`{value}` is not a valid {datatype} element");
+    let int = || value.as_i64().or_else(|| value.as_u64().map(|v| v as i64));
+    let float = || value.as_f64();
+    match datatype {
+        TritonDataTypes::BOOL => data.push(value.as_bool().ok_or_else(invalid)? as u8),
+        TritonDataTypes::INT8 | TritonDataTypes::UINT8 => {
+            data.push(int().ok_or_else(invalid)? as u8)
+        }
+        TritonDataTypes::INT16 | TritonDataTypes::UINT16 => {
+            data.extend((int().ok_or_else(invalid)? as u16).to_le_bytes())
+        }
+        TritonDataTypes::INT32 | TritonDataTypes::UINT32 => {
+            data.extend((int().ok_or_else(invalid)? as u32).to_le_bytes())
+        }
+        TritonDataTypes::INT64 => data.extend(int().ok_or_else(invalid)?.to_le_bytes()),
+        TritonDataTypes::UINT64 => data.extend(value.as_u64().ok_or_else(invalid)?.to_le_bytes()),
+        TritonDataTypes::FP16 => {
+            data.extend(f32_to_f16(float().ok_or_else(invalid)? as f32).to_le_bytes())
+        }
+        TritonDataTypes::BF16 => {
+            let bits = (float().ok_or_else(invalid)? as f32).to_bits();
+            data.extend(((bits >> 16) as u16).to_le_bytes())
+        }
+        TritonDataTypes::FP32 => data.extend((float().ok_or_else(invalid)? as f32).to_le_bytes()),
+        TritonDataTypes::FP64 => data.extend(float().ok_or_else(invalid)?.to_le_bytes()),
+        TritonDataTypes::BYTES => {
+            let value = value.as_str().ok_or_else(invalid)?;
+            data.extend((value.len() as u32).to_le_bytes());
+            data.extend(value.as_bytes());
+        }
+    }
+    Ok(())
+}
+
+// Shape of nested JSON arrays, checking that they are rectangular.
+fn json_shape(value: &Value) -> CliResult<Vec<i64>> {
+    match value {
+        Value::Array(items) => {
+            let mut shape = vec![items.len() as i64];
+            if let Some(first) = items.first() {
+                let inner = json_shape(first)?;
+                for item in &items[1..] {
+                    if json_shape(item)? != inner {
+                        return Err(format!("`{value}` is not a rectangular array").into());
+                    }
+                }
+                shape.extend(inner);
+            }
+            Ok(shape)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+fn flatten<'a>(value: &'a Value, elements: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| flatten(item, elements)),
+        value => elements.push(value),
+    }
+}
+
+// Inputs of a KServe v2 JSON request, a missing datatype comes from the model metadata.
+fn json_inputs(
+    path: &Path,
+    metadata: &pb::ModelMetadataResponse,
+) -> CliResult<Vec<(InferInputTensor, Bytes)>> {
+    let request: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let inputs = request
+        .get("inputs")
+        .and_then(Value::as_array)
+        .ok_or_else(|| format!("`{}` has no `inputs` array", path.display()))?;
+    let mut tensors = vec![];
+    for input in inputs {
+        let name = input
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("input `{input}` has no name"))?;
+        let data = input
+            .get("data")
+            .ok_or_else(|| format!("input `{name}` has no data"))?;
+        let datatype = match input.get("datatype").and_then(Value::as_str) {
+            Some(datatype) => datatype.parse::<TritonDataTypes>()?,
+            None => metadata
+                .inputs
+                .iter()
+                .find(|tensor| tensor.name == name)
+                .ok_or_else(|| format!("model has no input `{name}`, give its datatype"))?
+                .datatype
+                .parse::<TritonDataTypes>()?,
+        };
+        let shape = match input.get("shape") {
+            Some(shape) => serde_json::from_value::<Vec<i64>>(shape.clone())?,
+            None => json_shape(data)?,
+        };
+        let mut elements = vec![];
+        flatten(data, &mut elements);
+        let mut raw = vec![];
+        for element in elements {
+            encode_element(&datatype, element, &mut raw)?;
+        }
+        tensors.push((
+            InferInputTensor {
+                name: name.to_string(),
+                datatype: datatype.to_string(),
+                shape,
+                ..Default::default()
+            },
+            raw,
+        ));
+    }
+    Ok(tensors)
+}
+
+fn output_json(name: &str, array: &ArrayOutputOneOf) -> Value {
+    fn tensor<T: Clone + Into<Value>>(name: &str, datatype: &str, array: &ArrayD<T>) -> Value {
+        json!({
+            "name": name,
+            "datatype": datatype,
+            "shape": array.shape(),
+            "data": array.iter().cloned().map(Into::into).collect::<Vec<Value>>(),
+        })
+    }
+    match array {
+        ArrayOutputOneOf::BOOL(array) => tensor(name, "BOOL", array),
+        ArrayOutputOneOf::INT8(array) => tensor(name, "INT8", array),
+        ArrayOutputOneOf::INT16(array) => tensor(name, "INT16", array),
+        ArrayOutputOneOf::INT32(array) => tensor(name, "INT32", array),
+        ArrayOutputOneOf::INT64(array) => tensor(name, "INT64", array),
+        ArrayOutputOneOf::UINT8(array) => tensor(name, "UINT8", array),
+        ArrayOutputOneOf::UINT16(array) => tensor(name, "UINT16", array),
+        ArrayOutputOneOf::UINT32(array) => tensor(name, "UINT32", array),
+        ArrayOutputOneOf::UINT64(array) => tensor(name, "UINT64", array),
+        ArrayOutputOneOf::FP32(array) => tensor(name, "FP32", array),
+        ArrayOutputOneOf::FP64(array) => tensor(name, "FP64", array),
+        ArrayOutputOneOf::BYTES(array) => {
+            let strings = array.map(|bytes| String::from_utf8_lossy(bytes).into_owned());
+            tensor(name, "BYTES", &strings)
+        }
+    }
+}
+
+// Outputs sorted by name, as a KServe v2 JSON response with `--json`.
+fn format_output(json: bool, model: &str, output: ModelOutput) -> String {
+    let mut outputs = output.into_inner().into_iter().collect::<Vec<_>>();
+    outputs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    if json {
+        let outputs = outputs
+            .iter()
+            .map(|(name, array)| output_json(name, array))
+            .collect::<Vec<_>>();
+        let response = json!({ "model_name": model, "outputs": outputs });
+        return serde_json::to_string_pretty(&response).unwrap_or_default() + "\n";
+    }
+    let mut text = String::new();
+    for (name, array) in outputs {
+        let tensor = output_json(&name, &array);
+        let datatype = tensor["datatype"].as_str().unwrap_or_default();
+        text += &format!("{name}: {datatype} {:?}\n", array.shape());
+        text += &match array {
+            ArrayOutputOneOf::BOOL(array) => format!("{array}\n"),
+            ArrayOutputOneOf::INT8(array) => format!("{array}\n"),
+            ArrayOutputOneOf::INT16(array) => format!("{array}\n"),
+            ArrayOutputOneOf::INT32(array) => format!("{array}\n"),
+            ArrayOutputOneOf::INT64(array) => format!("{array}\n"),
+            ArrayOutputOneOf::UINT8(array) => format!("{array}\n"),
+            ArrayOutputOneOf::UINT16(array) => format!("{array}\n"),
+            ArrayOutputOneOf::UINT32(array) => format!("{array}\n"),
+            ArrayOutputOneOf::UINT64(array) => format!("{array}\n"),
+            ArrayOutputOneOf::FP32(array) => format!("{array}\n"),
+            ArrayOutputOneOf::FP64(array) => format!("{array}\n"),
+            // Quoted, so that empty and blank strings stay visible
+            ArrayOutputOneOf::BYTES(array) => {
+                let strings = array.map(|b| format!("{:?}", String::from_utf8_lossy(b)));
+                format!("{strings}\n")
+            }
+        };
+    }
+    text
+}
+
+async fn infer(client: &InferenceServerClient, json: bool, args: InferArgs) -> CliResult<()> {
+    let model = &args.model.model;
+    let version = args.model.model_version.as_deref();
+    let mut request = pb::ModelInferRequest {
+        model_name: model.clone(),
+        model_version: version.unwrap_or("").to_string(),
+        id: args.id,
+        outputs: args
+            .outputs
+            .iter()
+            .map(|name| InferRequestedOutputTensor {
+                name: name.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    // Every input goes as raw contents, the server rejects requests mixing both forms
+    if let Some(path) = &args.request {
+        let metadata = client.model_metadata(model, version).await?;
+        for (tensor, raw) in json_inputs(path, &metadata)? {
+            request.inputs.push(tensor);
+            request.raw_input_contents.push(raw);
+        }
+    }
+    let mut arrays = match &args.npz {
+        Some(path) => read_npz(path)?,
+        None => vec![],
+    };
+    for (name, path) in args.inputs {
+        arrays.push((name, read_npy(&path)?));
+    }
+    for (name, array) in arrays {
+        request.inputs.push(InferInputTensor {
+            name,
+            datatype: array.datatype.to_string(),
+            shape: array.shape.iter().map(|dim| *dim as i64).collect(),
+            ..Default::default()
+        });
+        request.raw_input_contents.push(array.data);
+    }
+    if request.inputs.is_empty() {
+        return Err(
+            "give inputs with `--input NAME=PATH`, `--npz FILE` or `--request FILE`".into(),
+        );
+    }
+    let output = client.infer(request).await?;
+    match &args.save {
+        Some(path) if path.extension().is_some_and(|ext| ext == "npz") => output.write_npz(path)?,
+        Some(dir) => output.write_npy_dir(dir)?,
+        None => {}
+    }
+    print!("{}", format_output(json, model, output));
+    Ok(())
+}
+
+async fn run(cli: Cli) -> CliResult<bool> {
+    let client = InferenceServerClient::new(InferenceServerClientConfig::from_uri(&cli.url)?);
+    let json = cli.json;
+    match cli.command {
+        Command::Health { service, watch } => {
+            let service = service.unwrap_or_default();
+            if !watch {
+                let status = client.check_health(&service).await?;
+                print_status(json, &service, status);
+                return Ok(status.is_serving());
+            }
+            let mut watch = client.watch_health(&service).await?;
+            while let Some(status) = watch.changed().await {
+                print_status(json, &service, status?);
+            }
+        }
+        Command::Live => return Ok(print_flag(json, "live", client.is_server_live().await?)),
+        Command::Ready(args) => {
+            let ready = match &args.model {
+                Some(model) => {
+                    client
+                        .is_model_ready(model, args.model_version.as_deref())
+                        .await?
+                }
+                None => client.is_server_ready().await?,
+            };
+            return Ok(print_flag(json, "ready", ready));
+        }
+        Command::Wait { models, timeout } => {
+            let wait = WaitOptions::new().timeout(Duration::from_secs(timeout));
+            let models = models
+                .iter()
+                .map(|model| match model.split_once(':') {
+                    Some((name, version)) => (name, Some(version)),
+                    None => (model.as_str(), None),
+                })
+                .collect::<Vec<_>>();
+            let result = match models.is_empty() {
+                true => client.wait_for_server_ready(&wait).await,
+                false => client.wait_for_models_ready(&models, &wait).await,
+            };
+            return match result {
+                Ok(()) => Ok(print_flag(json, "ready", true)),
+                Err(
+                    e @ (ClientError::ServerNotReady { .. } | ClientError::ModelsNotReady { .. }),
+                ) => {
+                    eprintln!("{e}");
+                    Ok(print_flag(json, "ready", false))
+                }
+                Err(e) => Err(e.into()),
+            };
+        }
+        Command::Metadata(args) => match &args.model {
+            Some(model) => {
+                let version = args.model_version.as_deref();
+                let response = client.model_metadata(model, version).await?;
+                print_message(json, &response, "inference.ModelMetadataResponse")?;
+            }
+            None => {
+                let response = client.server_metadata().await?;
+                print_message(json, &response, "inference.ServerMetadataResponse")?;
+            }
+        },
+        Command::Config(args) => {
+            let version = args.model_version.as_deref();
+            let config = client
+                .model_config(&args.model, version)
+                .await?
+                .config
+                .unwrap_or_default();
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&model_config_to_json(&config))?
+                );
+            } else {
+                print!("{}", model_config_to_pbtxt(&config));
+            }
+        }
+        Command::Stats(args) => {
+            let model = args.model.as_deref().unwrap_or("");
+            let version = args.model_version.as_deref();
+            let response = client.model_statistics(model, version).await?;
+            print_message(json, &response, "inference.ModelStatisticsResponse")?;
+        }
+        Command::Index { repository, ready } => {
+            let response = client.repository_index(&repository, ready).await?;
+            print_message(json, &response, "inference.RepositoryIndexResponse")?;
+        }
+        Command::Load {
+            model,
+            repository,
+            config,
+            files,
+        } => {
+            let mut request = ModelLoadRequest::new(&model).repository_name(repository);
+            if let Some(path) = config {
+                request.set_config(read_config(&path)?);
+            }
+            if let Some(dir) = files {
+                request.set_files_from_dir(dir)?;
+            }
+            client.load_model(request).await?;
+            print_done(json, format!("loaded {model}"));
+        }
+        Command::Unload {
+            model,
+            repository,
+            unload_dependents,
+        } => {
+            let parameters = HashMap::from([(
+                "unload_dependents".to_string(),
+                pb::ModelRepositoryParameter {
+                    parameter_choice: Some(
+                        pb::model_repository_parameter::ParameterChoice::BoolParam(
+                            unload_dependents,
+                        ),
+                    ),
+                },
+            )]);
+            client
+                .repository_model_unload(&repository, &model, Some(&parameters))
+                .await?;
+            print_done(json, format!("unloaded {model}"));
+        }
+        Command::Shm { command } => match command {
+            ShmCommand::Status { name, cuda: false } => {
+                let response = client
+                    .system_shared_memory_status(name.as_deref().unwrap_or(""))
+                    .await?;
+                print_message(
+                    json,
+                    &response,
+                    "inference.SystemSharedMemoryStatusResponse",
+                )?;
+            }
+            ShmCommand::Status { name, cuda: true } => {
+                let response = client
+                    .cuda_shared_memory_status(name.as_deref().unwrap_or(""))
+                    .await?;
+                print_message(json, &response, "inference.CudaSharedMemoryStatusResponse")?;
+            }
+            ShmCommand::Register {
+                name,
+                key,
+                byte_size,
+                offset,
+            } => {
+                client
+                    .system_shared_memory_register(&name, &key, offset, byte_size)
+                    .await?;
+                print_done(json, format!("registered {name}"));
+            }
+            ShmCommand::Unregister { name, cuda } => {
+                let name = name.unwrap_or_default();
+                if cuda {
+                    client.cuda_shared_memory_unregister(&name).await?;
+                } else {
+                    client.system_shared_memory_unregister(&name).await?;
+                }
+                match name.as_str() {
+                    "" => print_done(json, "unregistered every region"),
+                    name => print_done(json, format!("unregistered {name}")),
+                }
+            }
+        },
+        Command::Trace(args) => trace(&client, json, args).await?,
+        Command::Log(args) => log(&client, json, args).await?,
+        Command::Infer(args) => infer(&client, json, args).await?,
+    }
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn output() -> ModelOutput {
+        let raw = |values: &[i32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let tensor = |name: &str, datatype: &str, shape: Vec<i64>| pb::InferOutputTensor {
+            name: name.to_string(),
+            datatype: datatype.to_string(),
+            shape,
+            ..Default::default()
+        };
+        ModelOutput::new(pb::ModelInferResponse {
+            outputs: vec![
+                tensor("scores", "INT32", vec![2, 2]),
+                tensor("labels", "BYTES", vec![1]),
+            ],
+            raw_output_contents: vec![raw(&[1, 2, 3, 4]), b"\x03\x00\x00\x00cat".to_vec()],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn checks_the_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_commands() {
+        let cli = Cli::try_parse_from([
+            "tritonctl",
+            "--json",
+            "infer",
+            "resnet",
+            "-x",
+            "2",
+            "-i",
+            "x=a.npy",
+            "-o",
+            "y",
+        ])
+        .unwrap();
+        assert!(cli.json);
+        assert_eq!(cli.url, "http://localhost:8001");
+        match cli.command {
+            Command::Infer(args) => {
+                assert_eq!(args.model.model, "resnet");
+                assert_eq!(args.model.model_version.as_deref(), Some("2"));
+                assert_eq!(args.inputs, vec![("x".to_string(), PathBuf::from("a.npy"))]);
+                assert_eq!(args.outputs, vec!["y"]);
+            }
+            command => panic!("unexpected command {command:?}"),
+        }
+
+        let cli = Cli::try_parse_from(["tritonctl", "trace", "-m", "m", "--level", "tensors"]);
+        assert!(matches!(
+            cli.unwrap().command,
+            Command::Trace(TraceArgs { level, .. }) if level == vec![TraceLevel::Tensors]
+        ));
+        let invalid = [
+            vec!["tritonctl", "infer", "m", "-i", "no-path"],
+            vec!["tritonctl", "ready", "-x", "1"],
+            vec!["tritonctl", "load", "m", "--files", "dir"],
+            vec!["tritonctl", "trace", "--clear"],
+            vec!["tritonctl", "log", "--format", "xml"],
+        ];
+        for args in invalid {
+            assert!(Cli::try_parse_from(&args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn formats_outputs_as_text_and_json() {
+        assert_eq!(
+            format_output(false, "model", output()),
+            "labels: BYTES [1]\n[\"cat\"]\nscores: INT32 [2, 2]\n[[1, 2],\n [3, 4]]\n"
+        );
+        let json: Value = serde_json::from_str(&format_output(true, "model", output())).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "model_name": "model",
+                "outputs": [
+                    {"name": "labels", "datatype": "BYTES", "shape": [1], "data": ["cat"]},
+                    {"name": "scores", "datatype": "INT32", "shape": [2, 2], "data": [1, 2, 3, 4]},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn formats_answers() {
+        assert_eq!(format_flag(false, "ready", true), "true");
+        assert_eq!(format_flag(true, "ready", false), r#"{"ready":false}"#);
+        assert_eq!(
+            format_status(false, "", ServingStatus::NotServing),
+            "NOT_SERVING"
+        );
+        assert_eq!(
+            format_status(true, "svc", ServingStatus::Serving),
+            r#"{"service":"svc","status":"SERVING"}"#
+        );
+        assert_eq!(format_done(false, "loaded `m`"), "loaded `m`");
+        assert_eq!(
+            format_done(true, "loaded `m`"),
+            r#"{"message":"loaded `m`","status":"ok"}"#
+        );
+    }
+
+    #[test]
+    fn encodes_json_request_elements() {
+        let mut data = vec![];
+        encode_element(&TritonDataTypes::INT16, &json!(-2), &mut data).unwrap();
+        encode_element(&TritonDataTypes::BYTES, &json!("ab"), &mut data).unwrap();
+        encode_element(&TritonDataTypes::FP16, &json!(1.0), &mut data).unwrap();
+        assert_eq!(data, vec![0xfe, 0xff, 2, 0, 0, 0, b'a', b'b', 0x00, 0x3c]);
+        assert!(encode_element(&TritonDataTypes::BOOL, &json!(1), &mut data).is_err());
+
+        assert_eq!(
+            json_shape(&json!([[1, 2, 3], [4, 5, 6]])).unwrap(),
+            vec![2, 3]
+        );
+        assert!(json_shape(&json!([[1, 2], [3]])).is_err());
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(-0.5), 0xb800);
+    }
+}
@@ -0,0 +1,220 @@
+use super::client::{Error, Result};
+use super::input::{InferInput, ModelInput};
+use super::pb::{InferInputTensor, InferTensorContents};
+
+/// A request holding several samples, as built by [`batch_samples`].
+#[derive(Clone, Debug)]
+pub struct Batch {
+    pub input: ModelInput,
+    /// Number of samples merged into `input`
+    pub batch_size: usize,
+}
+
+fn append_contents(contents: &mut InferTensorContents, other: InferTensorContents) {
+    contents.bool_contents.extend(other.bool_contents);
+    contents.int_contents.extend(other.int_contents);
+    contents.int64_contents.extend(other.int64_contents);
+    contents.uint_contents.extend(other.uint_contents);
+    contents.uint64_contents.extend(other.uint64_contents);
+    contents.fp32_contents.extend(other.fp32_contents);
+    contents.fp64_contents.extend(other.fp64_contents);
+    contents.bytes_contents.extend(other.bytes_contents);
+}
+
+// Merge the same input of every group, either along a new first dimension or along the
+// existing one. Returns the merged tensors in the order of the first group.
+fn merge(groups: Vec<Vec<InferInput>>, new_dim: bool) -> Result<Vec<InferInput>> {
+    let mut groups = groups.into_iter().map(|inputs| {
+        inputs
+            .into_iter()
+            .map(InferInput::build)
+            .collect::<Vec<_>>()
+    });
+    let Some(first) = groups.next() else {
+        return Ok(vec![]);
+    };
+    let mut merged = first
+        .into_iter()
+        .map(|mut tensor| {
+            if tensor.contents.is_none() {
+                return Err(Error::InvalidBatch(format!(
+                    "input `{}` has no data to merge",
+                    tensor.name
+                )));
+            }
+            if new_dim {
+                tensor.shape.insert(0, 1);
+            } else if tensor.shape.is_empty() {
+                return Err(Error::InvalidBatch(format!(
+                    "input `{}` has no batch dimension",
+                    tensor.name
+                )));
+            }
+            Ok(tensor)
+        })
+        .collect::<Result<Vec<InferInputTensor>>>()?;
+
+    for (group, inputs) in groups.enumerate() {
+        if inputs.len() != merged.len() {
+            return Err(Error::InvalidBatch(format!(
+                "group {} has {} inputs, expected {}",
+                group + 1,
+                inputs.len(),
+                merged.len()
+            )));
+        }
+        for tensor in inputs {
+            let target = merged
+                .iter_mut()
+                .find(|target| target.name == tensor.name)
+                .ok_or_else(|| {
+                    Error::InvalidBatch(format!("unexpected input `{}`", tensor.name))
+                })?;
+            let (rows, shape) = if new_dim {
+                (1, tensor.shape.as_slice())
+            } else {
+                match tensor.shape.split_first() {
+                    Some((rows, shape)) => (*rows, shape),
+                    None => {
+                        return Err(Error::InvalidBatch(format!(
+                            "input `{}` has no batch dimension",
+                            tensor.name
+                        )))
+                    }
+                }
+            };
+            if target.datatype != tensor.datatype || target.shape[1..] != *shape {
+                return Err(Error::InvalidBatch(format!(
+                    "input `{}` is {} {:?}, expected {} {:?}",
+                    tensor.name,
+                    tensor.datatype,
+                    shape,
+                    target.datatype,
+                    &target.shape[1..]
+                )));
+            }
+            let contents = tensor.contents.ok_or_else(|| {
+                Error::InvalidBatch(format!("input `{}` has no data to merge", tensor.name))
+            })?;
+            target.shape[0] += rows;
+            if let Some(target) = target.contents.as_mut() {
+                append_contents(target, contents);
+            }
+        }
+    }
+    Ok(merged.into_iter().map(InferInput::from).collect())
+}
+
+/// Stack per-sample inputs (without batch dimension) along a new first dimension.
+pub fn stack_inputs(samples: Vec<Vec<InferInput>>) -> Result<Vec<InferInput>> {
+    merge(samples, true)
+}
+
+/// Concatenate inputs that already have a batch dimension along it.
+pub fn concat_inputs(batches: Vec<Vec<InferInput>>) -> Result<Vec<InferInput>> {
+    merge(batches, false)
+}
+
+/// Stack samples into requests of at most `max_batch_size` samples, built from `template`.
+/// A model that does not batch (`max_batch_size` of 0) gets one request per sample, unchanged.
+pub fn batch_samples(
+    template: &ModelInput,
+    samples: Vec<Vec<InferInput>>,
+    max_batch_size: i32,
+) -> Result<Vec<Batch>> {
+    if max_batch_size <= 0 {
+        return Ok(samples
+            .into_iter()
+            .map(|inputs| Batch {
+                input: template.clone().inputs(inputs),
+                batch_size: 1,
+            })
+            .collect());
+    }
+
+    let mut batches = vec![];
+    let mut samples = samples.into_iter().peekable();
+    while samples.peek().is_some() {
+        let chunk = samples
+            .by_ref()
+            .take(max_batch_size as usize)
+            .collect::<Vec<_>>();
+        let batch_size = chunk.len();
+        batches.push(Batch {
+            input: template.clone().inputs(stack_inputs(chunk)?),
+            batch_size,
+        });
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TritonDataTypes;
+    use ndarray::ArrayD;
+
+    fn sample(values: &[i32]) -> Vec<InferInput> {
+        let array = ArrayD::from_shape_vec(vec![values.len()], values.to_vec()).unwrap();
+        vec![InferInput::new()
+            .name("x".to_string())
+            .datatype(TritonDataTypes::INT32)
+            .data_from_ndarray(array)]
+    }
+
+    fn tensor(inputs: Vec<InferInput>) -> InferInputTensor {
+        let mut inputs = inputs.into_iter().map(InferInput::build);
+        let tensor = inputs.next().unwrap();
+        assert!(inputs.next().is_none());
+        tensor
+    }
+
+    #[test]
+    fn stacks_along_a_new_dimension() {
+        let stacked = tensor(stack_inputs(vec![sample(&[1, 2]), sample(&[3, 4])]).unwrap());
+        assert_eq!(stacked.shape, vec![2, 2]);
+        assert_eq!(stacked.contents.unwrap().int_contents, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn concatenates_along_the_batch_dimension() {
+        let batch = |values: &[i32]| stack_inputs(values.chunks(2).map(sample).collect()).unwrap();
+        let merged = tensor(concat_inputs(vec![batch(&[1, 2]), batch(&[3, 4, 5, 6])]).unwrap());
+        assert_eq!(merged.shape, vec![3, 2]);
+        assert_eq!(
+            merged.contents.unwrap().int_contents,
+            vec![1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn rejects_samples_of_different_shapes() {
+        let error = stack_inputs(vec![sample(&[1, 2]), sample(&[3])]).unwrap_err();
+        assert!(matches!(error, Error::InvalidBatch(_)), "{error}");
+    }
+
+    #[test]
+    fn splits_samples_by_max_batch_size() {
+        let samples = (0..5).map(|i| sample(&[i])).collect::<Vec<_>>();
+        let batches = batch_samples(&ModelInput::new(), samples, 2).unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.batch_size).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let request = batches[2].input.clone().build();
+        assert_eq!(request.inputs[0].shape, vec![1, 1]);
+        assert_eq!(
+            request.inputs[0].contents.as_ref().unwrap().int_contents,
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn keeps_samples_apart_without_batching() {
+        let samples = (0..3).map(|i| sample(&[i])).collect::<Vec<_>>();
+        let batches = batch_samples(&ModelInput::new(), samples, 0).unwrap();
+        assert_eq!(batches.len(), 3);
+        let request = batches[1].input.clone().build();
+        assert_eq!(request.inputs[0].shape, vec![1]);
+    }
+}
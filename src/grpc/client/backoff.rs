@@ -0,0 +1,131 @@
+use super::AsTimeout;
+use std::time::Duration;
+
+/// Exponential delays between polls or retries.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_initial<T: AsTimeout>(&mut self, initial: T) {
+        self.initial = initial.timeout();
+    }
+
+    pub fn initial<T: AsTimeout>(mut self, initial: T) -> Self {
+        self.set_initial(initial);
+        self
+    }
+
+    pub fn set_max<T: AsTimeout>(&mut self, max: T) {
+        self.max = max.timeout();
+    }
+
+    pub fn max<T: AsTimeout>(mut self, max: T) -> Self {
+        self.set_max(max);
+        self
+    }
+
+    pub fn set_multiplier(&mut self, multiplier: f64) {
+        self.multiplier = multiplier;
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.set_multiplier(multiplier);
+        self
+    }
+
+    /// Delay before attempt `attempt + 1`, attempts counting from 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.min(i32::MAX as u32) as i32);
+        self.initial
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(50),
+            max: Duration::from_secs(2),
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// How long to wait for a state change and how often to check.
+#[derive(Clone, Debug)]
+pub struct WaitOptions {
+    pub timeout: Duration,
+    pub backoff: Backoff,
+}
+
+impl WaitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_timeout<T: AsTimeout>(&mut self, timeout: T) {
+        self.timeout = timeout.timeout();
+    }
+
+    pub fn timeout<T: AsTimeout>(mut self, timeout: T) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.set_backoff(backoff);
+        self
+    }
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_up_to_the_cap() {
+        let backoff = Backoff::new()
+            .initial(Duration::from_millis(10))
+            .max(Duration::from_millis(100))
+            .multiplier(2.0);
+        let delays = (0..6)
+            .map(|attempt| backoff.delay(attempt))
+            .collect::<Vec<_>>();
+        let ms = [10, 20, 40, 80, 100, 100].map(Duration::from_millis);
+        assert_eq!(delays, ms);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn never_shrinks() {
+        let backoff = Backoff::new()
+            .initial(Duration::from_millis(10))
+            .multiplier(0.5);
+        assert_eq!(backoff.delay(3), Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,295 @@
+use super::{AsTimeout, Error, InferenceServerClient, Result};
+use crate::grpc::batch::concat_inputs;
+use crate::grpc::input::InferInput;
+use crate::grpc::model::DynamicBatching;
+use crate::grpc::output::ModelOutput;
+use crate::grpc::pb;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+struct Pending {
+    request: pb::ModelInferRequest,
+    rows: usize,
+    respond: oneshot::Sender<Result<ModelOutput>>,
+}
+
+#[derive(Clone, Debug)]
+struct Settings {
+    max_queue_delay: Duration,
+    preferred_batch_sizes: Vec<usize>,
+    max_batch_size: Option<usize>,
+}
+
+/// Merges concurrent `infer` calls for the same model into batched requests.
+///
+/// Calls are queued per model and version, a batch is sent once it reaches the largest preferred
+/// batch size (or the max batch size) or when the first queued call has waited `max_queue_delay`.
+/// Every caller gets back its own rows of the outputs.
+pub struct DynamicBatcher {
+    client: InferenceServerClient,
+    settings: Settings,
+    queues: Mutex<HashMap<(String, String), mpsc::UnboundedSender<Pending>>>,
+}
+
+impl DynamicBatcher {
+    pub fn new(client: InferenceServerClient) -> Self {
+        Self {
+            client,
+            settings: Settings {
+                max_queue_delay: Duration::from_millis(1),
+                preferred_batch_sizes: vec![],
+                max_batch_size: None,
+            },
+            queues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_max_queue_delay<T: AsTimeout>(&mut self, max_queue_delay: T) {
+        self.settings.max_queue_delay = max_queue_delay.timeout();
+    }
+
+    pub fn max_queue_delay<T: AsTimeout>(mut self, max_queue_delay: T) -> Self {
+        self.set_max_queue_delay(max_queue_delay);
+        self
+    }
+
+    pub fn set_preferred_batch_sizes(&mut self, preferred_batch_sizes: Vec<usize>) {
+        self.settings.preferred_batch_sizes = preferred_batch_sizes;
+    }
+
+    pub fn preferred_batch_sizes(mut self, preferred_batch_sizes: Vec<usize>) -> Self {
+        self.set_preferred_batch_sizes(preferred_batch_sizes);
+        self
+    }
+
+    /// Upper bound on the rows of a batch, the model `max_batch_size` always applies as well
+    pub fn set_max_batch_size(&mut self, max_batch_size: Option<usize>) {
+        self.settings.max_batch_size = max_batch_size;
+    }
+
+    pub fn max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.set_max_batch_size(Some(max_batch_size));
+        self
+    }
+
+    /// Take the delay and preferred batch sizes from a model `dynamic_batching` config.
+    pub fn set_dynamic_batching(&mut self, batching: &DynamicBatching) {
+        self.settings.max_queue_delay = batching.max_queue_delay;
+        self.settings.preferred_batch_sizes = batching
+            .preferred_batch_sizes
+            .iter()
+            .filter(|size| **size > 0)
+            .map(|size| *size as usize)
+            .collect();
+    }
+
+    pub fn dynamic_batching(mut self, batching: &DynamicBatching) -> Self {
+        self.set_dynamic_batching(batching);
+        self
+    }
+
+    pub fn client(&self) -> &InferenceServerClient {
+        &self.client
+    }
+
+    /// Same contract as `InferenceServerClient::infer`, the request must carry its batch dimension.
+    /// Requests whose data cannot be merged (raw contents, shared memory) are sent on their own.
+    pub async fn infer(&self, request: impl Into<pb::ModelInferRequest>) -> Result<ModelOutput> {
+        let request = request.into();
+        let Some(rows) = batch_rows(&request) else {
+            return self.client.infer(request).await;
+        };
+
+        let (respond, response) = oneshot::channel();
+        self.enqueue(Pending {
+            request,
+            rows,
+            respond,
+        })?;
+        response.await.unwrap_or_else(|_| {
+            Err(Error::InvalidBatch(
+                "the batch was dropped before completing".to_string(),
+            ))
+        })
+    }
+
+    fn enqueue(&self, mut pending: Pending) -> Result<()> {
+        let key = (
+            pending.request.model_name.clone(),
+            pending.request.model_version.clone(),
+        );
+        let mut queues = self.queues.lock()?;
+        if let Some(queue) = queues.get(&key) {
+            match queue.send(pending) {
+                Ok(()) => return Ok(()),
+                // The worker is gone, start a new one below.
+                Err(mpsc::error::SendError(returned)) => pending = returned,
+            }
+        }
+        let (queue, receiver) = mpsc::unbounded_channel();
+        // A fresh receiver is alive, sending cannot fail.
+        let _ = queue.send(pending);
+        queues.insert(key, queue);
+        tokio::spawn(run_queue(
+            self.client.clone(),
+            self.settings.clone(),
+            receiver,
+        ));
+        Ok(())
+    }
+}
+
+// Rows of the batch dimension, `None` when the request cannot be merged with others.
+fn batch_rows(request: &pb::ModelInferRequest) -> Option<usize> {
+    if !request.raw_input_contents.is_empty() {
+        return None;
+    }
+    let mut rows = None;
+    for input in &request.inputs {
+        let size = *input.shape.first()?;
+        if input.contents.is_none() || size < 1 || rows.is_some_and(|rows| rows != size) {
+            return None;
+        }
+        rows = Some(size);
+    }
+    rows.map(|rows| rows as usize)
+}
+
+// Requests can share a batch when they only differ by their batch dimension and data.
+fn compatible(a: &pb::ModelInferRequest, b: &pb::ModelInferRequest) -> bool {
+    a.outputs == b.outputs
+        && a.parameters == b.parameters
+        && a.inputs.len() == b.inputs.len()
+        && a.inputs.iter().all(|input| {
+            b.inputs.iter().any(|other| {
+                other.name == input.name
+                    && other.datatype == input.datatype
+                    && other.shape[1..] == input.shape[1..]
+                    && other.parameters == input.parameters
+            })
+        })
+}
+
+async fn max_rows(
+    client: &InferenceServerClient,
+    request: &pb::ModelInferRequest,
+    settings: &Settings,
+) -> Result<usize> {
+    let version = Some(request.model_version.as_str()).filter(|v| !v.is_empty());
+    let config = client
+        .cached_model_config(&request.model_name, version)
+        .await?;
+    let model_max = config.max_batch_size().max(0) as usize;
+    Ok(match settings.max_batch_size {
+        Some(max) => max.min(model_max),
+        None => model_max,
+    })
+}
+
+async fn run_queue(
+    client: InferenceServerClient,
+    settings: Settings,
+    mut queue: mpsc::UnboundedReceiver<Pending>,
+) {
+    let mut held = None;
+    loop {
+        let first = match held.take() {
+            Some(first) => first,
+            None => match queue.recv().await {
+                Some(first) => first,
+                None => return,
+            },
+        };
+        let limit = match max_rows(&client, &first.request, &settings).await {
+            Ok(limit) => limit,
+            Err(err) => {
+                let _ = first.respond.send(Err(err));
+                continue;
+            }
+        };
+        let target = settings
+            .preferred_batch_sizes
+            .iter()
+            .copied()
+            .filter(|size| *size <= limit)
+            .max()
+            .unwrap_or(limit);
+
+        let deadline = Instant::now() + settings.max_queue_delay;
+        let mut rows = first.rows;
+        let mut batch = vec![first];
+        while rows < target {
+            match tokio::time::timeout_at(deadline, queue.recv()).await {
+                Ok(Some(pending)) if rows + pending.rows > limit => {
+                    held = Some(pending);
+                    break;
+                }
+                Ok(Some(pending)) => {
+                    rows += pending.rows;
+                    batch.push(pending);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let mut groups: Vec<Vec<Pending>> = vec![];
+        for pending in batch {
+            match groups
+                .iter_mut()
+                .find(|group| compatible(&group[0].request, &pending.request))
+            {
+                Some(group) => group.push(pending),
+                None => groups.push(vec![pending]),
+            }
+        }
+        for group in groups {
+            tokio::spawn(send_group(client.clone(), group));
+        }
+    }
+}
+
+async fn send_group(client: InferenceServerClient, mut group: Vec<Pending>) {
+    if group.len() == 1 {
+        let pending = group.remove(0);
+        let _ = pending.respond.send(client.infer(pending.request).await);
+        return;
+    }
+
+    let rows = group.iter().map(|pending| pending.rows).collect::<Vec<_>>();
+    let mut request = group[0].request.clone();
+    request.id = String::new();
+    let inputs = group
+        .iter_mut()
+        .map(|pending| {
+            std::mem::take(&mut pending.request.inputs)
+                .into_iter()
+                .map(InferInput::from)
+                .collect()
+        })
+        .collect();
+    let result = match concat_inputs(inputs) {
+        Ok(inputs) => {
+            request.inputs = inputs.into_iter().map(InferInput::build).collect();
+            match client.infer(request).await {
+                Ok(output) => output.split_batch(&rows),
+                Err(err) => Err(err),
+            }
+        }
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(outputs) => {
+            for (pending, output) in group.into_iter().zip(outputs) {
+                let _ = pending.respond.send(Ok(output));
+            }
+        }
+        Err(err) => {
+            for pending in group {
+                let _ = pending.respond.send(Err(err.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::Result;
+use crate::grpc::model::{ModelConfigView, ModelMetadataView};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+// model name, version ("" for the server default)
+type ModelKey = (String, String);
+
+struct Entry<T> {
+    value: Arc<T>,
+    fetched_at: Instant,
+}
+
+struct Entries<T>(RwLock<HashMap<ModelKey, Entry<T>>>);
+
+impl<T> Default for Entries<T> {
+    fn default() -> Self {
+        Self(RwLock::default())
+    }
+}
+
+impl<T> Entries<T> {
+    fn get(&self, key: &ModelKey, ttl: Duration) -> Result<Option<Arc<T>>> {
+        let entries = self.0.read()?;
+        Ok(entries
+            .get(key)
+            .filter(|entry| entry.fetched_at.elapsed() < ttl)
+            .map(|entry| entry.value.clone()))
+    }
+
+    fn insert(&self, key: ModelKey, value: T) -> Result<Arc<T>> {
+        let value = Arc::new(value);
+        let mut entries = self.0.write()?;
+        entries.insert(
+            key,
+            Entry {
+                value: value.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+        Ok(value)
+    }
+
+    fn remove_model(&self, model_name: &str) -> Result<()> {
+        let mut entries = self.0.write()?;
+        entries.retain(|(name, _), _| name != model_name);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.0.write()?.clear();
+        Ok(())
+    }
+}
+
+/// Model configs and metadata fetched by the client, shared by its clones.
+#[derive(Default)]
+pub(crate) struct ModelCache {
+    configs: Entries<ModelConfigView>,
+    metadata: Entries<ModelMetadataView>,
+}
+
+fn key(model_name: &str, version: Option<&str>) -> ModelKey {
+    (model_name.to_string(), version.unwrap_or("").to_string())
+}
+
+impl ModelCache {
+    pub(crate) fn config(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+        ttl: Duration,
+    ) -> Result<Option<Arc<ModelConfigView>>> {
+        self.configs.get(&key(model_name, version), ttl)
+    }
+
+    pub(crate) fn insert_config(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+        config: ModelConfigView,
+    ) -> Result<Arc<ModelConfigView>> {
+        self.configs.insert(key(model_name, version), config)
+    }
+
+    pub(crate) fn metadata(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+        ttl: Duration,
+    ) -> Result<Option<Arc<ModelMetadataView>>> {
+        self.metadata.get(&key(model_name, version), ttl)
+    }
+
+    pub(crate) fn insert_metadata(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+        metadata: ModelMetadataView,
+    ) -> Result<Arc<ModelMetadataView>> {
+        self.metadata.insert(key(model_name, version), metadata)
+    }
+
+    /// Drop every version of the model.
+    pub(crate) fn invalidate(&self, model_name: &str) -> Result<()> {
+        self.configs.remove_model(model_name)?;
+        self.metadata.remove_model(model_name)
+    }
+
+    pub(crate) fn clear(&self) -> Result<()> {
+        self.configs.clear()?;
+        self.metadata.clear()
+    }
+}
@@ -1,3 +1,7 @@
+#[cfg(feature = "metrics")]
+use super::instrument;
+#[cfg(feature = "tracing")]
+use super::telemetry;
 use super::InferenceServerClientConfig;
 use super::{Error, Result};
 use std::future::Future;
@@ -6,8 +10,17 @@ use std::time::Duration;
 use tonic::transport::{Channel, ClientTlsConfig, Uri};
 use tonic::{Code, Status};
 
+// Statuses of a broken channel or an unreachable server, including a refused connection.
+pub(crate) fn is_transport_failure(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Internal | Code::Unavailable | Code::Cancelled | Code::Unknown
+    )
+}
+
 pub struct ChannelPool {
     channel: RwLock<Option<Channel>>,
+    preset: Option<Channel>,
     uri: Uri,
     tls: bool,
     timeout: Duration,
@@ -27,6 +40,7 @@ impl ChannelPool {
     ) -> Self {
         Self {
             channel: RwLock::new(None),
+            preset: None,
             uri,
             tls,
             timeout,
@@ -36,7 +50,23 @@ impl ChannelPool {
         }
     }
 
+    /// Use an already built channel (e.g. an in-memory transport) instead of dialing `uri`.
+    pub fn preset_channel(mut self, channel: Channel) -> Self {
+        self.preset = Some(channel);
+        self
+    }
+
+    pub(crate) fn preset(&self) -> Option<&Channel> {
+        self.preset.as_ref()
+    }
+
     async fn make_channel(&self) -> Result<Channel> {
+        if let Some(channel) = &self.preset {
+            let mut self_channel = self.channel.write()?;
+            *self_channel = Some(channel.clone());
+            return Ok(channel.clone());
+        }
+
         let mut tls = self.tls;
         let mut uri = self.uri.clone();
         match uri.scheme_str() {
@@ -53,7 +83,7 @@ impl ChannelPool {
                 }
             }
             None => {
-                uri = format!("http://{}", uri.to_string()).parse().unwrap();
+                uri = format!("http://{}", uri).parse().unwrap();
             }
         };
         let endpoint = Channel::builder(uri)
@@ -96,9 +126,12 @@ impl ChannelPool {
         Ok(())
     }
 
-    // Allow to retry request if channel is broken
+    // Allow to retry request if channel is broken, `method` only labels the client metrics.
+    // Reconnects and retries are also events of the current span.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
     pub async fn with_channel<T, O: Future<Output = Result<T>>>(
         &self,
+        method: &'static str,
         f: impl Fn(Channel) -> O,
         allow_retry: bool,
     ) -> Result<T> {
@@ -110,17 +143,25 @@ impl ChannelPool {
         match result {
             Ok(res) => Ok(res),
             Err(err) => match err {
-                Error::ResponseError { ref status } => match status.code() {
-                    Code::Internal | Code::Unavailable | Code::Cancelled | Code::Unknown => {
+                Error::ResponseError { ref status } => match is_transport_failure(status) {
+                    true => {
                         self.drop_channel().await?;
+                        #[cfg(feature = "metrics")]
+                        instrument::record_reconnect(method, status);
+                        #[cfg(feature = "tracing")]
+                        telemetry::record_reconnect(status);
                         if allow_retry {
+                            #[cfg(feature = "metrics")]
+                            instrument::record_retry(method);
+                            #[cfg(feature = "tracing")]
+                            telemetry::record_retry();
                             let channel = self.get_channel().await?;
                             Ok(f(channel).await?)
                         } else {
                             Err(err)
                         }
                     }
-                    _ => Err(err),
+                    false => Err(err),
                 },
                 _ => Err(err),
             },
@@ -25,6 +25,12 @@ pub struct InferenceServerClientConfig {
 
     /// Optional compression schema to use for API requests
     pub compression: Option<CompressionEncoding>,
+
+    /// Check `infer` requests against the model config before sending them
+    pub validate_requests: bool,
+
+    /// How long cached model configs and metadata are reused, zero disables the cache
+    pub model_cache_ttl: Duration,
 }
 
 impl InferenceServerClientConfig {
@@ -65,6 +71,16 @@ impl InferenceServerClientConfig {
         self
     }
 
+    pub fn validate_requests(mut self, validate_requests: bool) -> Self {
+        self.validate_requests = validate_requests;
+        self
+    }
+
+    pub fn model_cache_ttl<T: AsTimeout>(mut self, model_cache_ttl: T) -> Self {
+        self.model_cache_ttl = model_cache_ttl.timeout();
+        self
+    }
+
     pub fn set_timeout<T: AsTimeout>(&mut self, timeout: T) {
         self.timeout = AsTimeout::timeout(timeout);
     }
@@ -88,6 +104,14 @@ impl InferenceServerClientConfig {
     pub fn set_compression(&mut self, compression: Option<CompressionEncoding>) {
         self.compression = compression;
     }
+
+    pub fn set_validate_requests(&mut self, validate_requests: bool) {
+        self.validate_requests = validate_requests;
+    }
+
+    pub fn set_model_cache_ttl<T: AsTimeout>(&mut self, model_cache_ttl: T) {
+        self.model_cache_ttl = model_cache_ttl.timeout();
+    }
 }
 
 impl Default for InferenceServerClientConfig {
@@ -100,6 +124,8 @@ impl Default for InferenceServerClientConfig {
             keep_alive_while_idle: true,
             keep_alive_timeout: Duration::from_secs(20),
             compression: None,
+            validate_requests: false,
+            model_cache_ttl: Duration::from_secs(60),
         }
     }
 }
@@ -1,9 +1,10 @@
+use crate::grpc::validation::ValidationProblem;
 use thiserror::Error as ThisError;
 
-#[derive(ThisError, Debug)]
+#[derive(ThisError, Debug, Clone)]
 pub enum Error {
     #[error("Error in the response: {}", .status.message())]
-    ResponseError { status: tonic::Status },
+    ResponseError { status: Box<tonic::Status> },
 
     #[error("Invalid Uri: {}", .0)]
     InvalidUri(String),
@@ -13,11 +14,75 @@ pub enum Error {
 
     #[error("Error in conversion: {}", .0)]
     ConversionError(String),
+
+    #[error("IO error: {}", .0)]
+    IoError(String),
+
+    #[error("No recorded exchange for request: {}", .0)]
+    ReplayMismatch(String),
+
+    #[error("No server serves model `{}` (version `{}`)", .model, .version)]
+    ModelNotFound { model: String, version: String },
+
+    #[error("Invalid request: {}", .0.iter().map(|p| p.to_string()).collect::<Vec<_>>().join("; "))]
+    InvalidRequest(Vec<ValidationProblem>),
+
+    #[error("Invalid batch: {}", .0)]
+    InvalidBatch(String),
+
+    #[error("Model `{}` did not become {} in time: {}", .model, .state, .reason)]
+    ModelStateTimeout {
+        model: String,
+        state: String,
+        reason: String,
+    },
+
+    #[error("Server was not ready in time: {}", .reason)]
+    ServerNotReady { reason: String },
+
+    #[error("Models were not ready in time: {}", model_list(.models))]
+    ModelsNotReady {
+        /// `(name, version, reason)`, the version is empty when any version would do and the
+        /// reason is empty when the server answered that the model is not ready
+        models: Vec<(String, String, String)>,
+    },
+
+    #[error("Model `{}` was skipped, its dependency `{}` failed", .model, .dependency)]
+    DependencyFailed { model: String, dependency: String },
+
+    #[error("HTTP error: {}", .0)]
+    HttpError(String),
+
+    #[error("Parse error at line {}, column {}: {}", .line, .column, .message)]
+    ParseError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+fn model_list(models: &[(String, String, String)]) -> String {
+    models
+        .iter()
+        .map(|(name, version, reason)| {
+            let model = match version.as_str() {
+                "" => format!("`{name}`"),
+                version => format!("`{name}` version {version}"),
+            };
+            match reason.as_str() {
+                "" => model,
+                reason => format!("{model} ({reason})"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl From<tonic::Status> for Error {
     fn from(status: tonic::Status) -> Self {
-        Self::ResponseError { status }
+        Self::ResponseError {
+            status: Box::new(status),
+        }
     }
 }
 
@@ -33,4 +98,10 @@ impl From<ndarray::ShapeError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(error.to_string())
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,53 @@
+use super::rpc::RpcRequest;
+use super::{Error, Result};
+use metrics::{counter, histogram};
+use std::time::Duration;
+use tonic::Status;
+
+/// Histogram of RPC latencies in seconds, labels `method` and `model`.
+pub const REQUEST_DURATION: &str = "triton_client_request_duration_seconds";
+/// Counter of encoded request bytes, labels `method` and `model`.
+pub const REQUEST_BYTES: &str = "triton_client_request_bytes_total";
+/// Counter of encoded response bytes, labels `method` and `model`.
+pub const RESPONSE_BYTES: &str = "triton_client_response_bytes_total";
+/// Counter of failed RPCs, labels `method`, `model` and `code`, the gRPC status code or
+/// `client` when the request never got a status.
+pub const ERRORS: &str = "triton_client_errors_total";
+/// Counter of requests sent again on a new channel, label `method`.
+pub const RETRIES: &str = "triton_client_retries_total";
+/// Counter of channels dropped after a transport failure, labels `method` and `code`.
+pub const RECONNECTS: &str = "triton_client_reconnects_total";
+
+fn code(error: &Error) -> String {
+    match error {
+        Error::ResponseError { status } => format!("{:?}", status.code()),
+        _ => "client".to_string(),
+    }
+}
+
+pub(crate) fn record_call<R: RpcRequest, T: prost::Message>(
+    method: &'static str,
+    request: &R,
+    elapsed: Duration,
+    result: &Result<T>,
+) {
+    let model = request.model_name().to_string();
+    histogram!(REQUEST_DURATION, "method" => method, "model" => model.clone())
+        .record(elapsed.as_secs_f64());
+    counter!(REQUEST_BYTES, "method" => method, "model" => model.clone())
+        .increment(request.encoded_len() as u64);
+    match result {
+        Ok(response) => counter!(RESPONSE_BYTES, "method" => method, "model" => model)
+            .increment(response.encoded_len() as u64),
+        Err(error) => counter!(ERRORS, "method" => method, "model" => model, "code" => code(error))
+            .increment(1),
+    }
+}
+
+pub(crate) fn record_retry(method: &'static str) {
+    counter!(RETRIES, "method" => method).increment(1);
+}
+
+pub(crate) fn record_reconnect(method: &'static str, status: &Status) {
+    counter!(RECONNECTS, "method" => method, "code" => format!("{:?}", status.code())).increment(1);
+}
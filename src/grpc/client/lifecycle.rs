@@ -0,0 +1,204 @@
+use super::channel::is_transport_failure;
+use super::{Error, InferenceServerClient, Result, WaitOptions};
+use crate::grpc::pb::model_repository_parameter::ParameterChoice;
+use crate::grpc::pb::repository_index_response::ModelIndex;
+use crate::grpc::pb::{self, ModelRepositoryParameter};
+use crate::grpc::repository::ModelLoadRequest;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use tokio::time::Instant;
+use tonic::Code;
+
+/// Model version state as reported by `repository_index`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModelState {
+    Unknown,
+    Ready,
+    Unavailable,
+    Loading,
+    Unloading,
+}
+
+impl From<&str> for ModelState {
+    fn from(state: &str) -> Self {
+        match state {
+            "READY" => Self::Ready,
+            "UNAVAILABLE" => Self::Unavailable,
+            "LOADING" => Self::Loading,
+            "UNLOADING" => Self::Unloading,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Display for ModelState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Ready => "READY",
+            Self::Unavailable => "UNAVAILABLE",
+            Self::Loading => "LOADING",
+            Self::Unloading => "UNLOADING",
+        };
+        write!(f, "{state}")
+    }
+}
+
+// Whether the index entries of the model show it settled in `target`.
+// Without explicit versions, loading needs one ready version and unloading needs none.
+fn reached(entries: &[ModelIndex], versions: &[&str], target: ModelState) -> bool {
+    let state = |entry: &ModelIndex| ModelState::from(entry.state.as_str());
+    if !versions.is_empty() {
+        return versions.iter().all(|version| {
+            let entry = entries.iter().find(|entry| entry.version == *version);
+            match target {
+                ModelState::Unavailable => entry.map_or(true, |e| state(e) == target),
+                _ => entry.is_some_and(|e| state(e) == target),
+            }
+        });
+    }
+    let settled = entries
+        .iter()
+        .all(|e| !matches!(state(e), ModelState::Loading | ModelState::Unloading));
+    match target {
+        ModelState::Ready => settled && entries.iter().any(|e| state(e) == ModelState::Ready),
+        _ => entries.iter().all(|e| state(e) == target),
+    }
+}
+
+// Why the entries of the model are not in `target`, for the timeout error.
+fn not_reached_reason(entries: &[ModelIndex], versions: &[&str]) -> String {
+    let mut reasons = entries
+        .iter()
+        .filter(|entry| versions.is_empty() || versions.contains(&&*entry.version))
+        .map(|entry| match entry.reason.as_str() {
+            "" => format!("version {} is {}", entry.version, entry.state),
+            reason => format!("version {} is {}: {reason}", entry.version, entry.state),
+        })
+        .collect::<Vec<_>>();
+    reasons.extend(
+        versions
+            .iter()
+            .filter(|version| !entries.iter().any(|entry| entry.version == **version))
+            .map(|version| format!("version {version} is not in the repository")),
+    );
+    if entries.is_empty() && versions.is_empty() {
+        reasons.push("not in the repository".to_string());
+    }
+    reasons.join("; ")
+}
+
+// Errors that can clear up by themselves, e.g. while the server restarts.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::ResponseError { status } => {
+            is_transport_failure(status) || status.code() == Code::Unavailable
+        }
+        _ => false,
+    }
+}
+
+impl InferenceServerClient {
+    /// Poll `repository_index` with backoff until the model (or the given versions) is in `target`.
+    /// Transport failures and `UNAVAILABLE` are retried, on timeout the error carries the last
+    /// reason reported by the server or the last of these errors.
+    pub async fn wait_for_model_state(
+        &self,
+        repository_name: &str,
+        model_name: &str,
+        versions: &[&str],
+        target: ModelState,
+        wait: &WaitOptions,
+    ) -> Result<Vec<ModelIndex>> {
+        let deadline = Instant::now() + wait.timeout;
+        let mut reason = String::new();
+        let mut attempt = 0;
+        loop {
+            let index = self.repository_index(repository_name, false);
+            match tokio::time::timeout_at(deadline, index).await {
+                Ok(Ok(index)) => {
+                    let entries = index
+                        .models
+                        .into_iter()
+                        .filter(|entry| entry.name == model_name)
+                        .collect::<Vec<_>>();
+                    if reached(&entries, versions, target) {
+                        return Ok(entries);
+                    }
+                    reason = not_reached_reason(&entries, versions);
+                }
+                Ok(Err(e)) if is_transient(&e) => reason = e.to_string(),
+                Ok(Err(e)) => return Err(e),
+                // Cut off by the deadline, says nothing new about the model
+                Err(_) if reason.is_empty() => {
+                    reason = "repository_index did not answer in time".to_string()
+                }
+                Err(_) => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::ModelStateTimeout {
+                    model: model_name.to_string(),
+                    state: target.to_string(),
+                    reason,
+                });
+            }
+            let delay = wait.backoff.delay(attempt).min(deadline - now);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Load the model then wait until it, or the given versions, is READY.
+    pub async fn load_model_and_wait(
+        &self,
+        request: ModelLoadRequest,
+        versions: &[&str],
+        wait: &WaitOptions,
+    ) -> Result<Vec<ModelIndex>> {
+        let request = pb::RepositoryModelLoadRequest::try_from(request)?;
+        self.repository_model_load(
+            &request.repository_name,
+            &request.model_name,
+            Some(&request.parameters),
+        )
+        .await?;
+        self.wait_for_model_state(
+            &request.repository_name,
+            &request.model_name,
+            versions,
+            ModelState::Ready,
+            wait,
+        )
+        .await
+    }
+
+    /// Unload the model, and with `unload_dependents` the models it depends on, then wait until
+    /// it, or the given versions, is UNAVAILABLE.
+    pub async fn unload_model_and_wait(
+        &self,
+        repository_name: &str,
+        model_name: &str,
+        unload_dependents: bool,
+        versions: &[&str],
+        wait: &WaitOptions,
+    ) -> Result<Vec<ModelIndex>> {
+        let parameters = HashMap::from([(
+            "unload_dependents".to_string(),
+            ModelRepositoryParameter {
+                parameter_choice: Some(ParameterChoice::BoolParam(unload_dependents)),
+            },
+        )]);
+        self.repository_model_unload(repository_name, model_name, Some(&parameters))
+            .await?;
+        self.wait_for_model_state(
+            repository_name,
+            model_name,
+            versions,
+            ModelState::Unavailable,
+            wait,
+        )
+        .await
+    }
+}
@@ -1,20 +1,90 @@
+mod backoff;
+pub use backoff::*;
+mod batcher;
+pub use batcher::*;
+mod cache;
 pub(crate) mod channel;
 mod config;
 pub use config::*;
 mod error;
 pub use error::*;
+#[cfg(feature = "metrics")]
+mod instrument;
+#[cfg(feature = "metrics")]
+pub use instrument::*;
+mod lifecycle;
+pub use lifecycle::*;
+mod multi;
+pub use multi::*;
+mod readiness;
+mod reconciler;
+pub use reconciler::*;
+mod rpc;
+#[cfg(feature = "tracing")]
+mod telemetry;
+mod warmup;
+pub use warmup::*;
 
+use crate::grpc::batch::batch_samples;
+use crate::grpc::health::{HealthWatch, ServingStatus};
+use crate::grpc::input::{InferInput, ModelInput};
+use crate::grpc::model::{ModelConfigView, ModelMetadataView};
 use crate::grpc::output::ModelOutput;
 use crate::grpc::pb::{self, GrpcInferenceServiceClient, HealthClient};
+use crate::grpc::record::{RecordedExchange, Recorder};
+use crate::grpc::repository::ModelLoadRequest;
+use crate::grpc::settings::{LogSettings, LogSettingsUpdate, TraceSettings, TraceSettingsUpdate};
+use crate::grpc::statistics::StatisticsSnapshot;
+use crate::grpc::validation::validate_request;
 use crate::types::Bytes;
+use cache::ModelCache;
 use channel::ChannelPool;
+use rpc::RpcRequest;
 use std::collections::HashMap;
 use std::future::Future;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 use tonic::transport::Channel;
+use tonic::Code;
+
+// Record the client metrics and span of one RPC around `call`.
+#[cfg_attr(
+    not(any(feature = "metrics", feature = "tracing")),
+    allow(unused_variables)
+)]
+async fn observe<Req: RpcRequest, Resp: prost::Message>(
+    method: &'static str,
+    request: &Req,
+    call: impl Future<Output = Result<Resp>>,
+) -> Result<Resp> {
+    #[cfg(feature = "metrics")]
+    let start = Instant::now();
+    #[cfg(feature = "tracing")]
+    let span = telemetry::rpc_span(method, request);
+    #[cfg(feature = "tracing")]
+    let call = tracing::Instrument::instrument(call, span.clone());
+    let result = call.await;
+    #[cfg(feature = "tracing")]
+    telemetry::record_result(&span, &result);
+    #[cfg(feature = "metrics")]
+    instrument::record_call(method, request, start.elapsed(), &result);
+    result
+}
+
+// `request` carrying the trace context of the current span.
+fn traced_request<Req>(request: Req) -> tonic::Request<Req> {
+    #[allow(unused_mut)]
+    let mut request = tonic::Request::new(request);
+    #[cfg(feature = "opentelemetry")]
+    telemetry::inject_trace_context(request.metadata_mut());
+    request
+}
 
 pub struct InferenceServerClient {
     pub config: InferenceServerClientConfig,
     channel: ChannelPool,
+    recorder: Option<Arc<Recorder>>,
+    cache: Arc<ModelCache>,
 }
 
 impl InferenceServerClient {
@@ -22,87 +92,246 @@ impl InferenceServerClient {
         Self {
             channel: ChannelPool::from(config.clone()),
             config,
+            recorder: None,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Build a client on top of an existing channel, `config.uri` is then only informative.
+    pub fn from_channel(config: InferenceServerClientConfig, channel: Channel) -> Self {
+        Self {
+            channel: ChannelPool::from(config.clone()).preset_channel(channel),
+            config,
+            recorder: None,
+            cache: Arc::default(),
         }
     }
 
-    async fn with_root_client<T, O: Future<Output = Result<T>>>(
+    /// Record every `infer` request and its outcome, clones of the client share the recorder.
+    pub fn set_recorder(&mut self, recorder: Option<Arc<Recorder>>) {
+        self.recorder = recorder;
+    }
+
+    pub fn recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.set_recorder(Some(recorder));
+        self
+    }
+
+    // One unary RPC of the inference service, retried once on a fresh channel after a transport
+    // failure.
+    async fn with_root_client<Req, Resp, O>(
         &self,
-        f: impl Fn(GrpcInferenceServiceClient<Channel>) -> O,
-    ) -> Result<T> {
-        let result = self
-            .channel
+        method: &'static str,
+        request: Req,
+        f: impl Fn(GrpcInferenceServiceClient<Channel>, tonic::Request<Req>) -> O,
+    ) -> Result<Resp>
+    where
+        Req: RpcRequest,
+        Resp: prost::Message,
+        O: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let call = self.channel.with_channel(
+            method,
+            |channel| {
+                let mut client = GrpcInferenceServiceClient::new(channel)
+                    .max_decoding_message_size(usize::MAX)
+                    .max_encoding_message_size(usize::MAX);
+                if let Some(compression) = self.config.compression {
+                    client = client
+                        .send_compressed(compression.into())
+                        .accept_compressed(compression.into());
+                }
+                let response = f(client, traced_request(request.clone()));
+                async move { Ok(response.await?.into_inner()) }
+            },
+            true,
+        );
+        observe(method, &request, call).await
+    }
+
+    // One RPC of the health service, retried like `with_root_client`. Not observed, as the
+    // response of `Watch` is a stream.
+    async fn with_health_client<Req, Resp, O>(
+        &self,
+        method: &'static str,
+        request: Req,
+        f: impl Fn(HealthClient<Channel>, tonic::Request<Req>) -> O,
+    ) -> Result<Resp>
+    where
+        Req: RpcRequest,
+        O: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        self.channel
             .with_channel(
+                method,
                 |channel| {
-                    let mut client = GrpcInferenceServiceClient::new(channel)
-                        .max_decoding_message_size(usize::MAX)
-                        .max_encoding_message_size(usize::MAX);
-                    if let Some(compression) = self.config.compression {
-                        client = client
-                            .send_compressed(compression.into())
-                            .accept_compressed(compression.into());
-                    }
-                    f(client)
+                    let response = f(HealthClient::new(channel), traced_request(request.clone()));
+                    async move { Ok(response.await?.into_inner()) }
                 },
                 true,
             )
-            .await?;
-        Ok(result)
+            .await
     }
 
+    /// Health of the whole server.
     pub async fn health_check(&self) -> Result<pb::HealthCheckResponse> {
-        let channel = self.channel.get_channel().await?;
-        let mut health_check_client = HealthClient::new(channel);
-        let result = health_check_client
-            .check(pb::HealthCheckRequest {
-                service: String::new(),
+        let request = pb::HealthCheckRequest {
+            service: String::new(),
+        };
+        let call = self.with_health_client(
+            "health_check",
+            request.clone(),
+            |mut client, request| async move { client.check(request).await },
+        );
+        observe("health_check", &request, call).await
+    }
+
+    /// Serving status of `service`, or of the whole server when empty, e.g.
+    /// [`INFERENCE_SERVICE`](crate::grpc::health::INFERENCE_SERVICE). A service the server does
+    /// not know is `ServiceUnknown`.
+    pub async fn check_health(&self, service: &str) -> Result<ServingStatus> {
+        let request = pb::HealthCheckRequest {
+            service: service.to_string(),
+        };
+        let call = self.with_health_client(
+            "health_check",
+            request.clone(),
+            |mut client, request| async move { client.check(request).await },
+        );
+        match observe("health_check", &request, call).await {
+            Ok(response) => Ok(ServingStatus::from(&response)),
+            Err(Error::ResponseError { status }) if status.code() == Code::NotFound => {
+                Ok(ServingStatus::ServiceUnknown)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Follow the serving status of `service`, or of the whole server when empty.
+    pub async fn watch_health(&self, service: &str) -> Result<HealthWatch> {
+        let request = pb::HealthCheckRequest {
+            service: service.to_string(),
+        };
+        let stream = self
+            .with_health_client("health_watch", request, |mut client, request| async move {
+                client.watch(request).await
             })
             .await?;
-        Ok(result.into_inner())
+        Ok(HealthWatch::new(stream))
+    }
+
+    /// Check the request against the (cached) model config without sending it.
+    pub async fn validate(&self, request: &pb::ModelInferRequest) -> Result<()> {
+        let version = Some(request.model_version.as_str()).filter(|v| !v.is_empty());
+        let config = self
+            .cached_model_config(&request.model_name, version)
+            .await?;
+        validate_request(request, &config).map_err(Error::InvalidRequest)
     }
 
     pub async fn infer(&self, request: impl Into<pb::ModelInferRequest>) -> Result<ModelOutput> {
-        let request = &request.into();
-        self.with_root_client(|mut client| async move {
-            let result = client.model_infer(request.clone()).await?;
-            ModelOutput::new(result.into_inner())
-        })
-        .await
+        let request = request.into();
+        if self.config.validate_requests {
+            self.validate(&request).await?;
+        }
+        // Only a recording needs the request back, the payload is not copied otherwise.
+        let recorded = self.recorder.as_ref().map(|_| request.clone());
+        let sent_at = SystemTime::now();
+        let start = Instant::now();
+        let result = self
+            .with_root_client("model_infer", request, |mut client, request| async move {
+                client.model_infer(request).await
+            })
+            .await;
+        if let (Some(recorder), Some(request)) = (&self.recorder, recorded) {
+            let outcome = match &result {
+                Ok(response) => Some(Ok(response)),
+                Err(Error::ResponseError { status }) => Some(Err(status.as_ref())),
+                Err(_) => None,
+            };
+            if let Some(outcome) = outcome {
+                let exchange = RecordedExchange::new(request, outcome, sent_at, start.elapsed());
+                // A broken recording must not fail the inference itself, the failure is kept
+                // by `Recorder::error` (and logged with the `tracing` feature).
+                let _ = recorder.record(&exchange);
+            }
+        }
+        ModelOutput::new(result?)
+    }
+
+    /// Run single-sample inputs as few batched requests as the model allows, one output per sample.
+    /// `template` gives the model, version, requested outputs and parameters of every request.
+    pub async fn infer_samples(
+        &self,
+        template: ModelInput,
+        samples: Vec<Vec<InferInput>>,
+    ) -> Result<Vec<ModelOutput>> {
+        let request = pb::ModelInferRequest::from(template.clone());
+        let version = Some(request.model_version.as_str()).filter(|v| !v.is_empty());
+        let config = self
+            .cached_model_config(&request.model_name, version)
+            .await?;
+        let mut outputs = Vec::with_capacity(samples.len());
+        for batch in batch_samples(&template, samples, config.max_batch_size())? {
+            let output = self.infer(batch.input).await?;
+            if config.supports_batching() {
+                let samples = output.unstack()?;
+                if samples.len() != batch.batch_size {
+                    return Err(Error::InvalidBatch(format!(
+                        "a batch of {} samples returned {} outputs",
+                        batch.batch_size,
+                        samples.len()
+                    )));
+                }
+                outputs.extend(samples);
+            } else {
+                outputs.push(output);
+            }
+        }
+        Ok(outputs)
     }
 
     pub async fn is_server_ready(&self) -> Result<bool> {
-        self.with_root_client(|mut client| async move {
-            let result = client.server_ready(pb::ServerReadyRequest {}).await?;
-            Ok(result.into_inner().ready)
-        })
-        .await
+        let response = self
+            .with_root_client(
+                "server_ready",
+                pb::ServerReadyRequest {},
+                |mut client, request| async move { client.server_ready(request).await },
+            )
+            .await?;
+        Ok(response.ready)
     }
 
     pub async fn is_server_live(&self) -> Result<bool> {
-        self.with_root_client(|mut client| async move {
-            let result = client.server_live(pb::ServerLiveRequest {}).await?;
-            Ok(result.into_inner().live)
-        })
-        .await
+        let response = self
+            .with_root_client(
+                "server_live",
+                pb::ServerLiveRequest {},
+                |mut client, request| async move { client.server_live(request).await },
+            )
+            .await?;
+        Ok(response.live)
     }
 
     pub async fn is_model_ready(&self, model_name: &str, version: Option<&str>) -> Result<bool> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .model_ready(pb::ModelReadyRequest {
-                    name: model_name.to_string(),
-                    version: version.unwrap_or("").to_string(),
-                })
-                .await?;
-            Ok(result.into_inner().ready)
-        })
-        .await
+        let request = pb::ModelReadyRequest {
+            name: model_name.to_string(),
+            version: version.unwrap_or("").to_string(),
+        };
+        let response = self
+            .with_root_client("model_ready", request, |mut client, request| async move {
+                client.model_ready(request).await
+            })
+            .await?;
+        Ok(response.ready)
     }
 
     pub async fn server_metadata(&self) -> Result<pb::ServerMetadataResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client.server_metadata(pb::ServerMetadataRequest {}).await?;
-            Ok(result.into_inner())
-        })
+        self.with_root_client(
+            "server_metadata",
+            pb::ServerMetadataRequest {},
+            |mut client, request| async move { client.server_metadata(request).await },
+        )
         .await
     }
 
@@ -111,15 +340,15 @@ impl InferenceServerClient {
         model_name: &str,
         version: Option<&str>,
     ) -> Result<pb::ModelMetadataResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .model_metadata(pb::ModelMetadataRequest {
-                    name: model_name.to_string(),
-                    version: version.unwrap_or("").to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
-        })
+        let request = pb::ModelMetadataRequest {
+            name: model_name.to_string(),
+            version: version.unwrap_or("").to_string(),
+        };
+        self.with_root_client(
+            "model_metadata",
+            request,
+            |mut client, request| async move { client.model_metadata(request).await },
+        )
         .await
     }
 
@@ -128,49 +357,131 @@ impl InferenceServerClient {
         model_name: &str,
         version: Option<&str>,
     ) -> Result<pb::ModelConfigResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .model_config(pb::ModelConfigRequest {
-                    name: model_name.to_string(),
-                    version: version.unwrap_or("").to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
+        let request = pb::ModelConfigRequest {
+            name: model_name.to_string(),
+            version: version.unwrap_or("").to_string(),
+        };
+        self.with_root_client("model_config", request, |mut client, request| async move {
+            client.model_config(request).await
         })
         .await
     }
 
+    pub async fn model_metadata_view(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<ModelMetadataView> {
+        self.model_metadata(model_name, version).await?.try_into()
+    }
+
+    pub async fn model_config_view(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<ModelConfigView> {
+        self.model_config(model_name, version).await?.try_into()
+    }
+
+    /// Model config from the cache, fetched when missing or older than `config.model_cache_ttl`.
+    pub async fn cached_model_config(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Arc<ModelConfigView>> {
+        match self
+            .cache
+            .config(model_name, version, self.config.model_cache_ttl)?
+        {
+            Some(config) => Ok(config),
+            None => self.refresh_model_config(model_name, version).await,
+        }
+    }
+
+    /// Model metadata from the cache, fetched when missing or older than `config.model_cache_ttl`.
+    pub async fn cached_model_metadata(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Arc<ModelMetadataView>> {
+        match self
+            .cache
+            .metadata(model_name, version, self.config.model_cache_ttl)?
+        {
+            Some(metadata) => Ok(metadata),
+            None => self.refresh_model_metadata(model_name, version).await,
+        }
+    }
+
+    /// Fetch the model config and replace the cached one.
+    pub async fn refresh_model_config(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Arc<ModelConfigView>> {
+        let config = self.model_config_view(model_name, version).await?;
+        self.cache.insert_config(model_name, version, config)
+    }
+
+    /// Fetch the model metadata and replace the cached one.
+    pub async fn refresh_model_metadata(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Arc<ModelMetadataView>> {
+        let metadata = self.model_metadata_view(model_name, version).await?;
+        self.cache.insert_metadata(model_name, version, metadata)
+    }
+
+    /// Drop the cached config and metadata of every version of the model.
+    pub fn invalidate_model_cache(&self, model_name: &str) -> Result<()> {
+        self.cache.invalidate(model_name)
+    }
+
+    pub fn clear_model_cache(&self) -> Result<()> {
+        self.cache.clear()
+    }
+
     pub async fn model_statistics(
         &self,
         model_name: &str,
         version: Option<&str>,
     ) -> Result<pb::ModelStatisticsResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .model_statistics(pb::ModelStatisticsRequest {
-                    name: model_name.to_string(),
-                    version: version.unwrap_or("").to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
-        })
+        let request = pb::ModelStatisticsRequest {
+            name: model_name.to_string(),
+            version: version.unwrap_or("").to_string(),
+        };
+        self.with_root_client(
+            "model_statistics",
+            request,
+            |mut client, request| async move { client.model_statistics(request).await },
+        )
         .await
     }
 
+    /// Typed `model_statistics`, an empty `model_name` covers every model.
+    pub async fn statistics_snapshot(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<StatisticsSnapshot> {
+        Ok(self.model_statistics(model_name, version).await?.into())
+    }
+
     pub async fn repository_index(
         &self,
         repository_name: &str,
         ready: bool,
     ) -> Result<pb::RepositoryIndexResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .repository_index(pb::RepositoryIndexRequest {
-                    repository_name: repository_name.to_string(),
-                    ready,
-                })
-                .await?;
-            Ok(result.into_inner())
-        })
+        let request = pb::RepositoryIndexRequest {
+            repository_name: repository_name.to_string(),
+            ready,
+        };
+        self.with_root_client(
+            "repository_index",
+            request,
+            |mut client, request| async move { client.repository_index(request).await },
+        )
         .await
     }
 
@@ -180,16 +491,31 @@ impl InferenceServerClient {
         model_name: &str,
         parameters: Option<&HashMap<String, pb::ModelRepositoryParameter>>,
     ) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .repository_model_load(pb::RepositoryModelLoadRequest {
-                    repository_name: repository_name.to_string(),
-                    model_name: model_name.to_string(),
-                    parameters: parameters.unwrap_or(&HashMap::new()).clone(),
-                })
-                .await?;
-            Ok(())
-        })
+        let request = pb::RepositoryModelLoadRequest {
+            repository_name: repository_name.to_string(),
+            model_name: model_name.to_string(),
+            parameters: parameters.cloned().unwrap_or_default(),
+        };
+        let result = self
+            .with_root_client(
+                "repository_model_load",
+                request,
+                |mut client, request| async move { client.repository_model_load(request).await },
+            )
+            .await;
+        // The model config may have changed whatever the outcome.
+        self.cache.invalidate(model_name)?;
+        result.map(|_| ())
+    }
+
+    /// `repository_model_load` with the config override and files of `request`.
+    pub async fn load_model(&self, request: ModelLoadRequest) -> Result<()> {
+        let request = pb::RepositoryModelLoadRequest::try_from(request)?;
+        self.repository_model_load(
+            &request.repository_name,
+            &request.model_name,
+            Some(&request.parameters),
+        )
         .await
     }
 
@@ -199,31 +525,35 @@ impl InferenceServerClient {
         model_name: &str,
         parameters: Option<&HashMap<String, pb::ModelRepositoryParameter>>,
     ) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .repository_model_unload(pb::RepositoryModelUnloadRequest {
-                    repository_name: repository_name.to_string(),
-                    model_name: model_name.to_string(),
-                    parameters: parameters.unwrap_or(&HashMap::new()).clone(),
-                })
-                .await?;
-            Ok(())
-        })
-        .await
+        let request = pb::RepositoryModelUnloadRequest {
+            repository_name: repository_name.to_string(),
+            model_name: model_name.to_string(),
+            parameters: parameters.cloned().unwrap_or_default(),
+        };
+        let result = self
+            .with_root_client(
+                "repository_model_unload",
+                request,
+                |mut client, request| async move { client.repository_model_unload(request).await },
+            )
+            .await;
+        // The model config may have changed whatever the outcome.
+        self.cache.invalidate(model_name)?;
+        result.map(|_| ())
     }
 
     pub async fn system_shared_memory_status(
         &self,
         name: &str,
     ) -> Result<pb::SystemSharedMemoryStatusResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .system_shared_memory_status(pb::SystemSharedMemoryStatusRequest {
-                    name: name.to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
-        })
+        let request = pb::SystemSharedMemoryStatusRequest {
+            name: name.to_string(),
+        };
+        self.with_root_client(
+            "system_shared_memory_status",
+            request,
+            |mut client, request| async move { client.system_shared_memory_status(request).await },
+        )
         .await
     }
 
@@ -234,44 +564,46 @@ impl InferenceServerClient {
         offset: u64,
         byte_size: u64,
     ) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .system_shared_memory_register(pb::SystemSharedMemoryRegisterRequest {
-                    name: name.to_string(),
-                    key: key.to_string(),
-                    offset,
-                    byte_size,
-                })
-                .await?;
-            Ok(())
-        })
-        .await
+        let request = pb::SystemSharedMemoryRegisterRequest {
+            name: name.to_string(),
+            key: key.to_string(),
+            offset,
+            byte_size,
+        };
+        self.with_root_client(
+            "system_shared_memory_register",
+            request,
+            |mut client, request| async move { client.system_shared_memory_register(request).await },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn system_shared_memory_unregister(&self, name: &str) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .system_shared_memory_unregister(pb::SystemSharedMemoryUnregisterRequest {
-                    name: name.to_string(),
-                })
-                .await?;
-            Ok(())
-        })
-        .await
+        let request = pb::SystemSharedMemoryUnregisterRequest {
+            name: name.to_string(),
+        };
+        self.with_root_client(
+            "system_shared_memory_unregister",
+            request,
+            |mut client, request| async move { client.system_shared_memory_unregister(request).await },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn cuda_shared_memory_status(
         &self,
         name: &str,
     ) -> Result<pb::CudaSharedMemoryStatusResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .cuda_shared_memory_status(pb::CudaSharedMemoryStatusRequest {
-                    name: name.to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
-        })
+        let request = pb::CudaSharedMemoryStatusRequest {
+            name: name.to_string(),
+        };
+        self.with_root_client(
+            "cuda_shared_memory_status",
+            request,
+            |mut client, request| async move { client.cuda_shared_memory_status(request).await },
+        )
         .await
     }
 
@@ -282,30 +614,32 @@ impl InferenceServerClient {
         device_id: i64,
         byte_size: u64,
     ) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .cuda_shared_memory_register(pb::CudaSharedMemoryRegisterRequest {
-                    name: name.to_string(),
-                    raw_handle: raw_handle.clone(),
-                    device_id,
-                    byte_size,
-                })
-                .await?;
-            Ok(())
-        })
-        .await
+        let request = pb::CudaSharedMemoryRegisterRequest {
+            name: name.to_string(),
+            raw_handle: raw_handle.clone(),
+            device_id,
+            byte_size,
+        };
+        self.with_root_client(
+            "cuda_shared_memory_register",
+            request,
+            |mut client, request| async move { client.cuda_shared_memory_register(request).await },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn cuda_shared_memory_unregister(&self, name: &str) -> Result<()> {
-        self.with_root_client(|mut client| async move {
-            client
-                .cuda_shared_memory_unregister(pb::CudaSharedMemoryUnregisterRequest {
-                    name: name.to_string(),
-                })
-                .await?;
-            Ok(())
-        })
-        .await
+        let request = pb::CudaSharedMemoryUnregisterRequest {
+            name: name.to_string(),
+        };
+        self.with_root_client(
+            "cuda_shared_memory_unregister",
+            request,
+            |mut client, request| async move { client.cuda_shared_memory_unregister(request).await },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn trace_setting(
@@ -313,14 +647,12 @@ impl InferenceServerClient {
         model_name: &str,
         settings: Option<&HashMap<String, pb::TraceSettingValue>>,
     ) -> Result<pb::TraceSettingResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .trace_setting(pb::TraceSettingRequest {
-                    settings: settings.unwrap_or(&HashMap::new()).clone(),
-                    model_name: model_name.to_string(),
-                })
-                .await?;
-            Ok(result.into_inner())
+        let request = pb::TraceSettingRequest {
+            settings: settings.cloned().unwrap_or_default(),
+            model_name: model_name.to_string(),
+        };
+        self.with_root_client("trace_setting", request, |mut client, request| async move {
+            client.trace_setting(request).await
         })
         .await
     }
@@ -329,22 +661,54 @@ impl InferenceServerClient {
         &self,
         settings: Option<&HashMap<String, pb::LogSettingValue>>,
     ) -> Result<pb::LogSettingsResponse> {
-        self.with_root_client(|mut client| async move {
-            let result = client
-                .log_settings(pb::LogSettingsRequest {
-                    settings: settings.unwrap_or(&HashMap::new()).clone(),
-                })
-                .await?;
-            Ok(result.into_inner())
+        let request = pb::LogSettingsRequest {
+            settings: settings.cloned().unwrap_or_default(),
+        };
+        self.with_root_client("log_settings", request, |mut client, request| async move {
+            client.log_settings(request).await
         })
         .await
     }
+
+    /// Trace settings in effect for `model_name`, or the global ones when `None`.
+    pub async fn current_trace_settings(&self, model_name: Option<&str>) -> Result<TraceSettings> {
+        self.trace_setting(model_name.unwrap_or(""), None)
+            .await?
+            .try_into()
+    }
+
+    /// Apply `update` and return the settings in effect for its scope.
+    pub async fn update_trace_settings(
+        &self,
+        update: TraceSettingsUpdate,
+    ) -> Result<TraceSettings> {
+        let request = pb::TraceSettingRequest::from(update);
+        self.trace_setting(&request.model_name, Some(&request.settings))
+            .await?
+            .try_into()
+    }
+
+    /// Drop every trace setting override of the model.
+    pub async fn clear_model_trace_settings(&self, model_name: &str) -> Result<TraceSettings> {
+        self.update_trace_settings(TraceSettingsUpdate::for_model(model_name).clear_all())
+            .await
+    }
+
+    pub async fn current_log_settings(&self) -> Result<LogSettings> {
+        self.log_settings(None).await?.try_into()
+    }
+
+    /// Apply `update` and return the settings in effect.
+    pub async fn update_log_settings(&self, update: LogSettingsUpdate) -> Result<LogSettings> {
+        let request = pb::LogSettingsRequest::from(update);
+        self.log_settings(Some(&request.settings)).await?.try_into()
+    }
 }
 
 impl Clone for InferenceServerClient {
     fn clone(&self) -> Self {
         let config = self.config.clone();
-        let channel = ChannelPool::new(
+        let mut channel = ChannelPool::new(
             config.uri.clone(),
             config.tls,
             config.timeout,
@@ -352,6 +716,14 @@ impl Clone for InferenceServerClient {
             config.keep_alive_while_idle,
             config.keep_alive_timeout,
         );
-        Self { config, channel }
+        if let Some(preset) = self.channel.preset() {
+            channel = channel.preset_channel(preset.clone());
+        }
+        Self {
+            config,
+            channel,
+            recorder: self.recorder.clone(),
+            cache: self.cache.clone(),
+        }
     }
 }
@@ -0,0 +1,212 @@
+use super::{AsTimeout, Error, InferenceServerClient, Result};
+use crate::grpc::output::ModelOutput;
+use crate::grpc::pb;
+use futures_util::future::join_all;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+#[derive(Default)]
+struct RoutingTable {
+    // model name -> version -> indices of the servers where it is ready
+    models: HashMap<String, HashMap<String, Vec<usize>>>,
+    refreshed_at: Option<Instant>,
+    // Set when the last refresh reached no server
+    error: Option<Error>,
+}
+
+/// Routes inference requests to the server that has the requested model loaded.
+pub struct MultiServerClient {
+    clients: Vec<InferenceServerClient>,
+    repository_name: String,
+    refresh_interval: Duration,
+    min_refresh_interval: Duration,
+    routes: RwLock<RoutingTable>,
+    // Held while refreshing, callers arriving meanwhile share the running refresh
+    refreshing: tokio::sync::Mutex<()>,
+    next: AtomicUsize,
+}
+
+impl MultiServerClient {
+    pub fn new(clients: Vec<InferenceServerClient>) -> Self {
+        Self {
+            clients,
+            repository_name: String::new(),
+            refresh_interval: Duration::from_secs(30),
+            min_refresh_interval: Duration::from_secs(1),
+            routes: RwLock::new(RoutingTable::default()),
+            refreshing: tokio::sync::Mutex::new(()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// How old the routing table may get before the next request rebuilds it
+    pub fn set_refresh_interval<T: AsTimeout>(&mut self, refresh_interval: T) {
+        self.refresh_interval = refresh_interval.timeout();
+    }
+
+    pub fn refresh_interval<T: AsTimeout>(mut self, refresh_interval: T) -> Self {
+        self.set_refresh_interval(refresh_interval);
+        self
+    }
+
+    /// How old the routing table must be before a request for an unknown model rebuilds it
+    pub fn set_min_refresh_interval<T: AsTimeout>(&mut self, min_refresh_interval: T) {
+        self.min_refresh_interval = min_refresh_interval.timeout();
+    }
+
+    pub fn min_refresh_interval<T: AsTimeout>(mut self, min_refresh_interval: T) -> Self {
+        self.set_min_refresh_interval(min_refresh_interval);
+        self
+    }
+
+    pub fn set_repository_name<T: ToString>(&mut self, repository_name: T) {
+        self.repository_name = repository_name.to_string();
+    }
+
+    pub fn repository_name<T: ToString>(mut self, repository_name: T) -> Self {
+        self.set_repository_name(repository_name);
+        self
+    }
+
+    pub fn clients(&self) -> &[InferenceServerClient] {
+        &self.clients
+    }
+
+    /// Rebuild the routing table from `repository_index(ready=true)` of every server, queried
+    /// concurrently. Servers that cannot be reached are left out until the next refresh.
+    /// Concurrent calls share one refresh.
+    pub async fn refresh(&self) -> Result<()> {
+        let requested_at = Instant::now();
+        let _refreshing = self.refreshing.lock().await;
+        {
+            let routes = self.routes.read()?;
+            if routes.refreshed_at.is_some_and(|at| at >= requested_at) {
+                return routes.error.clone().map_or(Ok(()), Err);
+            }
+        }
+
+        let responses = join_all(
+            self.clients
+                .iter()
+                .map(|client| client.repository_index(&self.repository_name, true)),
+        )
+        .await;
+        let mut models: HashMap<String, HashMap<String, Vec<usize>>> = HashMap::new();
+        let mut last_error = None;
+        let mut reachable = 0;
+        for (index, response) in responses.into_iter().enumerate() {
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    last_error = Some(err);
+                    continue;
+                }
+            };
+            reachable += 1;
+            for model in response.models {
+                let servers = models
+                    .entry(model.name)
+                    .or_default()
+                    .entry(model.version)
+                    .or_default();
+                if !servers.contains(&index) {
+                    servers.push(index);
+                }
+            }
+        }
+
+        let error = last_error.filter(|_| reachable == 0);
+        let mut routes = self.routes.write()?;
+        routes.models = models;
+        routes.refreshed_at = Some(Instant::now());
+        routes.error = error.clone();
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Refresh the routing table every `refresh_interval` in the background, until the client
+    /// is dropped.
+    pub fn spawn_refresh(self: &Arc<Self>) -> JoinHandle<()> {
+        let client = Arc::downgrade(self);
+        // `interval` panics on a zero period
+        let period = self.refresh_interval.max(Duration::from_millis(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let Some(client) = client.upgrade() else {
+                    return;
+                };
+                // Unreachable servers are tried again on the next tick.
+                let _ = client.refresh().await;
+            }
+        })
+    }
+
+    // Whether the routing table is at least `age` old, or was never built.
+    fn is_older_than(&self, age: Duration) -> Result<bool> {
+        let routes = self.routes.read()?;
+        Ok(routes.refreshed_at.map_or(true, |at| at.elapsed() >= age))
+    }
+
+    /// Indices into [`Self::clients`] of the servers serving the model, an empty version means any.
+    pub fn servers_for(&self, model_name: &str, version: &str) -> Result<Vec<usize>> {
+        let routes = self.routes.read()?;
+        let Some(versions) = routes.models.get(model_name) else {
+            return Ok(vec![]);
+        };
+        let mut servers = if version.is_empty() {
+            versions.values().flatten().copied().collect::<Vec<_>>()
+        } else {
+            versions.get(version).cloned().unwrap_or_default()
+        };
+        servers.sort_unstable();
+        servers.dedup();
+        Ok(servers)
+    }
+
+    /// Ready models and versions per the last refresh.
+    pub fn models(&self) -> Result<HashMap<String, Vec<String>>> {
+        let routes = self.routes.read()?;
+        Ok(routes
+            .models
+            .iter()
+            .map(|(name, versions)| {
+                let mut versions = versions.keys().cloned().collect::<Vec<_>>();
+                versions.sort();
+                (name.clone(), versions)
+            })
+            .collect())
+    }
+
+    /// Pick a server for the model, refreshing the table when stale or when the model is unknown
+    /// and the table is older than `min_refresh_interval`.
+    pub async fn route(&self, model_name: &str, version: &str) -> Result<&InferenceServerClient> {
+        if self.is_older_than(self.refresh_interval)? {
+            self.refresh().await?;
+        }
+        let mut servers = self.servers_for(model_name, version)?;
+        if servers.is_empty() && self.is_older_than(self.min_refresh_interval)? {
+            self.refresh().await?;
+            servers = self.servers_for(model_name, version)?;
+        }
+        if servers.is_empty() {
+            return Err(Error::ModelNotFound {
+                model: model_name.to_string(),
+                version: version.to_string(),
+            });
+        }
+        let index = servers[self.next.fetch_add(1, Ordering::Relaxed) % servers.len()];
+        Ok(&self.clients[index])
+    }
+
+    pub async fn infer(&self, request: impl Into<pb::ModelInferRequest>) -> Result<ModelOutput> {
+        let request = request.into();
+        let client = self
+            .route(&request.model_name, &request.model_version)
+            .await?;
+        client.infer(request).await
+    }
+}
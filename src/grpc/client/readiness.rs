@@ -0,0 +1,118 @@
+use super::channel::is_transport_failure;
+use super::{Error, InferenceServerClient, Result, WaitOptions};
+use std::future::Future;
+use tokio::time::Instant;
+use tonic::Code;
+
+// Whether the error only means the server is not up yet, e.g. a refused connection while
+// Triton boots or a model it does not know about until the repository is loaded.
+fn not_up_yet(error: &Error) -> bool {
+    match error {
+        Error::ResponseError { status } => {
+            is_transport_failure(status)
+                || matches!(status.code(), Code::NotFound | Code::DeadlineExceeded)
+        }
+        _ => false,
+    }
+}
+
+// Outcome of one probe bounded by the deadline.
+enum Probe {
+    Ready,
+    NotReady,
+    Unreachable(String),
+    // Cut off by the deadline, says nothing new about the server
+    Expired,
+}
+
+async fn probe<F>(deadline: Instant, probe: F) -> Result<Probe>
+where
+    F: Future<Output = Result<bool>>,
+{
+    match tokio::time::timeout_at(deadline, probe).await {
+        Ok(Ok(true)) => Ok(Probe::Ready),
+        Ok(Ok(false)) => Ok(Probe::NotReady),
+        Ok(Err(e)) if not_up_yet(&e) => Ok(Probe::Unreachable(e.to_string())),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Ok(Probe::Expired),
+    }
+}
+
+impl InferenceServerClient {
+    /// Poll `is_server_live` then `is_server_ready` with backoff until the server is ready.
+    /// Connection failures count as not ready, on timeout the error carries the last reason.
+    pub async fn wait_for_server_ready(&self, wait: &WaitOptions) -> Result<()> {
+        let deadline = Instant::now() + wait.timeout;
+        let mut reason = "server is not live".to_string();
+        let mut attempt = 0;
+        loop {
+            match probe(deadline, self.is_server_live()).await? {
+                Probe::Ready => match probe(deadline, self.is_server_ready()).await? {
+                    Probe::Ready => return Ok(()),
+                    Probe::NotReady => reason = "server is live but not ready".to_string(),
+                    Probe::Unreachable(last) => reason = last,
+                    Probe::Expired => {}
+                },
+                Probe::NotReady => reason = "server is not live".to_string(),
+                Probe::Unreachable(last) => reason = last,
+                Probe::Expired => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::ServerNotReady { reason });
+            }
+            let delay = wait.backoff.delay(attempt).min(deadline - now);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Poll `is_model_ready` with backoff until every `(name, version)` is ready, a `None`
+    /// version lets the server pick one. Connection failures and models the server does not
+    /// know yet count as not ready, on timeout the error lists the models still not ready with
+    /// the last reason of each.
+    pub async fn wait_for_models_ready(
+        &self,
+        models: &[(&str, Option<&str>)],
+        wait: &WaitOptions,
+    ) -> Result<()> {
+        let deadline = Instant::now() + wait.timeout;
+        let mut pending = models
+            .iter()
+            .map(|(name, version)| (*name, *version, String::new()))
+            .collect::<Vec<_>>();
+        let mut attempt = 0;
+        loop {
+            let mut still_pending = vec![];
+            for (name, version, mut reason) in pending {
+                match probe(deadline, self.is_model_ready(name, version)).await? {
+                    Probe::Ready => continue,
+                    Probe::NotReady => reason.clear(),
+                    Probe::Unreachable(last) => reason = last,
+                    Probe::Expired => {}
+                }
+                still_pending.push((name, version, reason));
+            }
+            pending = still_pending;
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::ModelsNotReady {
+                    models: pending
+                        .into_iter()
+                        .map(|(name, version, reason)| {
+                            (name.to_string(), version.unwrap_or("").to_string(), reason)
+                        })
+                        .collect(),
+                });
+            }
+            let delay = wait.backoff.delay(attempt).min(deadline - now);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use super::{Error, InferenceServerClient, Result, WaitOptions};
+use crate::grpc::pbtxt::model_config_difference;
+use crate::grpc::repository::{ensemble_steps, ModelLoadRequest, RepositoryManifest};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReconcileAction {
+    Load {
+        model: String,
+        versions: Vec<i64>,
+        reason: String,
+    },
+    Unload {
+        model: String,
+        reason: String,
+    },
+}
+
+impl ReconcileAction {
+    pub fn model(&self) -> &str {
+        match self {
+            Self::Load { model, .. } | Self::Unload { model, .. } => model,
+        }
+    }
+}
+
+impl Display for ReconcileAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load {
+                model,
+                versions,
+                reason,
+            } if versions.is_empty() => write!(f, "load `{model}`: {reason}"),
+            Self::Load {
+                model,
+                versions,
+                reason,
+            } => write!(f, "load `{model}` versions {versions:?}: {reason}"),
+            Self::Unload { model, reason } => write!(f, "unload `{model}`: {reason}"),
+        }
+    }
+}
+
+/// What [`Reconciler::apply`] would do, in order: unloads, then loads with dependencies first.
+#[derive(Clone, Debug, Default)]
+pub struct ReconcilePlan {
+    pub actions: Vec<ReconcileAction>,
+    /// Manifest models already served as described
+    pub unchanged: Vec<String>,
+}
+
+impl ReconcilePlan {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl Display for ReconcilePlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.actions.is_empty() {
+            return writeln!(f, "nothing to do");
+        }
+        for action in &self.actions {
+            writeln!(f, "{action}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub applied: Vec<ReconcileAction>,
+    pub failed: Vec<(ReconcileAction, Error)>,
+    pub unchanged: Vec<String>,
+}
+
+impl ReconcileReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Converges the models served by a server to a [`RepositoryManifest`].
+pub struct Reconciler {
+    client: InferenceServerClient,
+    manifest: RepositoryManifest,
+    wait: WaitOptions,
+}
+
+// Order `names` so that every model comes after the models it depends on.
+fn dependency_order(
+    names: &[String],
+    dependencies: impl Fn(&str) -> Vec<String>,
+) -> Result<Vec<String>> {
+    let listed = names.iter().collect::<HashSet<_>>();
+    let mut ordered = vec![];
+    let mut done = HashSet::new();
+    let mut remaining = names.to_vec();
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|name| {
+            dependencies(name)
+                .iter()
+                .all(|dependency| !listed.contains(dependency) || done.contains(dependency))
+        });
+        if ready.is_empty() {
+            return Err(Error::ConversionError(format!(
+                "Invalid manifest: dependency cycle between {}",
+                blocked.join(", ")
+            )));
+        }
+        done.extend(ready.iter().cloned());
+        ordered.extend(ready);
+        remaining = blocked;
+    }
+    Ok(ordered)
+}
+
+impl Reconciler {
+    pub fn new(client: InferenceServerClient, manifest: RepositoryManifest) -> Self {
+        Self {
+            client,
+            manifest,
+            wait: WaitOptions::default(),
+        }
+    }
+
+    /// How long each load or unload may take to settle
+    pub fn set_wait(&mut self, wait: WaitOptions) {
+        self.wait = wait;
+    }
+
+    pub fn wait(mut self, wait: WaitOptions) -> Self {
+        self.set_wait(wait);
+        self
+    }
+
+    pub fn manifest(&self) -> &RepositoryManifest {
+        &self.manifest
+    }
+
+    /// Diff the manifest against `repository_index` without changing anything (dry run).
+    pub async fn plan(&self) -> Result<ReconcilePlan> {
+        let repository_name = &self.manifest.repository_name;
+        let mut ready: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for entry in self
+            .client
+            .repository_index(repository_name, false)
+            .await?
+            .models
+        {
+            if entry.state == "READY" {
+                ready.entry(entry.name).or_default().insert(entry.version);
+            }
+        }
+
+        let names = self
+            .manifest
+            .models
+            .iter()
+            .map(|model| model.name.clone())
+            .collect::<Vec<_>>();
+        let order = dependency_order(&names, |name| {
+            self.manifest
+                .get(name)
+                .map(|model| model.dependencies())
+                .unwrap_or_default()
+        })?;
+        // Dependencies are kept even when not listed themselves.
+        let needed = self
+            .manifest
+            .models
+            .iter()
+            .flat_map(|model| model.dependencies())
+            .chain(names)
+            .collect::<HashSet<_>>();
+
+        let mut plan = ReconcilePlan::default();
+        if self.manifest.unload_unlisted {
+            let unlisted = ready
+                .keys()
+                .filter(|name| !needed.contains(*name))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut steps = BTreeMap::new();
+            for name in &unlisted {
+                if let Ok(config) = self.client.cached_model_config(name, None).await {
+                    steps.insert(name.clone(), ensemble_steps(config.as_pb()));
+                }
+            }
+            // Ensembles go before the models they use.
+            let mut order = dependency_order(&unlisted, |name| {
+                steps.get(name).cloned().unwrap_or_default()
+            })?;
+            order.reverse();
+            plan.actions
+                .extend(order.into_iter().map(|model| ReconcileAction::Unload {
+                    model,
+                    reason: "not in the manifest".to_string(),
+                }));
+        }
+
+        for name in order {
+            let Some(model) = self.manifest.get(&name) else {
+                continue;
+            };
+            let served = ready.get(&name);
+            let expected = model
+                .versions
+                .iter()
+                .map(|version| version.to_string())
+                .collect::<BTreeSet<_>>();
+            let reason = match served {
+                None => Some("not ready".to_string()),
+                Some(served) if !expected.is_empty() && *served != expected => Some(format!(
+                    "ready versions {:?}, expected {:?}",
+                    served, expected
+                )),
+                Some(_) => match &model.config {
+                    Some(config) => {
+                        let response = self.client.model_config(&name, None).await?;
+                        let served = response.config.unwrap_or_default();
+                        model_config_difference(config, &served)
+                            .map(|field| format!("config differs at `{field}`"))
+                    }
+                    None => None,
+                },
+            };
+            match reason {
+                Some(reason) => plan.actions.push(ReconcileAction::Load {
+                    model: name,
+                    versions: model.versions.clone(),
+                    reason,
+                }),
+                None => plan.unchanged.push(name),
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Run the actions of `plan` in order. Loads whose dependencies failed are not attempted.
+    pub async fn apply(&self, plan: &ReconcilePlan) -> ReconcileReport {
+        let repository_name = &self.manifest.repository_name;
+        let mut report = ReconcileReport {
+            unchanged: plan.unchanged.clone(),
+            ..ReconcileReport::default()
+        };
+        let mut failed = HashSet::new();
+        for action in &plan.actions {
+            let result = match action {
+                ReconcileAction::Unload { model, .. } => self
+                    .client
+                    .unload_model_and_wait(repository_name, model, false, &[], &self.wait)
+                    .await
+                    .map(|_| ()),
+                ReconcileAction::Load {
+                    model, versions, ..
+                } => {
+                    let spec = self.manifest.get(model);
+                    let dependencies = spec.map(|spec| spec.dependencies()).unwrap_or_default();
+                    match dependencies.iter().find(|dep| failed.contains(*dep)) {
+                        Some(dependency) => Err(Error::DependencyFailed {
+                            model: model.clone(),
+                            dependency: dependency.clone(),
+                        }),
+                        None => {
+                            let request = match spec {
+                                Some(spec) => spec.load_request(repository_name),
+                                None => {
+                                    ModelLoadRequest::new(model).repository_name(repository_name)
+                                }
+                            };
+                            let versions = versions
+                                .iter()
+                                .map(|version| version.to_string())
+                                .collect::<Vec<_>>();
+                            let versions = versions.iter().map(String::as_str).collect::<Vec<_>>();
+                            self.client
+                                .load_model_and_wait(request, &versions, &self.wait)
+                                .await
+                                .map(|_| ())
+                        }
+                    }
+                }
+            };
+            match result {
+                Ok(()) => report.applied.push(action.clone()),
+                Err(err) => {
+                    failed.insert(action.model().to_string());
+                    report.failed.push((action.clone(), err));
+                }
+            }
+        }
+        report
+    }
+
+    /// Plan then apply, only planning when `dry_run` is set.
+    pub async fn reconcile(&self, dry_run: bool) -> Result<(ReconcilePlan, ReconcileReport)> {
+        let plan = self.plan().await?;
+        let report = if dry_run {
+            ReconcileReport {
+                unchanged: plan.unchanged.clone(),
+                ..ReconcileReport::default()
+            }
+        } else {
+            self.apply(&plan).await
+        };
+        Ok((plan, report))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::grpc::pb;
+
+// What a request is about, labels the client metrics and spans.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) trait RpcRequest: prost::Message + Clone {
+    fn model_name(&self) -> &str {
+        ""
+    }
+
+    fn model_version(&self) -> &str {
+        ""
+    }
+
+    fn request_id(&self) -> &str {
+        ""
+    }
+}
+
+impl RpcRequest for pb::ModelInferRequest {
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn model_version(&self) -> &str {
+        &self.model_version
+    }
+
+    fn request_id(&self) -> &str {
+        &self.id
+    }
+}
+
+macro_rules! model_request {
+    ($($request:ty),*) => {
+        $(impl RpcRequest for $request {
+            fn model_name(&self) -> &str {
+                &self.name
+            }
+
+            fn model_version(&self) -> &str {
+                &self.version
+            }
+        })*
+    };
+}
+
+model_request!(
+    pb::ModelReadyRequest,
+    pb::ModelMetadataRequest,
+    pb::ModelConfigRequest,
+    pb::ModelStatisticsRequest
+);
+
+macro_rules! model_name_request {
+    ($($request:ty),*) => {
+        $(impl RpcRequest for $request {
+            fn model_name(&self) -> &str {
+                &self.model_name
+            }
+        })*
+    };
+}
+
+model_name_request!(
+    pb::RepositoryModelLoadRequest,
+    pb::RepositoryModelUnloadRequest,
+    pb::TraceSettingRequest
+);
+
+macro_rules! server_request {
+    ($($request:ty),*) => {
+        $(impl RpcRequest for $request {})*
+    };
+}
+
+server_request!(
+    pb::HealthCheckRequest,
+    pb::ServerLiveRequest,
+    pb::ServerReadyRequest,
+    pb::ServerMetadataRequest,
+    pb::RepositoryIndexRequest,
+    pb::SystemSharedMemoryStatusRequest,
+    pb::SystemSharedMemoryRegisterRequest,
+    pb::SystemSharedMemoryUnregisterRequest,
+    pb::CudaSharedMemoryStatusRequest,
+    pb::CudaSharedMemoryRegisterRequest,
+    pb::CudaSharedMemoryUnregisterRequest,
+    pb::LogSettingsRequest
+);
@@ -0,0 +1,77 @@
+use super::rpc::RpcRequest;
+use super::Result;
+use tonic::Status;
+use tracing::field::{display, Empty};
+use tracing::Span;
+
+// Span around one RPC, named after the method for OpenTelemetry exporters.
+pub(crate) fn rpc_span<R: RpcRequest>(method: &'static str, request: &R) -> Span {
+    tracing::info_span!(
+        "triton_rpc",
+        otel.name = method,
+        otel.kind = "client",
+        otel.status_code = Empty,
+        rpc.system = "grpc",
+        rpc.method = method,
+        model_name = request.model_name(),
+        model_version = request.model_version(),
+        request_id = request.request_id(),
+        request_bytes = request.encoded_len(),
+        response_bytes = Empty,
+        error = Empty,
+    )
+}
+
+pub(crate) fn record_result<T: prost::Message>(span: &Span, result: &Result<T>) {
+    match result {
+        Ok(response) => {
+            span.record("response_bytes", response.encoded_len());
+        }
+        Err(error) => {
+            span.record("otel.status_code", "ERROR");
+            span.record("error", display(error));
+        }
+    }
+}
+
+pub(crate) fn record_reconnect(status: &Status) {
+    tracing::warn!(
+        code = ?status.code(),
+        status_message = status.message(),
+        "channel dropped after a transport failure"
+    );
+}
+
+pub(crate) fn record_retry() {
+    tracing::info!("request sent again on a new channel");
+}
+
+/// Add the W3C `traceparent` (and `tracestate`) of the current span to the request metadata,
+/// letting Triton's OpenTelemetry tracing attach its spans to ours.
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn inject_trace_context(metadata: &mut tonic::metadata::MetadataMap) {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return;
+    }
+    let traceparent = format!(
+        "00-{:032x}-{:016x}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    );
+    if let Ok(value) = traceparent.parse() {
+        metadata.insert("traceparent", value);
+    }
+    let tracestate = span_context.trace_state().header();
+    if !tracestate.is_empty() {
+        if let Ok(value) = tracestate.parse() {
+            metadata.insert("tracestate", value);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::{Error, InferenceServerClient, Result};
+use crate::grpc::pb;
+use crate::grpc::warmup::warmup_samples;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Outcome of every execution of one warmup sample.
+#[derive(Debug)]
+pub struct WarmupResult {
+    pub name: String,
+    /// Latency of each successful execution
+    pub latencies: Vec<Duration>,
+    pub errors: Vec<Error>,
+}
+
+impl WarmupResult {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let count = self.latencies.len() as u32;
+        (count > 0).then(|| self.latencies.iter().sum::<Duration>() / count)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WarmupReport {
+    pub model_name: String,
+    pub samples: Vec<WarmupResult>,
+}
+
+impl WarmupReport {
+    pub fn is_success(&self) -> bool {
+        self.samples.iter().all(WarmupResult::is_success)
+    }
+}
+
+impl InferenceServerClient {
+    /// Send the `model_warmup` samples of `config` to the server, `count` times each.
+    /// See [`warmup_samples`] for `warmup_dir`.
+    pub async fn run_warmup(
+        &self,
+        config: &pb::ModelConfig,
+        version: Option<&str>,
+        warmup_dir: Option<&Path>,
+    ) -> Result<WarmupReport> {
+        let mut report = WarmupReport {
+            model_name: config.name.clone(),
+            samples: vec![],
+        };
+        for sample in warmup_samples(config, version.unwrap_or(""), warmup_dir)? {
+            let mut result = WarmupResult {
+                name: sample.name,
+                latencies: vec![],
+                errors: vec![],
+            };
+            for _ in 0..sample.count {
+                let start = Instant::now();
+                match self.infer(sample.request.clone()).await {
+                    Ok(_) => result.latencies.push(start.elapsed()),
+                    Err(err) => result.errors.push(err),
+                }
+            }
+            report.samples.push(result);
+        }
+        Ok(report)
+    }
+
+    /// [`Self::run_warmup`] with the config served for the model.
+    pub async fn warmup_model(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+        warmup_dir: Option<&Path>,
+    ) -> Result<WarmupReport> {
+        let config = self.cached_model_config(model_name, version).await?;
+        self.run_warmup(config.as_pb(), version, warmup_dir).await
+    }
+}
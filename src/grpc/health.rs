@@ -0,0 +1,105 @@
+use super::client::{Error, Result};
+use super::pb::{self, health_check_response};
+use futures_core::Stream;
+use std::fmt::{Display, Formatter};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tonic::Streaming;
+
+/// Service name Triton reports the inference service health under, the empty name stands for
+/// the whole server.
+pub const INFERENCE_SERVICE: &str = "inference.GRPCInferenceService";
+
+/// `grpc.health.v1` serving status of a service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ServingStatus {
+    Unknown,
+    Serving,
+    NotServing,
+    /// The server does not know the service, only sent by `Watch`
+    ServiceUnknown,
+}
+
+impl ServingStatus {
+    pub fn is_serving(&self) -> bool {
+        matches!(self, Self::Serving)
+    }
+}
+
+impl From<health_check_response::ServingStatus> for ServingStatus {
+    fn from(status: health_check_response::ServingStatus) -> Self {
+        match status {
+            health_check_response::ServingStatus::Unknown => Self::Unknown,
+            health_check_response::ServingStatus::Serving => Self::Serving,
+            health_check_response::ServingStatus::NotServing => Self::NotServing,
+            health_check_response::ServingStatus::ServiceUnknown => Self::ServiceUnknown,
+        }
+    }
+}
+
+impl From<ServingStatus> for health_check_response::ServingStatus {
+    fn from(status: ServingStatus) -> Self {
+        match status {
+            ServingStatus::Unknown => Self::Unknown,
+            ServingStatus::Serving => Self::Serving,
+            ServingStatus::NotServing => Self::NotServing,
+            ServingStatus::ServiceUnknown => Self::ServiceUnknown,
+        }
+    }
+}
+
+impl From<&pb::HealthCheckResponse> for ServingStatus {
+    /// Values unknown to this client map to `Unknown`.
+    fn from(response: &pb::HealthCheckResponse) -> Self {
+        health_check_response::ServingStatus::try_from(response.status)
+            .map(Self::from)
+            .unwrap_or(Self::Unknown)
+    }
+}
+
+impl Display for ServingStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Serving => "SERVING",
+            Self::NotServing => "NOT_SERVING",
+            Self::ServiceUnknown => "SERVICE_UNKNOWN",
+        };
+        write!(f, "{status}")
+    }
+}
+
+/// Serving status of one service as the server reports it, the current status first and then
+/// every change. Ends when the server closes the stream.
+pub struct HealthWatch {
+    inner: Streaming<pb::HealthCheckResponse>,
+}
+
+impl HealthWatch {
+    pub(crate) fn new(inner: Streaming<pb::HealthCheckResponse>) -> Self {
+        Self { inner }
+    }
+
+    /// Wait for the next status, `None` once the stream ended.
+    pub async fn changed(&mut self) -> Option<Result<ServingStatus>> {
+        self.inner
+            .message()
+            .await
+            .transpose()
+            .map(|response| Ok(ServingStatus::from(&response?)))
+    }
+}
+
+impl Stream for HealthWatch {
+    type Item = Result<ServingStatus>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx).map(|item| {
+            item.map(|response| {
+                response
+                    .map(|r| ServingStatus::from(&r))
+                    .map_err(Error::from)
+            })
+        })
+    }
+}
@@ -1,4 +1,6 @@
+use super::client::Result;
 use super::macros::{array_to_tensor, generate_trait_transform_infer_tensor_contents};
+use super::npy::{read_npy, read_npz, NpyArray};
 use super::pb::{
     InferInputTensor, InferParameter, InferRequestedOutputTensor, InferTensorContents,
     ModelInferRequest, ParameterChoice,
@@ -7,12 +9,13 @@ use crate::types::{Bytes, TritonDataTypes};
 use ndarray::ArrayD;
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
+use std::path::Path;
 
 pub trait TransformInferTensorContents: Sized + 'static {
     fn transform(array: ArrayD<Self>) -> InferTensorContents;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct InferInput {
     inner: InferInputTensor,
 }
@@ -122,12 +125,41 @@ impl InferInput {
         self
     }
 
+    /// Input with the datatype, shape and typed contents of a NumPy array, `FP16` arrays have
+    /// no typed contents, see [`ModelInput::set_npy_inputs`].
+    pub fn from_npy(name: String, array: &NpyArray) -> Result<Self> {
+        let mut input = Self::new()
+            .name(name)
+            .datatype(array.datatype.clone())
+            .shape(array.shape.iter().map(|dim| *dim as i64).collect());
+        input.inner.contents = Some(array.contents()?);
+        Ok(input)
+    }
+
+    pub fn from_npy_file<P: AsRef<Path>>(name: String, path: P) -> Result<Self> {
+        Self::from_npy(name, &read_npy(path)?)
+    }
+
+    /// One input per array of a `.npz` archive, named after the array.
+    pub fn from_npz_file<P: AsRef<Path>>(path: P) -> Result<Vec<Self>> {
+        read_npz(path)?
+            .into_iter()
+            .map(|(name, array)| Self::from_npy(name, &array))
+            .collect()
+    }
+
     pub(crate) fn build(self) -> InferInputTensor {
         self.inner
     }
 }
 
-#[derive(Clone, Debug)]
+impl From<InferInputTensor> for InferInput {
+    fn from(inner: InferInputTensor) -> Self {
+        Self { inner }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct ModelInput {
     inner: ModelInferRequest,
 }
@@ -218,6 +250,29 @@ impl ModelInput {
         self
     }
 
+    /// Replace the inputs with NumPy arrays by name, sent as raw contents which also carry `FP16`.
+    pub fn set_npy_inputs(&mut self, arrays: Vec<(String, NpyArray)>) {
+        let (inputs, raw_input_contents) = arrays
+            .into_iter()
+            .map(|(name, array)| {
+                let input = InferInputTensor {
+                    name,
+                    datatype: array.datatype.to_string(),
+                    shape: array.shape.iter().map(|dim| *dim as i64).collect(),
+                    ..Default::default()
+                };
+                (input, array.data)
+            })
+            .unzip();
+        self.inner.inputs = inputs;
+        self.inner.raw_input_contents = raw_input_contents;
+    }
+
+    pub fn npy_inputs(mut self, arrays: Vec<(String, NpyArray)>) -> Self {
+        self.set_npy_inputs(arrays);
+        self
+    }
+
     pub(crate) fn build(self) -> ModelInferRequest {
         self.inner
     }
@@ -236,8 +291,8 @@ generate_trait_transform_infer_tensor_contents!(f32);
 generate_trait_transform_infer_tensor_contents!(f64);
 generate_trait_transform_infer_tensor_contents!(Bytes);
 
-impl Into<ModelInferRequest> for ModelInput {
-    fn into(self) -> ModelInferRequest {
-        self.build()
+impl From<ModelInput> for ModelInferRequest {
+    fn from(value: ModelInput) -> Self {
+        value.build()
     }
 }
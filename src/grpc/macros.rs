@@ -160,3 +160,62 @@ macro_rules! generate_trait_transform_infer_tensor_contents {
 
 pub(crate) use array_to_tensor;
 pub(crate) use generate_trait_transform_infer_tensor_contents;
+
+#[cfg(feature = "server")]
+macro_rules! generate_trait_transform_raw_contents {
+    ($dtype:ident, $datatype:ident) => {
+        impl TransformRawContents for $dtype {
+            fn datatype() -> TritonDataTypes {
+                TritonDataTypes::$datatype
+            }
+
+            fn transform(array: ArrayD<Self>) -> Bytes {
+                array.iter().flat_map(|v| v.to_le_bytes()).collect()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "server")]
+pub(crate) use generate_trait_transform_raw_contents;
+
+macro_rules! map_array_output {
+    ($output:expr, $array:ident => $body:expr) => {
+        match $output {
+            ArrayOutputOneOf::BOOL($array) => ArrayOutputOneOf::BOOL($body),
+            ArrayOutputOneOf::INT8($array) => ArrayOutputOneOf::INT8($body),
+            ArrayOutputOneOf::INT16($array) => ArrayOutputOneOf::INT16($body),
+            ArrayOutputOneOf::INT32($array) => ArrayOutputOneOf::INT32($body),
+            ArrayOutputOneOf::INT64($array) => ArrayOutputOneOf::INT64($body),
+            ArrayOutputOneOf::UINT8($array) => ArrayOutputOneOf::UINT8($body),
+            ArrayOutputOneOf::UINT16($array) => ArrayOutputOneOf::UINT16($body),
+            ArrayOutputOneOf::UINT32($array) => ArrayOutputOneOf::UINT32($body),
+            ArrayOutputOneOf::UINT64($array) => ArrayOutputOneOf::UINT64($body),
+            ArrayOutputOneOf::FP32($array) => ArrayOutputOneOf::FP32($body),
+            ArrayOutputOneOf::FP64($array) => ArrayOutputOneOf::FP64($body),
+            ArrayOutputOneOf::BYTES($array) => ArrayOutputOneOf::BYTES($body),
+        }
+    };
+}
+
+macro_rules! match_array_output {
+    ($output:expr, $array:ident => $body:expr) => {
+        match $output {
+            ArrayOutputOneOf::BOOL($array) => $body,
+            ArrayOutputOneOf::INT8($array) => $body,
+            ArrayOutputOneOf::INT16($array) => $body,
+            ArrayOutputOneOf::INT32($array) => $body,
+            ArrayOutputOneOf::INT64($array) => $body,
+            ArrayOutputOneOf::UINT8($array) => $body,
+            ArrayOutputOneOf::UINT16($array) => $body,
+            ArrayOutputOneOf::UINT32($array) => $body,
+            ArrayOutputOneOf::UINT64($array) => $body,
+            ArrayOutputOneOf::FP32($array) => $body,
+            ArrayOutputOneOf::FP64($array) => $body,
+            ArrayOutputOneOf::BYTES($array) => $body,
+        }
+    };
+}
+
+pub(crate) use map_array_output;
+pub(crate) use match_array_output;
@@ -1,11 +1,28 @@
+pub mod batch;
 pub mod client;
+pub mod health;
 pub mod input;
 pub(crate) mod macros;
+pub mod model;
+pub mod npy;
 pub mod output;
+pub mod pbtxt;
+pub mod perf;
+pub mod record;
+pub mod repository;
+#[cfg(feature = "server")]
+pub mod response;
+pub mod settings;
+pub mod statistics;
+pub mod trace;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod validation;
+pub mod warmup;
 
 pub mod pb {
-    include!("proto/inference.rs");
-    include!("proto/grpc.health.v1.rs");
+    include!(concat!(env!("OUT_DIR"), "/inference.rs"));
+    include!(concat!(env!("OUT_DIR"), "/grpc.health.v1.rs"));
     pub use grpc_inference_service_client::*;
     pub use health_client::*;
     pub use infer_parameter::*;
@@ -13,4 +30,9 @@ pub mod pb {
     pub use model_infer_request::*;
     pub use model_infer_response::*;
     pub use trace_setting_request::SettingValue as TraceSettingValue;
+
+    #[cfg(feature = "server")]
+    pub use grpc_inference_service_server::*;
+    #[cfg(feature = "server")]
+    pub use health_server::*;
 }
@@ -0,0 +1,596 @@
+use super::client::{Error, Result};
+use super::pb::model_config::SchedulingChoice;
+use super::pb::model_input::Format;
+use super::pb::model_instance_group::Kind;
+use super::pb::model_metadata_response::TensorMetadata;
+use super::pb::model_sequence_batching::StrategyChoice;
+use super::pb::model_version_policy::PolicyChoice;
+use super::pb::{self, DataType};
+use crate::types::TritonDataTypes;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dim {
+    Fixed(i64),
+    /// `-1` in Triton shapes
+    Dynamic,
+}
+
+impl Dim {
+    pub fn is_dynamic(&self) -> bool {
+        matches!(self, Self::Dynamic)
+    }
+
+    /// Whether a concrete size fits this dimension.
+    pub fn accepts(&self, size: i64) -> bool {
+        match self {
+            Self::Fixed(expected) => *expected == size,
+            Self::Dynamic => size >= 0,
+        }
+    }
+}
+
+impl From<i64> for Dim {
+    fn from(value: i64) -> Self {
+        if value < 0 {
+            Self::Dynamic
+        } else {
+            Self::Fixed(value)
+        }
+    }
+}
+
+impl From<Dim> for i64 {
+    fn from(value: Dim) -> Self {
+        match value {
+            Dim::Fixed(size) => size,
+            Dim::Dynamic => -1,
+        }
+    }
+}
+
+fn dims(shape: &[i64]) -> Vec<Dim> {
+    shape.iter().map(|v| Dim::from(*v)).collect()
+}
+
+fn datatype(name: &str, data_type: i32) -> Result<TritonDataTypes> {
+    DataType::try_from(data_type)
+        .map_err(|_| Error::ConversionError(format!("Unknown data type {data_type} of `{name}`")))?
+        .try_into()
+}
+
+/// An input or output tensor as declared by the model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TensorSpec {
+    pub name: String,
+    pub datatype: TritonDataTypes,
+    /// Config dims exclude the batch dimension, metadata shapes include it
+    pub dims: Vec<Dim>,
+    pub format: Format,
+    pub reshape: Option<Vec<Dim>>,
+    pub is_shape_tensor: bool,
+    pub optional: bool,
+    pub allow_ragged_batch: bool,
+}
+
+impl TensorSpec {
+    pub fn has_dynamic_dims(&self) -> bool {
+        self.dims.iter().any(Dim::is_dynamic)
+    }
+
+    /// Number of elements, `None` when a dimension is dynamic.
+    pub fn element_count(&self) -> Option<i64> {
+        self.dims.iter().try_fold(1, |count, dim| match dim {
+            Dim::Fixed(size) => Some(count * size),
+            Dim::Dynamic => None,
+        })
+    }
+}
+
+impl TryFrom<&pb::ModelInput> for TensorSpec {
+    type Error = Error;
+
+    fn try_from(input: &pb::ModelInput) -> Result<Self> {
+        Ok(Self {
+            name: input.name.clone(),
+            datatype: datatype(&input.name, input.data_type)?,
+            dims: dims(&input.dims),
+            format: Format::try_from(input.format).unwrap_or(Format::None),
+            reshape: input.reshape.as_ref().map(|reshape| dims(&reshape.shape)),
+            is_shape_tensor: input.is_shape_tensor,
+            optional: input.optional,
+            allow_ragged_batch: input.allow_ragged_batch,
+        })
+    }
+}
+
+impl TryFrom<&pb::ModelOutput> for TensorSpec {
+    type Error = Error;
+
+    fn try_from(output: &pb::ModelOutput) -> Result<Self> {
+        Ok(Self {
+            name: output.name.clone(),
+            datatype: datatype(&output.name, output.data_type)?,
+            dims: dims(&output.dims),
+            format: Format::None,
+            reshape: output.reshape.as_ref().map(|reshape| dims(&reshape.shape)),
+            is_shape_tensor: output.is_shape_tensor,
+            optional: false,
+            allow_ragged_batch: false,
+        })
+    }
+}
+
+impl TryFrom<&TensorMetadata> for TensorSpec {
+    type Error = Error;
+
+    fn try_from(tensor: &TensorMetadata) -> Result<Self> {
+        Ok(Self {
+            name: tensor.name.clone(),
+            datatype: tensor.datatype.parse()?,
+            dims: dims(&tensor.shape),
+            format: Format::None,
+            reshape: None,
+            is_shape_tensor: false,
+            optional: false,
+            allow_ragged_batch: false,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicBatching {
+    pub preferred_batch_sizes: Vec<i32>,
+    pub max_queue_delay: Duration,
+    pub preserve_ordering: bool,
+    pub priority_levels: u64,
+    pub default_priority_level: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SequenceStrategy {
+    Direct {
+        max_queue_delay: Duration,
+        minimum_slot_utilization: f32,
+    },
+    Oldest {
+        max_candidate_sequences: i32,
+        preferred_batch_sizes: Vec<i32>,
+        max_queue_delay: Duration,
+        preserve_ordering: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceBatching {
+    pub max_sequence_idle: Duration,
+    pub strategy: Option<SequenceStrategy>,
+    /// Names of the control inputs (start, end, ready, correlation id...)
+    pub control_inputs: Vec<String>,
+    pub iterative_sequence: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceGroup {
+    pub name: String,
+    pub kind: Kind,
+    pub count: i32,
+    pub gpus: Vec<i32>,
+    pub passive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VersionPolicy {
+    Latest(u32),
+    All,
+    Specific(Vec<i64>),
+}
+
+impl Default for VersionPolicy {
+    /// Triton serves the latest version when no policy is given.
+    fn default() -> Self {
+        Self::Latest(1)
+    }
+}
+
+/// Typed view over `pb::ModelConfig`, read-only so that it always matches [`Self::as_pb`].
+#[derive(Clone, Debug)]
+pub struct ModelConfigView {
+    name: String,
+    platform: String,
+    backend: String,
+    max_batch_size: i32,
+    inputs: Vec<TensorSpec>,
+    outputs: Vec<TensorSpec>,
+    dynamic_batching: Option<DynamicBatching>,
+    sequence_batching: Option<SequenceBatching>,
+    is_ensemble: bool,
+    decoupled: bool,
+    instance_groups: Vec<InstanceGroup>,
+    version_policy: VersionPolicy,
+    inner: pb::ModelConfig,
+}
+
+impl ModelConfigView {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn platform(&self) -> &str {
+        &self.platform
+    }
+
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// 0 when the model does not support batching
+    pub fn max_batch_size(&self) -> i32 {
+        self.max_batch_size
+    }
+
+    pub fn inputs(&self) -> &[TensorSpec] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[TensorSpec] {
+        &self.outputs
+    }
+
+    pub fn dynamic_batching(&self) -> Option<&DynamicBatching> {
+        self.dynamic_batching.as_ref()
+    }
+
+    pub fn sequence_batching(&self) -> Option<&SequenceBatching> {
+        self.sequence_batching.as_ref()
+    }
+
+    pub fn is_ensemble(&self) -> bool {
+        self.is_ensemble
+    }
+
+    pub fn decoupled(&self) -> bool {
+        self.decoupled
+    }
+
+    pub fn instance_groups(&self) -> &[InstanceGroup] {
+        &self.instance_groups
+    }
+
+    pub fn version_policy(&self) -> &VersionPolicy {
+        &self.version_policy
+    }
+
+    pub fn supports_batching(&self) -> bool {
+        self.max_batch_size > 0
+    }
+
+    pub fn input(&self, name: &str) -> Option<&TensorSpec> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    pub fn output(&self, name: &str) -> Option<&TensorSpec> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+
+    pub fn as_pb(&self) -> &pb::ModelConfig {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> pb::ModelConfig {
+        self.inner
+    }
+}
+
+impl TryFrom<pb::ModelConfig> for ModelConfigView {
+    type Error = Error;
+
+    fn try_from(config: pb::ModelConfig) -> Result<Self> {
+        let (dynamic_batching, sequence_batching, is_ensemble) = match &config.scheduling_choice {
+            Some(SchedulingChoice::DynamicBatching(batching)) => (
+                Some(DynamicBatching {
+                    preferred_batch_sizes: batching.preferred_batch_size.clone(),
+                    max_queue_delay: Duration::from_micros(batching.max_queue_delay_microseconds),
+                    preserve_ordering: batching.preserve_ordering,
+                    priority_levels: batching.priority_levels,
+                    default_priority_level: batching.default_priority_level,
+                }),
+                None,
+                false,
+            ),
+            Some(SchedulingChoice::SequenceBatching(batching)) => {
+                let strategy = batching
+                    .strategy_choice
+                    .as_ref()
+                    .map(|strategy| match strategy {
+                        StrategyChoice::Direct(direct) => SequenceStrategy::Direct {
+                            max_queue_delay: Duration::from_micros(
+                                direct.max_queue_delay_microseconds,
+                            ),
+                            minimum_slot_utilization: direct.minimum_slot_utilization,
+                        },
+                        StrategyChoice::Oldest(oldest) => SequenceStrategy::Oldest {
+                            max_candidate_sequences: oldest.max_candidate_sequences,
+                            preferred_batch_sizes: oldest.preferred_batch_size.clone(),
+                            max_queue_delay: Duration::from_micros(
+                                oldest.max_queue_delay_microseconds,
+                            ),
+                            preserve_ordering: oldest.preserve_ordering,
+                        },
+                    });
+                let sequence_batching = SequenceBatching {
+                    max_sequence_idle: Duration::from_micros(
+                        batching.max_sequence_idle_microseconds,
+                    ),
+                    strategy,
+                    control_inputs: batching
+                        .control_input
+                        .iter()
+                        .map(|control| control.name.clone())
+                        .collect(),
+                    iterative_sequence: batching.iterative_sequence,
+                };
+                (None, Some(sequence_batching), false)
+            }
+            Some(SchedulingChoice::EnsembleScheduling(_)) => (None, None, true),
+            None => (None, None, false),
+        };
+
+        let version_policy = match config
+            .version_policy
+            .as_ref()
+            .and_then(|policy| policy.policy_choice.as_ref())
+        {
+            Some(PolicyChoice::Latest(latest)) => VersionPolicy::Latest(latest.num_versions),
+            Some(PolicyChoice::All(_)) => VersionPolicy::All,
+            Some(PolicyChoice::Specific(specific)) => {
+                VersionPolicy::Specific(specific.versions.clone())
+            }
+            None => VersionPolicy::default(),
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            platform: config.platform.clone(),
+            backend: config.backend.clone(),
+            max_batch_size: config.max_batch_size,
+            inputs: config
+                .input
+                .iter()
+                .map(TensorSpec::try_from)
+                .collect::<Result<_>>()?,
+            outputs: config
+                .output
+                .iter()
+                .map(TensorSpec::try_from)
+                .collect::<Result<_>>()?,
+            dynamic_batching,
+            sequence_batching,
+            is_ensemble,
+            decoupled: config
+                .model_transaction_policy
+                .as_ref()
+                .is_some_and(|policy| policy.decoupled),
+            instance_groups: config
+                .instance_group
+                .iter()
+                .map(|group| InstanceGroup {
+                    name: group.name.clone(),
+                    kind: Kind::try_from(group.kind).unwrap_or(Kind::Auto),
+                    count: group.count,
+                    gpus: group.gpus.clone(),
+                    passive: group.passive,
+                })
+                .collect(),
+            version_policy,
+            inner: config,
+        })
+    }
+}
+
+impl TryFrom<pb::ModelConfigResponse> for ModelConfigView {
+    type Error = Error;
+
+    fn try_from(response: pb::ModelConfigResponse) -> Result<Self> {
+        response
+            .config
+            .ok_or_else(|| Error::ConversionError("Missing model config".to_string()))?
+            .try_into()
+    }
+}
+
+/// Typed view over `pb::ModelMetadataResponse`.
+#[derive(Clone, Debug)]
+pub struct ModelMetadataView {
+    pub name: String,
+    pub versions: Vec<String>,
+    pub platform: String,
+    pub inputs: Vec<TensorSpec>,
+    pub outputs: Vec<TensorSpec>,
+}
+
+impl ModelMetadataView {
+    pub fn input(&self, name: &str) -> Option<&TensorSpec> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    pub fn output(&self, name: &str) -> Option<&TensorSpec> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+}
+
+impl TryFrom<pb::ModelMetadataResponse> for ModelMetadataView {
+    type Error = Error;
+
+    fn try_from(metadata: pb::ModelMetadataResponse) -> Result<Self> {
+        Ok(Self {
+            inputs: metadata
+                .inputs
+                .iter()
+                .map(TensorSpec::try_from)
+                .collect::<Result<_>>()?,
+            outputs: metadata
+                .outputs
+                .iter()
+                .map(TensorSpec::try_from)
+                .collect::<Result<_>>()?,
+            name: metadata.name,
+            versions: metadata.versions,
+            platform: metadata.platform,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::pb::model_sequence_batching::{ControlInput, StrategyOldest};
+    use crate::grpc::pb::model_version_policy::Specific;
+
+    fn input(dims: Vec<i64>) -> pb::ModelInput {
+        pb::ModelInput {
+            name: "x".to_string(),
+            data_type: DataType::TypeFp32 as i32,
+            dims,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn maps_negative_dims_to_dynamic() {
+        let config = pb::ModelConfig {
+            max_batch_size: 8,
+            input: vec![pb::ModelInput {
+                reshape: Some(pb::ModelTensorReshape { shape: vec![-1] }),
+                ..input(vec![-1, 3])
+            }],
+            ..Default::default()
+        };
+        let view = ModelConfigView::try_from(config).unwrap();
+        let spec = view.input("x").unwrap();
+        assert_eq!(spec.dims, vec![Dim::Dynamic, Dim::Fixed(3)]);
+        assert_eq!(spec.reshape, Some(vec![Dim::Dynamic]));
+        assert!(spec.has_dynamic_dims());
+        assert_eq!(spec.element_count(), None);
+        assert!(spec.dims[0].accepts(5));
+        assert!(!spec.dims[0].accepts(-1));
+        assert!(!spec.dims[1].accepts(4));
+        assert_eq!(i64::from(Dim::Dynamic), -1);
+        assert!(view.supports_batching());
+        assert_eq!(view.max_batch_size(), 8);
+
+        let view = ModelConfigView::try_from(pb::ModelConfig {
+            input: vec![input(vec![2, 3])],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(view.inputs()[0].element_count(), Some(6));
+        assert!(!view.supports_batching());
+    }
+
+    #[test]
+    fn defaults_to_the_latest_version() {
+        let view = ModelConfigView::try_from(pb::ModelConfig::default()).unwrap();
+        assert_eq!(view.version_policy(), &VersionPolicy::Latest(1));
+
+        let config = pb::ModelConfig {
+            version_policy: Some(pb::ModelVersionPolicy {
+                policy_choice: Some(PolicyChoice::Specific(Specific {
+                    versions: vec![1, 3],
+                })),
+            }),
+            ..Default::default()
+        };
+        let view = ModelConfigView::try_from(config).unwrap();
+        assert_eq!(view.version_policy(), &VersionPolicy::Specific(vec![1, 3]));
+    }
+
+    #[test]
+    fn maps_the_scheduling_choice() {
+        let config = pb::ModelConfig {
+            scheduling_choice: Some(SchedulingChoice::DynamicBatching(
+                pb::ModelDynamicBatching {
+                    preferred_batch_size: vec![4, 8],
+                    max_queue_delay_microseconds: 1500,
+                    preserve_ordering: true,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+        let view = ModelConfigView::try_from(config.clone()).unwrap();
+        assert_eq!(
+            view.dynamic_batching(),
+            Some(&DynamicBatching {
+                preferred_batch_sizes: vec![4, 8],
+                max_queue_delay: Duration::from_micros(1500),
+                preserve_ordering: true,
+                priority_levels: 0,
+                default_priority_level: 0,
+            })
+        );
+        assert!(view.sequence_batching().is_none());
+        assert!(!view.is_ensemble());
+        assert_eq!(view.as_pb(), &config);
+        assert_eq!(view.into_inner(), config);
+
+        let config = pb::ModelConfig {
+            scheduling_choice: Some(SchedulingChoice::SequenceBatching(
+                pb::ModelSequenceBatching {
+                    max_sequence_idle_microseconds: 2_000_000,
+                    control_input: vec![ControlInput {
+                        name: "START".to_string(),
+                        ..Default::default()
+                    }],
+                    strategy_choice: Some(StrategyChoice::Oldest(StrategyOldest {
+                        max_candidate_sequences: 4,
+                        preferred_batch_size: vec![2],
+                        max_queue_delay_microseconds: 100,
+                        preserve_ordering: false,
+                    })),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+        let view = ModelConfigView::try_from(config).unwrap();
+        assert!(view.dynamic_batching().is_none());
+        assert_eq!(
+            view.sequence_batching(),
+            Some(&SequenceBatching {
+                max_sequence_idle: Duration::from_secs(2),
+                strategy: Some(SequenceStrategy::Oldest {
+                    max_candidate_sequences: 4,
+                    preferred_batch_sizes: vec![2],
+                    max_queue_delay: Duration::from_micros(100),
+                    preserve_ordering: false,
+                }),
+                control_inputs: vec!["START".to_string()],
+                iterative_sequence: false,
+            })
+        );
+
+        let config = pb::ModelConfig {
+            scheduling_choice: Some(SchedulingChoice::EnsembleScheduling(
+                pb::ModelEnsembling::default(),
+            )),
+            ..Default::default()
+        };
+        let view = ModelConfigView::try_from(config).unwrap();
+        assert!(view.is_ensemble());
+        assert!(view.dynamic_batching().is_none() && view.sequence_batching().is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_data_types() {
+        let config = pb::ModelConfig {
+            input: vec![pb::ModelInput {
+                data_type: 999,
+                ..input(vec![1])
+            }],
+            ..Default::default()
+        };
+        assert!(ModelConfigView::try_from(config).is_err());
+        assert!(ModelConfigView::try_from(pb::ModelConfigResponse { config: None }).is_err());
+    }
+}
@@ -0,0 +1,883 @@
+use super::client::{Error, Result};
+use super::output::ArrayOutputOneOf;
+use super::pb::InferTensorContents;
+use crate::types::{Bytes, TritonDataTypes};
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+use ndarray::ArrayD;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// A tensor of a NumPy `.npy` file, data laid out as Triton expects raw contents: little-endian,
+/// C order and `BYTES` elements prefixed by their u32 length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpyArray {
+    pub datatype: TritonDataTypes,
+    pub shape: Vec<usize>,
+    pub data: Bytes,
+}
+
+impl NpyArray {
+    pub fn element_count(&self) -> usize {
+        self.shape.iter().product()
+    }
+}
+
+fn npy_error(message: String) -> Error {
+    Error::ConversionError(format!("Invalid .npy data: {message}"))
+}
+
+// The header dict, e.g. `{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }`.
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+// Text following `'key':` in the header dict.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let quoted = format!("'{key}'");
+    let start = header
+        .find(&quoted)
+        .ok_or_else(|| npy_error(format!("header `{header}` has no `{key}`")))?;
+    let rest = header[start + quoted.len()..].trim_start();
+    rest.strip_prefix(':')
+        .map(str::trim_start)
+        .ok_or_else(|| npy_error(format!("header `{header}` has no value for `{key}`")))
+}
+
+fn parse_header(header: &str) -> Result<Header> {
+    let descr = header_value(header, "descr")?;
+    let descr = descr
+        .strip_prefix('\'')
+        .and_then(|descr| descr.split_once('\''))
+        .map(|(descr, _)| descr.to_string())
+        .ok_or_else(|| npy_error(format!("`descr` of `{header}` is not a string")))?;
+    let fortran_order = match header_value(header, "fortran_order")? {
+        value if value.starts_with("True") => true,
+        value if value.starts_with("False") => false,
+        _ => {
+            return Err(npy_error(format!(
+                "`fortran_order` of `{header}` is not a bool"
+            )))
+        }
+    };
+    let shape = header_value(header, "shape")?
+        .strip_prefix('(')
+        .and_then(|shape| shape.split_once(')'))
+        .map(|(shape, _)| shape)
+        .ok_or_else(|| npy_error(format!("`shape` of `{header}` is not a tuple")))?
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| {
+            dim.parse::<usize>()
+                .map_err(|_| npy_error(format!("invalid dimension `{dim}` in `{header}`")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Header {
+        descr,
+        fortran_order,
+        shape,
+    })
+}
+
+// Element type of a dtype like `<f4`, `>i8`, `|b1` or `<U5`.
+struct Dtype {
+    datatype: TritonDataTypes,
+    // Bytes per element in the file
+    size: usize,
+    big_endian: bool,
+    // Fixed width strings, `S` of bytes or `U` of UTF-32 code points
+    text: Option<char>,
+}
+
+fn dtype(descr: &str) -> Result<Dtype> {
+    let (order, kind) = descr.split_at_checked(1).unwrap_or_default();
+    let big_endian = match order {
+        "<" | "|" | "=" => false,
+        ">" => true,
+        _ => return Err(npy_error(format!("dtype `{descr}` has no byte order"))),
+    };
+    let numeric = |datatype: TritonDataTypes| {
+        let size = datatype.size_of().unwrap_or(1);
+        (datatype, size, None)
+    };
+    let (datatype, size, text) = match kind {
+        "b1" => numeric(TritonDataTypes::BOOL),
+        "i1" => numeric(TritonDataTypes::INT8),
+        "i2" => numeric(TritonDataTypes::INT16),
+        "i4" => numeric(TritonDataTypes::INT32),
+        "i8" => numeric(TritonDataTypes::INT64),
+        "u1" => numeric(TritonDataTypes::UINT8),
+        "u2" => numeric(TritonDataTypes::UINT16),
+        "u4" => numeric(TritonDataTypes::UINT32),
+        "u8" => numeric(TritonDataTypes::UINT64),
+        "f2" => numeric(TritonDataTypes::FP16),
+        "f4" => numeric(TritonDataTypes::FP32),
+        "f8" => numeric(TritonDataTypes::FP64),
+        _ => {
+            let (code, width) = kind.split_at_checked(1).unwrap_or_default();
+            let width = width.parse::<usize>().ok().filter(|width| *width > 0);
+            match (code, width) {
+                ("S", Some(width)) => (TritonDataTypes::BYTES, width, Some('S')),
+                ("U", Some(width)) => match width.checked_mul(4) {
+                    Some(size) => (TritonDataTypes::BYTES, size, Some('U')),
+                    None => return Err(npy_error(format!("dtype `{descr}` is too wide"))),
+                },
+                _ => return Err(npy_error(format!("unsupported dtype `{descr}`"))),
+            }
+        }
+    };
+    Ok(Dtype {
+        datatype,
+        size,
+        big_endian,
+        text,
+    })
+}
+
+// Reorder the elements of a Fortran-order (column major) array to C order.
+fn fortran_to_c(data: &[u8], shape: &[usize], size: usize) -> Vec<u8> {
+    let mut strides = vec![size; shape.len()];
+    for dim in 1..shape.len() {
+        strides[dim] = strides[dim - 1] * shape[dim - 1];
+    }
+    let mut index = vec![0; shape.len()];
+    let mut c_order = Vec::with_capacity(data.len());
+    for _ in 0..shape.iter().product::<usize>() {
+        let offset = index
+            .iter()
+            .zip(&strides)
+            .map(|(i, s)| i * s)
+            .sum::<usize>();
+        c_order.extend_from_slice(&data[offset..offset + size]);
+        for dim in (0..shape.len()).rev() {
+            index[dim] += 1;
+            if index[dim] < shape[dim] {
+                break;
+            }
+            index[dim] = 0;
+        }
+    }
+    c_order
+}
+
+// Fixed width little-endian strings as length prefixed elements, without the NUL padding.
+fn encode_text(data: &[u8], count: usize, size: usize, code: char) -> Result<Bytes> {
+    let mut encoded = vec![];
+    for element in (0..count).map(|i| &data[i * size..(i + 1) * size]) {
+        let text = match code {
+            'S' => element.to_vec(),
+            _ => element
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .filter(|c| *c != 0)
+                .map(|c| {
+                    char::from_u32(c).ok_or_else(|| npy_error(format!("invalid code point {c:#x}")))
+                })
+                .collect::<Result<String>>()?
+                .into_bytes(),
+        };
+        let len = text
+            .iter()
+            .rposition(|b| *b != 0)
+            .map_or(0, |last| last + 1);
+        encoded.extend_from_slice(&(len as u32).to_le_bytes());
+        encoded.extend_from_slice(&text[..len]);
+    }
+    Ok(encoded)
+}
+
+// Elements of length prefixed `BYTES` raw contents.
+fn split_bytes(mut data: &[u8]) -> Result<Vec<Bytes>> {
+    let mut elements = vec![];
+    while let [a, b, c, d, rest @ ..] = data {
+        let len = u32::from_le_bytes([*a, *b, *c, *d]) as usize;
+        if rest.len() < len {
+            break;
+        }
+        elements.push(rest[..len].to_vec());
+        data = &rest[len..];
+    }
+    match data.is_empty() {
+        true => Ok(elements),
+        false => Err(npy_error("truncated BYTES element".to_string())),
+    }
+}
+
+/// Parse the content of a `.npy` file. Big-endian and Fortran-order arrays are converted, fixed
+/// width `S` and `U` strings become `BYTES` without their NUL padding.
+pub fn parse_npy(bytes: &[u8]) -> Result<NpyArray> {
+    let rest = bytes
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| npy_error("missing magic string".to_string()))?;
+    let (header_len, rest) = match rest {
+        [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+        [2 | 3, _, a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+        _ => return Err(npy_error("unsupported format version".to_string())),
+    };
+    if rest.len() < header_len {
+        return Err(npy_error("truncated header".to_string()));
+    }
+    let (header, data) = rest.split_at(header_len);
+    let header =
+        std::str::from_utf8(header).map_err(|_| npy_error("header is not text".to_string()))?;
+    let header = parse_header(header)?;
+    let dtype = dtype(&header.descr)?;
+    let too_large = || npy_error(format!("shape {:?} is too large", header.shape));
+    let count = header
+        .shape
+        .iter()
+        .try_fold(1usize, |count, dim| count.checked_mul(*dim))
+        .ok_or_else(too_large)?;
+    let size = count.checked_mul(dtype.size).ok_or_else(too_large)?;
+    if data.len() != size {
+        return Err(npy_error(format!(
+            "shape {:?} of `{}` needs {size} bytes of data, found {}",
+            header.shape,
+            header.descr,
+            data.len()
+        )));
+    }
+
+    let mut data = match header.fortran_order && header.shape.len() > 1 {
+        true => fortran_to_c(data, &header.shape, dtype.size),
+        false => data.to_vec(),
+    };
+    let unit = match dtype.text {
+        Some('S') => 1,
+        Some(_) => 4,
+        None => dtype.size,
+    };
+    if dtype.big_endian && unit > 1 {
+        data.chunks_exact_mut(unit).for_each(<[u8]>::reverse);
+    }
+    if let Some(code) = dtype.text {
+        data = encode_text(&data, count, dtype.size, code)?;
+    }
+    Ok(NpyArray {
+        datatype: dtype.datatype,
+        shape: header.shape,
+        data,
+    })
+}
+
+pub fn read_npy<P: AsRef<Path>>(path: P) -> Result<NpyArray> {
+    parse_npy(&fs::read(path)?)
+}
+
+// `.npy` magic, version and header for a C-order array, padded so the data is 64 byte aligned.
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [dim] => format!("({dim},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let dict = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+    let padded = |prefix: usize| {
+        let len = prefix + dict.len() + 1;
+        format!("{dict}{}\n", " ".repeat((64 - len % 64) % 64))
+    };
+    let mut header = MAGIC.to_vec();
+    let dict = padded(MAGIC.len() + 4);
+    match u16::try_from(dict.len()) {
+        Ok(len) => {
+            header.extend_from_slice(&[1, 0]);
+            header.extend_from_slice(&len.to_le_bytes());
+            header.extend_from_slice(dict.as_bytes());
+        }
+        Err(_) => {
+            let dict = padded(MAGIC.len() + 6);
+            header.extend_from_slice(&[2, 0]);
+            header.extend_from_slice(&(dict.len() as u32).to_le_bytes());
+            header.extend_from_slice(dict.as_bytes());
+        }
+    }
+    header
+}
+
+// Fixed width little-endian values, in C order.
+fn le_bytes<T: Copy, const N: usize>(array: &ArrayD<T>, to_le: fn(T) -> [u8; N]) -> Bytes {
+    array.iter().flat_map(|value| to_le(*value)).collect()
+}
+
+// Fixed width chunks of raw contents.
+fn le_chunks<const N: usize>(data: &[u8]) -> impl Iterator<Item = [u8; N]> + '_ {
+    data.chunks_exact(N)
+        .map(|chunk| <[u8; N]>::try_from(chunk).unwrap_or([0; N]))
+}
+
+impl NpyArray {
+    /// Typed contents of the array, `FP16` has none and only goes as raw contents.
+    pub fn contents(&self) -> Result<InferTensorContents> {
+        let data = &self.data;
+        let mut contents = InferTensorContents::default();
+        match self.datatype {
+            TritonDataTypes::BOOL => {
+                contents.bool_contents = data.iter().map(|b| *b != 0).collect()
+            }
+            TritonDataTypes::INT8 => {
+                contents.int_contents = data.iter().map(|b| *b as i8 as i32).collect()
+            }
+            TritonDataTypes::INT16 => {
+                contents.int_contents = le_chunks(data)
+                    .map(|b| i16::from_le_bytes(b) as i32)
+                    .collect()
+            }
+            TritonDataTypes::INT32 => {
+                contents.int_contents = le_chunks(data).map(i32::from_le_bytes).collect()
+            }
+            TritonDataTypes::INT64 => {
+                contents.int64_contents = le_chunks(data).map(i64::from_le_bytes).collect()
+            }
+            TritonDataTypes::UINT8 => {
+                contents.uint_contents = data.iter().map(|b| *b as u32).collect()
+            }
+            TritonDataTypes::UINT16 => {
+                contents.uint_contents = le_chunks(data)
+                    .map(|b| u16::from_le_bytes(b) as u32)
+                    .collect()
+            }
+            TritonDataTypes::UINT32 => {
+                contents.uint_contents = le_chunks(data).map(u32::from_le_bytes).collect()
+            }
+            TritonDataTypes::UINT64 => {
+                contents.uint64_contents = le_chunks(data).map(u64::from_le_bytes).collect()
+            }
+            TritonDataTypes::FP32 => {
+                contents.fp32_contents = le_chunks(data).map(f32::from_le_bytes).collect()
+            }
+            TritonDataTypes::FP64 => {
+                contents.fp64_contents = le_chunks(data).map(f64::from_le_bytes).collect()
+            }
+            TritonDataTypes::BYTES => contents.bytes_contents = split_bytes(data)?,
+            TritonDataTypes::FP16 | TritonDataTypes::BF16 => {
+                return Err(Error::ConversionError(format!(
+                    "{} has no typed contents, send it as raw input contents",
+                    self.datatype
+                )))
+            }
+        }
+        Ok(contents)
+    }
+
+    /// The array as a `.npy` file, `BYTES` as fixed width `S` strings padded with NULs.
+    pub fn to_npy(&self) -> Result<Vec<u8>> {
+        let (descr, data) = match self.datatype {
+            TritonDataTypes::BOOL => ("|b1".to_string(), None),
+            TritonDataTypes::INT8 => ("|i1".to_string(), None),
+            TritonDataTypes::INT16 => ("<i2".to_string(), None),
+            TritonDataTypes::INT32 => ("<i4".to_string(), None),
+            TritonDataTypes::INT64 => ("<i8".to_string(), None),
+            TritonDataTypes::UINT8 => ("|u1".to_string(), None),
+            TritonDataTypes::UINT16 => ("<u2".to_string(), None),
+            TritonDataTypes::UINT32 => ("<u4".to_string(), None),
+            TritonDataTypes::UINT64 => ("<u8".to_string(), None),
+            TritonDataTypes::FP16 => ("<f2".to_string(), None),
+            TritonDataTypes::FP32 => ("<f4".to_string(), None),
+            TritonDataTypes::FP64 => ("<f8".to_string(), None),
+            TritonDataTypes::BYTES => {
+                let elements = split_bytes(&self.data)?;
+                let width = elements
+                    .iter()
+                    .map(Vec::len)
+                    .max()
+                    .unwrap_or_default()
+                    .max(1);
+                let mut data = Vec::with_capacity(elements.len() * width);
+                for element in elements {
+                    data.extend_from_slice(&element);
+                    data.resize(data.len() + width - element.len(), 0);
+                }
+                (format!("|S{width}"), Some(data))
+            }
+            TritonDataTypes::BF16 => {
+                return Err(Error::ConversionError(
+                    "NumPy has no dtype for BF16".to_string(),
+                ))
+            }
+        };
+        let mut npy = npy_header(&descr, &self.shape);
+        npy.extend_from_slice(data.as_deref().unwrap_or(&self.data));
+        Ok(npy)
+    }
+
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, self.to_npy()?)?)
+    }
+}
+
+impl From<&ArrayOutputOneOf> for NpyArray {
+    fn from(output: &ArrayOutputOneOf) -> Self {
+        let (datatype, data) = match output {
+            ArrayOutputOneOf::BOOL(array) => (
+                TritonDataTypes::BOOL,
+                array.iter().map(|v| *v as u8).collect(),
+            ),
+            ArrayOutputOneOf::INT8(array) => {
+                (TritonDataTypes::INT8, le_bytes(array, i8::to_le_bytes))
+            }
+            ArrayOutputOneOf::INT16(array) => {
+                (TritonDataTypes::INT16, le_bytes(array, i16::to_le_bytes))
+            }
+            ArrayOutputOneOf::INT32(array) => {
+                (TritonDataTypes::INT32, le_bytes(array, i32::to_le_bytes))
+            }
+            ArrayOutputOneOf::INT64(array) => {
+                (TritonDataTypes::INT64, le_bytes(array, i64::to_le_bytes))
+            }
+            ArrayOutputOneOf::UINT8(array) => {
+                (TritonDataTypes::UINT8, le_bytes(array, u8::to_le_bytes))
+            }
+            ArrayOutputOneOf::UINT16(array) => {
+                (TritonDataTypes::UINT16, le_bytes(array, u16::to_le_bytes))
+            }
+            ArrayOutputOneOf::UINT32(array) => {
+                (TritonDataTypes::UINT32, le_bytes(array, u32::to_le_bytes))
+            }
+            ArrayOutputOneOf::UINT64(array) => {
+                (TritonDataTypes::UINT64, le_bytes(array, u64::to_le_bytes))
+            }
+            ArrayOutputOneOf::FP32(array) => {
+                (TritonDataTypes::FP32, le_bytes(array, f32::to_le_bytes))
+            }
+            ArrayOutputOneOf::FP64(array) => {
+                (TritonDataTypes::FP64, le_bytes(array, f64::to_le_bytes))
+            }
+            ArrayOutputOneOf::BYTES(array) => (
+                TritonDataTypes::BYTES,
+                array
+                    .iter()
+                    .flat_map(|element| {
+                        let len = (element.len() as u32).to_le_bytes();
+                        len.into_iter().chain(element.iter().copied())
+                    })
+                    .collect(),
+            ),
+        };
+        Self {
+            datatype,
+            shape: output.shape().to_vec(),
+            data,
+        }
+    }
+}
+
+fn npz_error(message: String) -> Error {
+    Error::ConversionError(format!("Invalid .npz data: {message}"))
+}
+
+const LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+const CENTRAL_HEADER: &[u8] = b"PK\x01\x02";
+const END_OF_CENTRAL_DIRECTORY: &[u8] = b"PK\x05\x06";
+const ZIP64_LOCATOR: &[u8] = b"PK\x06\x07";
+
+// Little-endian integer of `N` bytes at `offset`, zero extended.
+fn le_at<const N: usize>(bytes: &[u8], offset: usize) -> Result<u64> {
+    let field = bytes
+        .get(offset..offset + N)
+        .ok_or_else(|| npz_error("truncated archive".to_string()))?;
+    Ok(field
+        .iter()
+        .rev()
+        .fold(0, |value, byte| value << 8 | *byte as u64))
+}
+
+// Central directory offset and entry count, from the zip64 record when the archive has one.
+fn central_directory(bytes: &[u8]) -> Result<(usize, usize)> {
+    let end = bytes
+        .windows(4)
+        .rposition(|window| window == END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(|| npz_error("not a zip archive".to_string()))?;
+    let entries = le_at::<2>(bytes, end + 10)?;
+    let offset = le_at::<4>(bytes, end + 16)?;
+    if entries != 0xffff && offset != 0xffff_ffff {
+        return Ok((offset as usize, entries as usize));
+    }
+    let locator = end
+        .checked_sub(20)
+        .filter(|locator| bytes[*locator..].starts_with(ZIP64_LOCATOR))
+        .ok_or_else(|| npz_error("missing zip64 end of central directory".to_string()))?;
+    let record = le_at::<8>(bytes, locator + 8)? as usize;
+    Ok((
+        le_at::<8>(bytes, record + 48)? as usize,
+        le_at::<8>(bytes, record + 32)? as usize,
+    ))
+}
+
+/// Arrays of a NumPy `.npz` archive by name, without the `.npy` suffix. Reads the stored and
+/// deflated entries written by `np.savez` and `np.savez_compressed`.
+pub fn parse_npz(bytes: &[u8]) -> Result<Vec<(String, NpyArray)>> {
+    let (mut offset, entries) = central_directory(bytes)?;
+    let mut arrays = Vec::with_capacity(entries);
+    for _ in 0..entries {
+        if !bytes[offset.min(bytes.len())..].starts_with(CENTRAL_HEADER) {
+            return Err(npz_error("invalid central directory".to_string()));
+        }
+        let method = le_at::<2>(bytes, offset + 10)?;
+        let crc = le_at::<4>(bytes, offset + 16)? as u32;
+        let mut compressed = le_at::<4>(bytes, offset + 20)?;
+        let mut size = le_at::<4>(bytes, offset + 24)?;
+        let name_len = le_at::<2>(bytes, offset + 28)? as usize;
+        let extra_len = le_at::<2>(bytes, offset + 30)? as usize;
+        let comment_len = le_at::<2>(bytes, offset + 32)? as usize;
+        let mut local = le_at::<4>(bytes, offset + 42)?;
+        let name = bytes
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| npz_error("truncated archive".to_string()))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // Zip64 extra field, holding the sizes and offset that do not fit in 32 bits
+        let mut extra = offset + 46 + name_len;
+        let extra_end = extra + extra_len;
+        while extra + 4 <= extra_end {
+            let id = le_at::<2>(bytes, extra)?;
+            let len = le_at::<2>(bytes, extra + 2)? as usize;
+            if id == 1 {
+                let mut field = extra + 4;
+                for value in [&mut size, &mut compressed, &mut local] {
+                    if *value == 0xffff_ffff && field + 8 <= extra + 4 + len {
+                        *value = le_at::<8>(bytes, field)?;
+                        field += 8;
+                    }
+                }
+            }
+            extra += 4 + len;
+        }
+        offset = extra_end + comment_len;
+        if name.ends_with('/') {
+            continue;
+        }
+
+        let local = local as usize;
+        if !bytes[local.min(bytes.len())..].starts_with(LOCAL_HEADER) {
+            return Err(npz_error(format!("invalid local header of `{name}`")));
+        }
+        let start = local
+            + 30
+            + le_at::<2>(bytes, local + 26)? as usize
+            + le_at::<2>(bytes, local + 28)? as usize;
+        let raw = bytes
+            .get(start..start + compressed as usize)
+            .ok_or_else(|| npz_error(format!("truncated entry `{name}`")))?;
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut data = Vec::with_capacity(size as usize);
+                DeflateDecoder::new(raw)
+                    .read_to_end(&mut data)
+                    .map_err(|e| npz_error(format!("entry `{name}`: {e}")))?;
+                data
+            }
+            _ => {
+                return Err(npz_error(format!(
+                    "entry `{name}` uses unsupported compression method {method}"
+                )))
+            }
+        };
+        let mut checksum = Crc::new();
+        checksum.update(&data);
+        if data.len() as u64 != size || checksum.sum() != crc {
+            return Err(npz_error(format!("entry `{name}` is corrupt")));
+        }
+        let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+        arrays.push((name, parse_npy(&data)?));
+    }
+    Ok(arrays)
+}
+
+pub fn read_npz<P: AsRef<Path>>(path: P) -> Result<Vec<(String, NpyArray)>> {
+    parse_npz(&fs::read(path)?)
+}
+
+/// A `.npz` archive of the arrays, each stored uncompressed as `<name>.npy` like `np.savez`.
+pub fn npz_bytes<'a, I>(arrays: I) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = (&'a str, &'a NpyArray)>,
+{
+    let too_large = || npz_error("archives over 4 GiB are not supported".to_string());
+    // Version 2.0, UTF-8 names, stored, 1980-01-01 00:00
+    let entry_header = |header: &mut Vec<u8>, crc: u32, size: u32, name: &str| {
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0x0800u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&0x0021u16.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+    };
+
+    let mut archive = vec![];
+    let mut central = vec![];
+    let mut entries = 0u16;
+    for (name, array) in arrays {
+        let name = format!("{name}.npy");
+        let data = array.to_npy()?;
+        let mut checksum = Crc::new();
+        checksum.update(&data);
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(archive.len()).map_err(|_| too_large())?;
+
+        archive.extend_from_slice(LOCAL_HEADER);
+        entry_header(&mut archive, checksum.sum(), size, &name);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&data);
+
+        central.extend_from_slice(CENTRAL_HEADER);
+        central.extend_from_slice(&20u16.to_le_bytes());
+        entry_header(&mut central, checksum.sum(), size, &name);
+        // No comment, disk 0, no attributes
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        entries = entries
+            .checked_add(1)
+            .ok_or_else(|| npz_error("more than 65535 arrays".to_string()))?;
+    }
+
+    let offset = u32::try_from(archive.len()).map_err(|_| too_large())?;
+    let central_len = u32::try_from(central.len()).map_err(|_| too_large())?;
+    archive.extend_from_slice(&central);
+    archive.extend_from_slice(END_OF_CENTRAL_DIRECTORY);
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&entries.to_le_bytes());
+    archive.extend_from_slice(&entries.to_le_bytes());
+    archive.extend_from_slice(&central_len.to_le_bytes());
+    archive.extend_from_slice(&offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    Ok(archive)
+}
+
+pub fn write_npz<'a, P, I>(path: P, arrays: I) -> Result<()>
+where
+    P: AsRef<Path>,
+    I: IntoIterator<Item = (&'a str, &'a NpyArray)>,
+{
+    Ok(fs::write(path, npz_bytes(arrays)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    // A version 1.0 `.npy` file, the header is not padded.
+    fn npy(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let fortran_order = if fortran_order { "True" } else { "False" };
+        let dict = format!(
+            "{{'descr': '{descr}', 'fortran_order': {fortran_order}, 'shape': {shape}, }}\n"
+        );
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(dict.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn array(datatype: TritonDataTypes, shape: &[usize], data: Bytes) -> NpyArray {
+        NpyArray {
+            datatype,
+            shape: shape.to_vec(),
+            data,
+        }
+    }
+
+    fn le<const N: usize>(values: impl IntoIterator<Item = [u8; N]>) -> Bytes {
+        values.into_iter().flatten().collect()
+    }
+
+    #[test]
+    fn parses_little_endian_c_order() {
+        let data = le([1.5f32, -2.0, 3.25].map(f32::to_le_bytes));
+        let parsed = parse_npy(&npy("<f4", false, "(3,)", &data)).unwrap();
+        assert_eq!(parsed, array(TritonDataTypes::FP32, &[3], data));
+        assert_eq!(
+            parsed.contents().unwrap().fp32_contents,
+            vec![1.5, -2.0, 3.25]
+        );
+    }
+
+    #[test]
+    fn converts_big_endian() {
+        let data = le([1i32, -2, 300].map(i32::to_be_bytes));
+        let parsed = parse_npy(&npy(">i4", false, "(3,)", &data)).unwrap();
+        assert_eq!(parsed.data, le([1i32, -2, 300].map(i32::to_le_bytes)));
+        assert_eq!(parsed.contents().unwrap().int_contents, vec![1, -2, 300]);
+    }
+
+    #[test]
+    fn converts_fortran_order() {
+        // [[1, 2, 3], [4, 5, 6]] stored column by column
+        let data = le([1i16, 4, 2, 5, 3, 6].map(i16::to_le_bytes));
+        let parsed = parse_npy(&npy("<i2", true, "(2, 3)", &data)).unwrap();
+        assert_eq!(parsed.shape, vec![2, 3]);
+        assert_eq!(
+            parsed.contents().unwrap().int_contents,
+            vec![1, 2, 3, 4, 5, 6]
+        );
+
+        // Big-endian and Fortran order at once
+        let data = le([1u16, 4, 2, 5, 3, 6].map(u16::to_be_bytes));
+        let parsed = parse_npy(&npy(">u2", true, "(2, 3)", &data)).unwrap();
+        assert_eq!(
+            parsed.contents().unwrap().uint_contents,
+            vec![1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn converts_fixed_width_strings() {
+        let mut data = vec![];
+        for text in ["ab", "é", ""] {
+            let mut element = text.chars().map(|c| c as u32).collect::<Vec<_>>();
+            element.resize(2, 0);
+            data.extend(element.into_iter().flat_map(u32::to_be_bytes));
+        }
+        let parsed = parse_npy(&npy(">U2", false, "(3,)", &data)).unwrap();
+        assert_eq!(parsed.datatype, TritonDataTypes::BYTES);
+        assert_eq!(
+            parsed.contents().unwrap().bytes_contents,
+            vec![b"ab".to_vec(), "é".as_bytes().to_vec(), vec![]]
+        );
+
+        let parsed = parse_npy(&npy("|S3", false, "(2,)", b"ab\0xyz")).unwrap();
+        assert_eq!(parsed.data, b"\x02\0\0\0ab\x03\0\0\0xyz".to_vec());
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let invalid = [
+            npy("<f4", false, "(3,)", &[0; 8]),
+            npy("é4", false, "(1,)", &[0; 4]),
+            npy("<é", false, "(1,)", &[0; 4]),
+            npy("<U0", false, "(1,)", &[]),
+            npy("<c8", false, "(1,)", &[0; 8]),
+            npy("<f8", false, &format!("({}, {})", usize::MAX, 2), &[]),
+            npy("<U4611686018427387904", false, "(1,)", &[]),
+            b"\x93NUMPY\x01\x00\xff\x00{".to_vec(),
+        ];
+        for bytes in invalid {
+            assert!(
+                matches!(parse_npy(&bytes), Err(Error::ConversionError(_))),
+                "{}",
+                String::from_utf8_lossy(&bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_npy() {
+        let arrays = [
+            array(
+                TritonDataTypes::INT64,
+                &[2, 2],
+                le([1i64, 2, 3, 4].map(i64::to_le_bytes)),
+            ),
+            array(TritonDataTypes::BOOL, &[3], vec![1, 0, 1]),
+            array(
+                TritonDataTypes::FP64,
+                &[],
+                le([0.5f64].map(f64::to_le_bytes)),
+            ),
+        ];
+        for array in arrays {
+            let bytes = array.to_npy().unwrap();
+            // Data starts 64 byte aligned, as NumPy writes it
+            assert_eq!((bytes.len() - array.data.len()) % 64, 0);
+            assert_eq!(parse_npy(&bytes).unwrap(), array);
+        }
+
+        // BYTES go out as fixed width `S` strings and come back without the padding
+        let strings = array(
+            TritonDataTypes::BYTES,
+            &[2],
+            b"\x01\0\0\0a\x03\0\0\0bcd".to_vec(),
+        );
+        assert_eq!(parse_npy(&strings.to_npy().unwrap()).unwrap(), strings);
+    }
+
+    #[test]
+    fn round_trips_through_npz() {
+        let ints = array(
+            TritonDataTypes::INT32,
+            &[2],
+            le([7i32, 8].map(i32::to_le_bytes)),
+        );
+        let floats = array(
+            TritonDataTypes::FP32,
+            &[1, 1],
+            le([1f32].map(f32::to_le_bytes)),
+        );
+        let archive = npz_bytes([("ints", &ints), ("floats", &floats)]).unwrap();
+        assert_eq!(
+            parse_npz(&archive).unwrap(),
+            vec![("ints".to_string(), ints), ("floats".to_string(), floats)]
+        );
+    }
+
+    // A single entry archive deflated like `np.savez_compressed`.
+    fn deflated_npz(name: &str, npy: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+        encoder.write_all(npy).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut crc = Crc::new();
+        crc.update(npy);
+        let fields = |header: &mut Vec<u8>| {
+            header.extend_from_slice(&[20, 0, 0, 0, 8, 0, 0, 0, 0x21, 0]);
+            header.extend_from_slice(&crc.sum().to_le_bytes());
+            header.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(npy.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]);
+        };
+        let mut archive = LOCAL_HEADER.to_vec();
+        fields(&mut archive);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&compressed);
+        let central = archive.len() as u32;
+        archive.extend_from_slice(CENTRAL_HEADER);
+        archive.extend_from_slice(&[20, 0]);
+        fields(&mut archive);
+        archive.extend_from_slice(&[0; 14]);
+        archive.extend_from_slice(name.as_bytes());
+        let central_len = archive.len() as u32 - central;
+        archive.extend_from_slice(END_OF_CENTRAL_DIRECTORY);
+        archive.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        archive.extend_from_slice(&central_len.to_le_bytes());
+        archive.extend_from_slice(&central.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    #[test]
+    fn reads_deflated_npz() {
+        let data = le([1i32, -2, 300].map(i32::to_be_bytes));
+        let archive = deflated_npz("x.npy", &npy(">i4", false, "(3,)", &data));
+        let arrays = parse_npz(&archive).unwrap();
+        assert_eq!(arrays[0].0, "x");
+        assert_eq!(
+            arrays[0].1.contents().unwrap().int_contents,
+            vec![1, -2, 300]
+        );
+
+        let mut corrupt = archive.clone();
+        corrupt[LOCAL_HEADER.len() + 26 + "x.npy".len() + 2] ^= 0xff;
+        assert!(parse_npz(&corrupt).is_err());
+    }
+}
@@ -1,8 +1,12 @@
-use super::client::Result;
+use super::client::{Error, Result};
+use super::macros::{map_array_output, match_array_output};
+use super::npy::{self, NpyArray};
 use super::pb::ModelInferResponse;
 use crate::types::{Bytes, TritonDataTypes};
-use ndarray::ArrayD;
+use ndarray::{ArrayD, Axis, Slice};
 use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
 
 #[derive(Debug)]
 pub enum ArrayOutputOneOf {
@@ -20,6 +24,32 @@ pub enum ArrayOutputOneOf {
     BYTES(ArrayD<Bytes>),
 }
 
+impl ArrayOutputOneOf {
+    pub fn shape(&self) -> &[usize] {
+        match_array_output!(self, array => array.shape())
+    }
+
+    /// Rows `range` of the first (batch) dimension, panics when out of bounds.
+    pub fn slice_batch(&self, range: Range<usize>) -> Self {
+        map_array_output!(self, array => array.slice_axis(Axis(0), Slice::from(range)).to_owned())
+    }
+
+    /// Row `index` of the first (batch) dimension without that dimension, panics when out of
+    /// bounds.
+    pub fn index_batch(&self, index: usize) -> Self {
+        map_array_output!(self, array => array.index_axis(Axis(0), index).to_owned())
+    }
+
+    /// The array as a `.npy` file, `BYTES` as fixed width `S` strings padded with NULs.
+    pub fn to_npy(&self) -> Result<Vec<u8>> {
+        NpyArray::from(self).to_npy()
+    }
+
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        NpyArray::from(self).write_npy(path)
+    }
+}
+
 fn vec_u8_to_vec_t<T: Sized>(data: Vec<u8>) -> Vec<T> {
     let ratio = std::mem::size_of::<T>() / std::mem::size_of::<u8>();
     let capacity = data.len() / ratio;
@@ -43,7 +73,7 @@ fn vec_u8_to_bytes(data: Vec<u8>) -> Vec<Bytes> {
     let mut offset = 0;
     let mut vec = Vec::<Bytes>::new();
     while offset < data.len() {
-        let length = u32::from_be_bytes([
+        let length = u32::from_le_bytes([
             data[offset],
             data[offset + 1],
             data[offset + 2],
@@ -62,6 +92,11 @@ pub struct ModelOutput {
     inner: HashMap<String, ArrayOutputOneOf>,
 }
 
+// Whether `name` stays one file of the directory it is joined to.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
 impl ModelOutput {
     pub fn new(response: ModelInferResponse) -> Result<Self> {
         let mut inner = HashMap::new();
@@ -147,4 +182,168 @@ impl ModelOutput {
     pub fn into_inner(self) -> HashMap<String, ArrayOutputOneOf> {
         self.inner
     }
+
+    /// Write every output to `<dir>/<name>.npy`, creating `dir` if needed. Names that are not a
+    /// plain file name, e.g. with a `/`, `\` or `..`, are rejected before anything is written.
+    pub fn write_npy_dir<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        if let Some(name) = self.inner.keys().find(|name| !is_file_name(name)) {
+            return Err(Error::ConversionError(format!(
+                "Output `{name}` can not be written as a file name"
+            )));
+        }
+        std::fs::create_dir_all(&dir)?;
+        for (name, array) in &self.inner {
+            array.write_npy(dir.as_ref().join(format!("{name}.npy")))?;
+        }
+        Ok(())
+    }
+
+    /// Write every output to one `.npz` archive, sorted by name.
+    pub fn write_npz<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut arrays = self
+            .inner
+            .iter()
+            .map(|(name, array)| (name.as_str(), NpyArray::from(array)))
+            .collect::<Vec<_>>();
+        arrays.sort_by_key(|(name, _)| *name);
+        npy::write_npz(path, arrays.iter().map(|(name, array)| (*name, array)))
+    }
+
+    /// Size of the first dimension shared by every output, `None` without outputs.
+    pub fn batch_size(&self) -> Result<Option<usize>> {
+        let mut batch_size = None;
+        for (name, array) in &self.inner {
+            let size = *array.shape().first().ok_or_else(|| {
+                Error::InvalidBatch(format!("output `{name}` has no batch dimension"))
+            })?;
+            match batch_size {
+                Some(expected) if expected != size => {
+                    return Err(Error::InvalidBatch(format!(
+                        "output `{name}` has batch size {size}, other outputs have {expected}"
+                    )));
+                }
+                _ => batch_size = Some(size),
+            }
+        }
+        Ok(batch_size)
+    }
+
+    /// Split along the batch dimension into consecutive outputs of `sizes` rows each.
+    pub fn split_batch(self, sizes: &[usize]) -> Result<Vec<ModelOutput>> {
+        let total = sizes.iter().sum::<usize>();
+        if let Some(batch_size) = self.batch_size()? {
+            if batch_size != total {
+                return Err(Error::InvalidBatch(format!(
+                    "outputs have batch size {batch_size}, {total} rows were requested"
+                )));
+            }
+        }
+        let mut start = 0;
+        Ok(sizes
+            .iter()
+            .map(|size| {
+                let range = start..start + size;
+                start += size;
+                ModelOutput {
+                    inner: self
+                        .inner
+                        .iter()
+                        .map(|(name, array)| (name.clone(), array.slice_batch(range.clone())))
+                        .collect(),
+                }
+            })
+            .collect())
+    }
+
+    /// One output per row of the batch dimension, with that dimension removed.
+    pub fn unstack(self) -> Result<Vec<ModelOutput>> {
+        let batch_size = self.batch_size()?.unwrap_or_default();
+        Ok((0..batch_size)
+            .map(|index| ModelOutput {
+                inner: self
+                    .inner
+                    .iter()
+                    .map(|(name, array)| (name.clone(), array.index_batch(index)))
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(rows: usize) -> ModelOutput {
+        let ints = ArrayD::from_shape_vec(vec![rows, 2], (0..2 * rows as i32).collect()).unwrap();
+        let names = (0..rows).map(|i| format!("n{i}").into_bytes()).collect();
+        let names = ArrayD::from_shape_vec(vec![rows], names).unwrap();
+        ModelOutput {
+            inner: HashMap::from([
+                ("ints".to_string(), ArrayOutputOneOf::INT32(ints)),
+                ("names".to_string(), ArrayOutputOneOf::BYTES(names)),
+            ]),
+        }
+    }
+
+    fn ints(output: &ModelOutput) -> &ArrayD<i32> {
+        match output.as_ndarray("ints") {
+            Some(ArrayOutputOneOf::INT32(array)) => array,
+            other => panic!("unexpected output {other:?}"),
+        }
+    }
+
+    #[test]
+    fn splits_a_batch_into_consecutive_rows() {
+        let parts = output(3).split_batch(&[1, 2]).unwrap();
+        assert_eq!(ints(&parts[0]).shape(), &[1, 2]);
+        assert_eq!(
+            ints(&parts[1]).iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+        assert!(output(3).split_batch(&[1, 1]).is_err());
+    }
+
+    #[test]
+    fn unstacks_one_output_per_row() {
+        let rows = output(3).unstack().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(ints(&rows[2]).shape(), &[2]);
+        assert_eq!(
+            ints(&rows[2]).iter().copied().collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        match rows[1].as_ndarray("names") {
+            Some(ArrayOutputOneOf::BYTES(names)) => assert_eq!(names[[]], b"n1".to_vec()),
+            other => panic!("unexpected output {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_outputs_of_different_batch_sizes() {
+        let mut output = output(3);
+        let other = ArrayD::from_shape_vec(vec![2], vec![1.0f32, 2.0]).unwrap();
+        output
+            .inner
+            .insert("other".to_string(), ArrayOutputOneOf::FP32(other));
+        assert!(matches!(output.batch_size(), Err(Error::InvalidBatch(_))));
+    }
+
+    #[test]
+    fn writes_one_npy_file_per_output() {
+        let dir = std::env::temp_dir().join(format!("tritonclient-npy-{}", std::process::id()));
+        output(2).write_npy_dir(&dir).unwrap();
+        let ints = npy::read_npy(dir.join("ints.npy")).unwrap();
+        assert_eq!(ints.shape, vec![2, 2]);
+        assert_eq!(ints.datatype, TritonDataTypes::INT32);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        for name in ["../ints", "a/b", "a\\b", ".."] {
+            let mut output = output(2);
+            let ints = output.pop("ints").unwrap();
+            output.inner.insert(name.to_string(), ints);
+            assert!(output.write_npy_dir(&dir).is_err(), "{name}");
+            assert!(!dir.exists());
+        }
+    }
 }
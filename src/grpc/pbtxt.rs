@@ -0,0 +1,356 @@
+use super::client::{Error, Result};
+use super::pb;
+use miette::Diagnostic;
+use prost::Message;
+use prost_reflect::text_format::FormatOptions;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, SerializeOptions,
+};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static DESCRIPTORS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/descriptors.bin"));
+
+fn descriptor_pool() -> &'static DescriptorPool {
+    static POOL: OnceLock<DescriptorPool> = OnceLock::new();
+    POOL.get_or_init(|| DescriptorPool::decode(DESCRIPTORS).expect("Invalid compiled descriptors!"))
+}
+
+fn model_config_descriptor() -> MessageDescriptor {
+    static DESCRIPTOR: OnceLock<MessageDescriptor> = OnceLock::new();
+    DESCRIPTOR
+        .get_or_init(|| {
+            descriptor_pool()
+                .get_message_by_name("inference.ModelConfig")
+                .expect("Missing inference.ModelConfig descriptor!")
+        })
+        .clone()
+}
+
+// 1-based line and column of a byte offset.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+// Triton configs write repeated messages as `input [ { .. } ]`, which is valid text format but
+// rejected by the parser without a colon: add the colon after such field names. Returns the
+// rewritten text and the original offsets where a colon was inserted.
+fn insert_list_colons(text: &str) -> (String, Vec<usize>) {
+    let bytes = text.as_bytes();
+    let mut output = String::with_capacity(text.len());
+    let mut insertions = vec![];
+    // end of the last identifier in field-name position, if nothing significant followed it
+    let mut field_end = None;
+    let mut after_colon = false;
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                field_end = None;
+                after_colon = false;
+            }
+            c if c.is_ascii_whitespace() => {}
+            c if c.is_ascii_digit() => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                field_end = None;
+                after_colon = false;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                field_end = (!after_colon).then_some(i);
+                after_colon = false;
+                continue;
+            }
+            b'[' => {
+                if let Some(end) = field_end {
+                    output.push_str(&text[copied..end]);
+                    output.push(':');
+                    insertions.push(end);
+                    copied = end;
+                }
+                field_end = None;
+                after_colon = false;
+            }
+            c => {
+                field_end = None;
+                after_colon = c == b':';
+            }
+        }
+        i += 1;
+    }
+    output.push_str(&text[copied..]);
+    (output, insertions)
+}
+
+pub(crate) fn json_error(error: serde_json::Error) -> Error {
+    // serde_json appends the position to its message
+    let message = error.to_string();
+    Error::ParseError {
+        line: error.line(),
+        column: error.column(),
+        message: message
+            .rsplit_once(" at line ")
+            .map_or(message.as_str(), |(message, _)| message)
+            .to_string(),
+    }
+}
+
+fn transcode(message: &DynamicMessage) -> Result<pb::ModelConfig> {
+    message
+        .transcode_to()
+        .map_err(|e| Error::ConversionError(e.to_string()))
+}
+
+fn to_dynamic(config: &pb::ModelConfig) -> DynamicMessage {
+    let mut message = DynamicMessage::new(model_config_descriptor());
+    message
+        .transcode_from(config)
+        .expect("ModelConfig matches its own descriptor");
+    message
+}
+
+/// Parse a `config.pbtxt` (protobuf text format).
+pub fn parse_model_config(text: &str) -> Result<pb::ModelConfig> {
+    let (rewritten, insertions) = insert_list_colons(text);
+    let message = DynamicMessage::parse_text_format(model_config_descriptor(), &rewritten)
+        .map_err(|e| {
+            let offset = e
+                .labels()
+                .and_then(|mut labels| labels.next())
+                .map_or(rewritten.len(), |label| label.offset());
+            let shift = insertions
+                .iter()
+                .enumerate()
+                .filter(|(index, at)| **at + index < offset)
+                .count();
+            let (line, column) = position(text, offset - shift);
+            Error::ParseError {
+                line,
+                column,
+                message: e.to_string(),
+            }
+        })?;
+    transcode(&message)
+}
+
+pub fn read_model_config<P: AsRef<Path>>(path: P) -> Result<pb::ModelConfig> {
+    parse_model_config(&fs::read_to_string(path)?)
+}
+
+/// Protobuf text format with one field per line, as Triton writes `config.pbtxt`.
+pub fn model_config_to_pbtxt(config: &pb::ModelConfig) -> String {
+    let mut text =
+        to_dynamic(config).to_text_format_with_options(&FormatOptions::new().pretty(true));
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+pub fn write_model_config<P: AsRef<Path>>(path: P, config: &pb::ModelConfig) -> Result<()> {
+    fs::write(path, model_config_to_pbtxt(config))?;
+    Ok(())
+}
+
+/// JSON as returned by Triton: proto field names, every scalar field printed and 64-bit
+/// integers as numbers.
+pub fn model_config_to_json(config: &pb::ModelConfig) -> serde_json::Value {
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .skip_default_fields(false)
+        .stringify_64_bit_integers(false);
+    to_dynamic(config)
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .expect("ModelConfig serializes to JSON")
+}
+
+/// Parse a config in Triton's JSON form, unknown fields are ignored.
+pub fn model_config_from_json(json: &str) -> Result<pb::ModelConfig> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize_with_options(
+        model_config_descriptor(),
+        &mut deserializer,
+        &DeserializeOptions::new().deny_unknown_fields(false),
+    )
+    .and_then(|message| deserializer.end().map(|_| message))
+    .map_err(json_error)?;
+    transcode(&message)
+}
+
+// Path of the first field set in `expected` whose value differs in `actual`.
+fn message_difference(
+    expected: &DynamicMessage,
+    actual: &DynamicMessage,
+    path: &str,
+) -> Option<String> {
+    expected.fields().find_map(|(field, value)| {
+        let path = match path {
+            "" => field.name().to_string(),
+            path => format!("{path}.{}", field.name()),
+        };
+        value_difference(value, &actual.get_field(&field), &path)
+    })
+}
+
+fn value_difference(
+    expected: &prost_reflect::Value,
+    actual: &prost_reflect::Value,
+    path: &str,
+) -> Option<String> {
+    use prost_reflect::Value;
+    match (expected, actual) {
+        (Value::Message(expected), Value::Message(actual)) => {
+            message_difference(expected, actual, path)
+        }
+        (Value::List(expected), Value::List(actual)) if expected.len() == actual.len() => expected
+            .iter()
+            .zip(actual)
+            .enumerate()
+            .find_map(|(index, (e, a))| value_difference(e, a, &format!("{path}[{index}]"))),
+        (Value::Map(expected), Value::Map(actual)) => expected.iter().find_map(|(key, e)| {
+            let path = format!("{path}[{key:?}]");
+            match actual.get(key) {
+                Some(a) => value_difference(e, a, &path),
+                None => Some(path),
+            }
+        }),
+        _ if expected == actual => None,
+        _ => Some(path.to_string()),
+    }
+}
+
+/// Path of the first field set in `expected` that `served` holds a different value for, e.g.
+/// `max_batch_size` or `input[0].dims`. Fields left unset in `expected` are ignored, as Triton
+/// fills in defaults for them.
+pub fn model_config_difference(
+    expected: &pb::ModelConfig,
+    served: &pb::ModelConfig,
+) -> Option<String> {
+    message_difference(&to_dynamic(expected), &to_dynamic(served), "")
+}
+
+// `message` as a dynamic message of the protobuf type `full_name`, e.g.
+// `inference.ModelMetadataResponse`.
+fn to_dynamic_named<M: Message>(message: &M, full_name: &str) -> Result<DynamicMessage> {
+    let descriptor = descriptor_pool()
+        .get_message_by_name(full_name)
+        .ok_or_else(|| Error::ConversionError(format!("Unknown message type `{full_name}`")))?;
+    let mut dynamic = DynamicMessage::new(descriptor);
+    dynamic
+        .transcode_from(message)
+        .map_err(|e| Error::ConversionError(e.to_string()))?;
+    Ok(dynamic)
+}
+
+/// Any message of the inference or health protocol as JSON, in the style of
+/// [`model_config_to_json`]. `full_name` is its protobuf type, e.g.
+/// `inference.ModelMetadataResponse`.
+pub fn message_to_json<M: Message>(message: &M, full_name: &str) -> Result<serde_json::Value> {
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .skip_default_fields(false)
+        .stringify_64_bit_integers(false);
+    to_dynamic_named(message, full_name)?
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(json_error)
+}
+
+/// Any message of the inference or health protocol in pretty protobuf text format.
+pub fn message_to_pbtxt<M: Message>(message: &M, full_name: &str) -> Result<String> {
+    let mut text = to_dynamic_named(message, full_name)?
+        .to_text_format_with_options(&FormatOptions::new().pretty(true));
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::pb::model_config::SchedulingChoice;
+
+    const CONFIG: &str = r#"
+name: "resnet"  # comment with input [ brackets ]
+platform: "onnxruntime_onnx"
+max_batch_size: 8
+input [
+  {
+    name: "image"
+    data_type: TYPE_FP32
+    dims: [ 3, 224, 224 ]
+  }
+]
+output [
+  {
+    name: "probabilities"
+    data_type: TYPE_FP32
+    dims: [ 1000 ]
+  }
+]
+dynamic_batching { preferred_batch_size: [ 4, 8 ] }
+"#;
+
+    #[test]
+    fn parses_triton_configs() {
+        let config = parse_model_config(CONFIG).unwrap();
+        assert_eq!(config.name, "resnet");
+        assert_eq!(config.max_batch_size, 8);
+        assert_eq!(config.input[0].dims, vec![3, 224, 224]);
+        assert_eq!(config.input[0].data_type, pb::DataType::TypeFp32 as i32);
+        assert_eq!(config.output[0].name, "probabilities");
+        match config.scheduling_choice {
+            Some(SchedulingChoice::DynamicBatching(batching)) => {
+                assert_eq!(batching.preferred_batch_size, vec![4, 8])
+            }
+            other => panic!("unexpected scheduling {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_pbtxt() {
+        let config = parse_model_config(CONFIG).unwrap();
+        let text = model_config_to_pbtxt(&config);
+        assert!(text.ends_with('\n'));
+        assert_eq!(parse_model_config(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let config = parse_model_config(CONFIG).unwrap();
+        let json = model_config_to_json(&config);
+        assert_eq!(json["max_batch_size"], 8);
+        assert_eq!(json["input"][0]["data_type"], "TYPE_FP32");
+        let parsed = model_config_from_json(&json.to_string()).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn reports_error_positions_in_the_original_text() {
+        let text = "name: \"resnet\"\ninput [ { name: \"image\" } ]\nmax_batch_size: nope\n";
+        match parse_model_config(text) {
+            Err(Error::ParseError { line, column, .. }) => assert_eq!((line, column), (3, 17)),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+}
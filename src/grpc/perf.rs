@@ -0,0 +1,566 @@
+use super::client::{Error, InferenceServerClient, Result};
+use super::model::{Dim, ModelMetadataView};
+use super::pb::{InferInputTensor, ModelInferRequest};
+use super::statistics::{DurationStat, ModelStatsDelta, StatisticsSnapshot};
+use super::trace::Percentiles;
+use super::warmup::{random_element, Rng};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::{self, Instant, MissedTickBehavior};
+
+/// Where the tensors of generated requests come from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum InputData {
+    Zero,
+    #[default]
+    Random,
+    /// A directory with one raw file per input, named after it, holding one batch row
+    Dir(PathBuf),
+}
+
+impl FromStr for InputData {
+    type Err = Error;
+
+    /// `zero`, `random` or a directory path.
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "zero" => Self::Zero,
+            "random" => Self::Random,
+            path => Self::Dir(PathBuf::from(path)),
+        })
+    }
+}
+
+/// How synthetic requests are built from the model metadata.
+#[derive(Clone, Debug)]
+pub struct SyntheticInputs {
+    /// Rows per request, ignored by models without batching
+    pub batch_size: usize,
+    /// Input name -> shape without the batch dimension, required for inputs with dynamic dims
+    pub shapes: HashMap<String, Vec<i64>>,
+    pub data: InputData,
+}
+
+impl Default for SyntheticInputs {
+    fn default() -> Self {
+        Self {
+            batch_size: 1,
+            shapes: HashMap::new(),
+            data: InputData::default(),
+        }
+    }
+}
+
+impl SyntheticInputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size.max(1);
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.set_batch_size(batch_size);
+        self
+    }
+
+    pub fn set_shape<T: ToString>(&mut self, name: T, shape: Vec<i64>) {
+        self.shapes.insert(name.to_string(), shape);
+    }
+
+    pub fn shape<T: ToString>(mut self, name: T, shape: Vec<i64>) -> Self {
+        self.set_shape(name, shape);
+        self
+    }
+
+    pub fn set_data(&mut self, data: InputData) {
+        self.data = data;
+    }
+
+    pub fn data(mut self, data: InputData) -> Self {
+        self.set_data(data);
+        self
+    }
+
+    /// Build one request for `model_version` of the model. `max_batch_size` comes from the
+    /// model config, metadata shapes carry the batch dimension when it is positive.
+    pub fn request(
+        &self,
+        metadata: &ModelMetadataView,
+        model_version: &str,
+        max_batch_size: i32,
+    ) -> Result<ModelInferRequest> {
+        let batching = max_batch_size > 0;
+        if batching && self.batch_size > max_batch_size as usize {
+            return Err(Error::ConversionError(format!(
+                "Batch size {} exceeds max_batch_size {max_batch_size} of `{}`",
+                self.batch_size, metadata.name
+            )));
+        }
+        let mut rng = Rng::new();
+        let mut request = ModelInferRequest {
+            model_name: metadata.name.clone(),
+            model_version: model_version.to_string(),
+            ..Default::default()
+        };
+        for input in &metadata.inputs {
+            let dims = if batching {
+                input.dims.get(1..).unwrap_or_default()
+            } else {
+                &input.dims[..]
+            };
+            let shape = self.sample_shape(&input.name, dims)?;
+            // BYTES elements are a 4-byte length followed by the (empty) content
+            let element_size = input.datatype.size_of().unwrap_or(4);
+            let rows = if batching { self.batch_size } else { 1 };
+            let too_large = || {
+                Error::ConversionError(format!(
+                    "Shape {shape:?} of input `{}` is too large",
+                    input.name
+                ))
+            };
+            let elements = shape
+                .iter()
+                .try_fold(1usize, |count, size| {
+                    count.checked_mul(usize::try_from(*size).ok()?)
+                })
+                .ok_or_else(too_large)?;
+            let size = elements
+                .checked_mul(element_size)
+                .and_then(|size| size.checked_mul(rows))
+                .ok_or_else(too_large)?;
+
+            let mut data = Vec::with_capacity(size);
+            match &self.data {
+                InputData::Zero => data.resize(size, 0),
+                InputData::Random => {
+                    for _ in 0..rows * elements {
+                        random_element(&input.datatype, &mut rng, &mut data);
+                    }
+                }
+                InputData::Dir(dir) => {
+                    let sample = fs::read(dir.join(&input.name))?;
+                    if input.datatype.size_of().is_some() && sample.len() != elements * element_size
+                    {
+                        return Err(Error::ConversionError(format!(
+                            "`{}` holds {} bytes, input `{}` of shape {shape:?} needs {}",
+                            dir.join(&input.name).display(),
+                            sample.len(),
+                            input.name,
+                            elements * element_size
+                        )));
+                    }
+                    for _ in 0..rows {
+                        data.extend_from_slice(&sample);
+                    }
+                }
+            }
+
+            let mut shape = shape;
+            if batching {
+                shape.insert(0, self.batch_size as i64);
+            }
+            request.inputs.push(InferInputTensor {
+                name: input.name.clone(),
+                datatype: input.datatype.to_string(),
+                shape,
+                ..Default::default()
+            });
+            request.raw_input_contents.push(data);
+        }
+        Ok(request)
+    }
+
+    // The shape of one batch row, from `shapes` when given.
+    fn sample_shape(&self, name: &str, dims: &[Dim]) -> Result<Vec<i64>> {
+        match self.shapes.get(name) {
+            Some(shape) => {
+                let fits = shape.len() == dims.len()
+                    && dims.iter().zip(shape).all(|(dim, size)| dim.accepts(*size));
+                if !fits {
+                    let dims = dims.iter().map(|dim| i64::from(*dim)).collect::<Vec<_>>();
+                    return Err(Error::ConversionError(format!(
+                        "Shape {shape:?} does not fit input `{name}` with dims {dims:?}"
+                    )));
+                }
+                Ok(shape.clone())
+            }
+            None => dims
+                .iter()
+                .map(|dim| match dim {
+                    Dim::Fixed(size) => Ok(*size),
+                    Dim::Dynamic => Err(Error::ConversionError(format!(
+                        "Input `{name}` has dynamic dims, give its shape"
+                    ))),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The load put on the server during a measurement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadMode {
+    /// Requests in flight at any time, each sent as soon as the previous one completes
+    Concurrency(usize),
+    /// Requests sent per second whatever the server latency
+    RequestRate(f64),
+}
+
+impl Display for LoadMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Concurrency(concurrency) => write!(f, "concurrency {concurrency}"),
+            Self::RequestRate(rate) => write!(f, "request rate {rate}/s"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PerfOptions {
+    /// Load before the measurement starts, not counted
+    pub warmup: Duration,
+    pub measurement: Duration,
+    /// Diff `model_statistics` over the measurement
+    pub server_stats: bool,
+}
+
+impl Default for PerfOptions {
+    fn default() -> Self {
+        Self {
+            warmup: Duration::from_secs(1),
+            measurement: Duration::from_secs(5),
+            server_stats: false,
+        }
+    }
+}
+
+impl PerfOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_warmup(&mut self, warmup: Duration) {
+        self.warmup = warmup;
+    }
+
+    pub fn warmup(mut self, warmup: Duration) -> Self {
+        self.set_warmup(warmup);
+        self
+    }
+
+    pub fn set_measurement(&mut self, measurement: Duration) {
+        self.measurement = measurement;
+    }
+
+    pub fn measurement(mut self, measurement: Duration) -> Self {
+        self.set_measurement(measurement);
+        self
+    }
+
+    pub fn set_server_stats(&mut self, server_stats: bool) {
+        self.server_stats = server_stats;
+    }
+
+    pub fn server_stats(mut self, server_stats: bool) -> Self {
+        self.set_server_stats(server_stats);
+        self
+    }
+}
+
+/// Client and server view of one load level.
+#[derive(Clone, Debug)]
+pub struct PerfMeasurement {
+    pub mode: LoadMode,
+    pub batch_size: usize,
+    /// Requests completed successfully within the measurement
+    pub requests: usize,
+    pub errors: usize,
+    pub elapsed: Duration,
+    /// Client side latency of the successful requests
+    pub latency: Option<Percentiles>,
+    pub server: Option<ModelStatsDelta>,
+}
+
+/// Columns of [`PerfMeasurement::csv_row`], latencies are in microseconds.
+pub const PERF_CSV_HEADER: &str = "concurrency,request_rate,batch_size,requests,errors,\
+    throughput,latency_avg_us,latency_p50_us,latency_p90_us,latency_p95_us,latency_p99_us,\
+    server_queue_us,server_compute_input_us,server_compute_infer_us,server_compute_output_us";
+
+fn micros(duration: Option<Duration>) -> Option<u128> {
+    duration.map(|duration| duration.as_micros())
+}
+
+fn csv_field<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+impl PerfMeasurement {
+    /// Inferences (batch rows) per second
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => (self.requests * self.batch_size) as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    fn server_stat(&self, f: fn(&ModelStatsDelta) -> DurationStat) -> Option<Duration> {
+        self.server.as_ref().and_then(|delta| f(delta).average())
+    }
+
+    /// Average time per execution in the scheduler queue and the compute phases
+    pub fn server_breakdown(&self) -> [Option<Duration>; 4] {
+        [
+            self.server_stat(|delta| delta.stats.queue),
+            self.server_stat(|delta| delta.stats.compute_input),
+            self.server_stat(|delta| delta.stats.compute_infer),
+            self.server_stat(|delta| delta.stats.compute_output),
+        ]
+    }
+
+    /// One line matching [`PERF_CSV_HEADER`].
+    pub fn csv_row(&self) -> String {
+        let (concurrency, rate) = match self.mode {
+            LoadMode::Concurrency(concurrency) => (Some(concurrency.to_string()), None),
+            LoadMode::RequestRate(rate) => (None, Some(rate.to_string())),
+        };
+        let latency = self.latency.as_ref();
+        let mut fields = vec![
+            csv_field(concurrency),
+            csv_field(rate),
+            self.batch_size.to_string(),
+            self.requests.to_string(),
+            self.errors.to_string(),
+            format!("{:.3}", self.throughput()),
+            csv_field(micros(latency.map(|l| l.mean))),
+            csv_field(micros(latency.map(|l| l.p50))),
+            csv_field(micros(latency.map(|l| l.p90))),
+            csv_field(micros(latency.map(|l| l.p95))),
+            csv_field(micros(latency.map(|l| l.p99))),
+        ];
+        fields.extend(
+            self.server_breakdown()
+                .into_iter()
+                .map(|stat| csv_field(micros(stat))),
+        );
+        fields.join(",")
+    }
+
+    pub fn to_json(&self) -> Value {
+        let (concurrency, rate) = match self.mode {
+            LoadMode::Concurrency(concurrency) => (Some(concurrency), None),
+            LoadMode::RequestRate(rate) => (None, Some(rate)),
+        };
+        let latency = self.latency.as_ref().map(|latency| {
+            json!({
+                "avg_us": latency.mean.as_micros() as u64,
+                "min_us": latency.min.as_micros() as u64,
+                "p50_us": latency.p50.as_micros() as u64,
+                "p90_us": latency.p90.as_micros() as u64,
+                "p95_us": latency.p95.as_micros() as u64,
+                "p99_us": latency.p99.as_micros() as u64,
+                "max_us": latency.max.as_micros() as u64,
+            })
+        });
+        let server = self.server.as_ref().map(|delta| {
+            let [queue, compute_input, compute_infer, compute_output] = self
+                .server_breakdown()
+                .map(|stat| micros(stat).map(|us| us as u64));
+            json!({
+                "request_rate": delta.request_rate(),
+                "inference_rate": delta.inference_rate(),
+                "execution_rate": delta.execution_rate(),
+                "queue_us": queue,
+                "compute_input_us": compute_input,
+                "compute_infer_us": compute_infer,
+                "compute_output_us": compute_output,
+            })
+        });
+        json!({
+            "concurrency": concurrency,
+            "request_rate": rate,
+            "batch_size": self.batch_size,
+            "requests": self.requests,
+            "errors": self.errors,
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "throughput": self.throughput(),
+            "latency": latency,
+            "server": server,
+        })
+    }
+}
+
+// One completed request, `end` decides whether it falls in the measurement.
+struct Completion {
+    end: Instant,
+    latency: Duration,
+    error: Option<Error>,
+}
+
+async fn send(client: &InferenceServerClient, request: ModelInferRequest) -> Completion {
+    let start = Instant::now();
+    let error = client.infer(request).await.err();
+    let end = Instant::now();
+    Completion {
+        end,
+        latency: end - start,
+        error,
+    }
+}
+
+/// Drives one model with a prepared request, see [`SyntheticInputs::request`].
+pub struct PerfRunner {
+    client: Arc<InferenceServerClient>,
+    request: ModelInferRequest,
+    batch_size: usize,
+    options: PerfOptions,
+}
+
+impl PerfRunner {
+    /// `batch_size` is the number of rows in `request`, 1 for models without batching.
+    pub fn new(
+        client: Arc<InferenceServerClient>,
+        request: ModelInferRequest,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            client,
+            request,
+            batch_size,
+            options: PerfOptions::default(),
+        }
+    }
+
+    pub fn set_options(&mut self, options: PerfOptions) {
+        self.options = options;
+    }
+
+    pub fn options(mut self, options: PerfOptions) -> Self {
+        self.set_options(options);
+        self
+    }
+
+    /// Put `mode` load on the server for the warmup and measurement periods. Fails with the
+    /// first error when no request of the measurement succeeded.
+    pub async fn measure(&self, mode: LoadMode) -> Result<PerfMeasurement> {
+        let start = Instant::now() + self.options.warmup;
+        let end = start + self.options.measurement;
+
+        let mut tasks = JoinSet::new();
+        match mode {
+            LoadMode::Concurrency(concurrency) => {
+                for _ in 0..concurrency.max(1) {
+                    let client = self.client.clone();
+                    let request = self.request.clone();
+                    tasks.spawn(async move {
+                        let mut completions = vec![];
+                        while Instant::now() < end {
+                            completions.push(send(&client, request.clone()).await);
+                        }
+                        completions
+                    });
+                }
+            }
+            LoadMode::RequestRate(rate) if rate > 0.0 && rate.is_finite() => {
+                let client = self.client.clone();
+                let request = self.request.clone();
+                tasks.spawn(async move {
+                    // Open loop, every tick sends whether or not earlier requests completed
+                    let mut ticks = time::interval(Duration::from_secs_f64(1.0 / rate));
+                    ticks.set_missed_tick_behavior(MissedTickBehavior::Burst);
+                    let mut requests = JoinSet::new();
+                    while ticks.tick().await < end {
+                        let client = client.clone();
+                        let request = request.clone();
+                        requests.spawn(async move { send(&client, request).await });
+                    }
+                    let mut completions = vec![];
+                    while let Some(completion) = requests.join_next().await {
+                        completions.extend(completion.ok());
+                    }
+                    completions
+                });
+            }
+            LoadMode::RequestRate(rate) => {
+                return Err(Error::ConversionError(format!(
+                    "Invalid request rate {rate}"
+                )))
+            }
+        }
+
+        let server = if self.options.server_stats {
+            time::sleep_until(start).await;
+            let before = self.statistics().await?;
+            time::sleep_until(end).await;
+            let after = self.statistics().await?;
+            let delta = after.since(&before);
+            let request = &self.request;
+            // Without a version every loaded one is reported, the first stands for the model
+            delta.models.into_iter().find(|delta| {
+                delta.stats.name == request.model_name
+                    && (request.model_version.is_empty()
+                        || delta.stats.version == request.model_version)
+            })
+        } else {
+            None
+        };
+
+        let mut latencies = vec![];
+        let mut errors = 0;
+        let mut first_error = None;
+        while let Some(completions) = tasks.join_next().await {
+            let completions = completions.map_err(|e| Error::ConversionError(e.to_string()))?;
+            for completion in completions {
+                if completion.end < start || completion.end > end {
+                    continue;
+                }
+                match completion.error {
+                    None => latencies.push(completion.latency),
+                    Some(error) => {
+                        errors += 1;
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+        }
+        if let (true, Some(error)) = (latencies.is_empty(), first_error) {
+            return Err(error);
+        }
+        Ok(PerfMeasurement {
+            mode,
+            batch_size: self.batch_size,
+            requests: latencies.len(),
+            errors,
+            elapsed: self.options.measurement,
+            latency: Percentiles::new(latencies),
+            server,
+        })
+    }
+
+    /// Measure every load level in turn.
+    pub async fn sweep(
+        &self,
+        modes: impl IntoIterator<Item = LoadMode>,
+    ) -> Result<Vec<PerfMeasurement>> {
+        let mut measurements = vec![];
+        for mode in modes {
+            measurements.push(self.measure(mode).await?);
+        }
+        Ok(measurements)
+    }
+
+    async fn statistics(&self) -> Result<StatisticsSnapshot> {
+        let version = Some(self.request.model_version.as_str()).filter(|v| !v.is_empty());
+        self.client
+            .statistics_snapshot(&self.request.model_name, version)
+            .await
+    }
+}
@@ -0,0 +1,426 @@
+use super::client::{Error, Result};
+use super::output::ModelOutput;
+use super::pb::{ModelInferRequest, ModelInferResponse};
+use prost::Message;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tonic::{Code, Status};
+
+/// One `ModelInfer` call as written to a recording, frames are length-delimited protobuf.
+#[derive(Clone, PartialEq, Message)]
+pub struct RecordedExchange {
+    /// Unix time in nanoseconds at which the request was sent
+    #[prost(uint64, tag = "1")]
+    pub timestamp_ns: u64,
+
+    /// Time spent waiting for the response, retries included
+    #[prost(uint64, tag = "2")]
+    pub latency_ns: u64,
+
+    #[prost(message, optional, tag = "3")]
+    pub request: Option<ModelInferRequest>,
+
+    /// Missing when the call failed
+    #[prost(message, optional, tag = "4")]
+    pub response: Option<ModelInferResponse>,
+
+    /// gRPC status code of a failed call, 0 on success
+    #[prost(int32, tag = "5")]
+    pub error_code: i32,
+
+    #[prost(string, tag = "6")]
+    pub error_message: String,
+}
+
+impl RecordedExchange {
+    pub fn new(
+        request: ModelInferRequest,
+        result: std::result::Result<&ModelInferResponse, &Status>,
+        sent_at: SystemTime,
+        latency: Duration,
+    ) -> Self {
+        let (response, error_code, error_message) = match result {
+            Ok(response) => (Some(response.clone()), 0, String::new()),
+            Err(status) => (None, status.code() as i32, status.message().to_string()),
+        };
+        Self {
+            timestamp_ns: sent_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64,
+            latency_ns: latency.as_nanos() as u64,
+            request: Some(request),
+            response,
+            error_code,
+            error_message,
+        }
+    }
+
+    pub fn sent_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.timestamp_ns)
+    }
+
+    pub fn latency(&self) -> Duration {
+        Duration::from_nanos(self.latency_ns)
+    }
+
+    /// The recorded outcome, failed calls are turned back into an [`Error::ResponseError`].
+    pub fn result(&self) -> Result<ModelInferResponse> {
+        match &self.response {
+            Some(response) if self.error_code == 0 => Ok(response.clone()),
+            _ => {
+                Err(Status::new(Code::from_i32(self.error_code), self.error_message.clone()).into())
+            }
+        }
+    }
+
+    /// Whether `request` asks for the same thing as the recorded one, the request `id` is ignored.
+    pub fn matches(&self, request: &ModelInferRequest) -> bool {
+        match &self.request {
+            Some(recorded) => {
+                recorded.model_name == request.model_name
+                    && recorded.model_version == request.model_version
+                    && recorded.inputs == request.inputs
+                    && recorded.outputs == request.outputs
+                    && recorded.raw_input_contents == request.raw_input_contents
+                    && recorded.parameters == request.parameters
+            }
+            None => false,
+        }
+    }
+}
+
+enum Command {
+    Frame(Vec<u8>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Appends inference traffic to a file or any other writer. Frames are written and flushed by a
+/// background thread, recording never blocks an inference on I/O.
+pub struct Recorder {
+    sender: Option<mpsc::Sender<Command>>,
+    // First write failure, nothing is written after it
+    error: Arc<Mutex<Option<Error>>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn new<W: Write + Send + 'static>(mut writer: W) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let error = Arc::new(Mutex::new(None));
+        let failure = error.clone();
+        let writer = thread::spawn(move || {
+            for command in receiver {
+                match command {
+                    Command::Frame(frame) => {
+                        if let Err(e) = writer.write_all(&frame).and_then(|_| writer.flush()) {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(error = %e, "recording stopped after a write failure");
+                            if let Ok(mut failure) = failure.lock() {
+                                *failure = Some(Error::from(e));
+                            }
+                            return;
+                        }
+                    }
+                    Command::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            error,
+            writer: Some(writer),
+        }
+    }
+
+    /// Create (or truncate) the recording at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Append to the recording at `path`, creating it when missing.
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Queue the exchange for writing, fails once a previous write failed.
+    pub fn record(&self, exchange: &RecordedExchange) -> Result<()> {
+        if let Some(error) = self.error() {
+            return Err(error);
+        }
+        let frame = exchange.encode_length_delimited_to_vec();
+        let sent = self
+            .sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(Command::Frame(frame)).is_ok());
+        match sent {
+            true => Ok(()),
+            false => Err(self
+                .error()
+                .unwrap_or_else(|| Error::IoError("recorder stopped".to_string()))),
+        }
+    }
+
+    /// The write failure that stopped the recording, if any.
+    pub fn error(&self) -> Option<Error> {
+        self.error.lock().ok().and_then(|error| error.clone())
+    }
+
+    /// Wait until every exchange recorded so far is written, blocking the calling thread.
+    pub fn flush(&self) -> Result<()> {
+        let (done, written) = mpsc::channel();
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Command::Flush(done));
+        }
+        let _ = written.recv();
+        match self.error() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    // Write what is still queued before the writer goes away.
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Read every exchange of a recording.
+pub fn read_recording<P: AsRef<Path>>(path: P) -> Result<Vec<RecordedExchange>> {
+    let mut data = vec![];
+    File::open(path)?.read_to_end(&mut data)?;
+    decode_recording(&data)
+}
+
+/// Decode length-delimited exchanges. A truncated last frame, as left by a crash while
+/// recording, is dropped when it follows a complete frame or stops inside its length prefix;
+/// anything else is an error.
+pub fn decode_recording(mut data: &[u8]) -> Result<Vec<RecordedExchange>> {
+    let invalid =
+        |e: &dyn std::fmt::Display| Error::ConversionError(format!("Invalid recording: {e}"));
+    let mut exchanges = vec![];
+    while !data.is_empty() {
+        let mut frame = data;
+        let len = match prost::encoding::decode_varint(&mut frame) {
+            Ok(len) => len as usize,
+            // Varints take at most 10 bytes, all but the last with the continuation bit set
+            Err(_) if data.len() < 10 && data.iter().all(|byte| byte & 0x80 != 0) => break,
+            Err(e) => return Err(invalid(&e)),
+        };
+        if frame.len() < len {
+            if exchanges.is_empty() {
+                return Err(invalid(&format!(
+                    "a frame of {len} bytes has only {}",
+                    frame.len()
+                )));
+            }
+            break;
+        }
+        exchanges.push(RecordedExchange::decode(&frame[..len]).map_err(|e| invalid(&e))?);
+        data = &frame[len..];
+    }
+    Ok(exchanges)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// Answer with the first unused exchange whose request matches
+    Matching,
+    /// Answer with the exchanges in recorded order, whatever the request
+    Sequential,
+}
+
+/// Answers inference requests from a recording instead of a server.
+pub struct ReplayClient {
+    mode: ReplayMode,
+    exchanges: Mutex<VecDeque<RecordedExchange>>,
+}
+
+impl ReplayClient {
+    pub fn new(exchanges: Vec<RecordedExchange>, mode: ReplayMode) -> Self {
+        Self {
+            mode,
+            exchanges: Mutex::new(exchanges.into()),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P, mode: ReplayMode) -> Result<Self> {
+        Ok(Self::new(read_recording(path)?, mode))
+    }
+
+    /// Number of exchanges not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.exchanges.lock().map(|e| e.len()).unwrap_or_default()
+    }
+
+    pub fn next_exchange(&self, request: &ModelInferRequest) -> Result<RecordedExchange> {
+        let mut exchanges = self.exchanges.lock()?;
+        let position = match self.mode {
+            ReplayMode::Matching => exchanges.iter().position(|e| e.matches(request)),
+            ReplayMode::Sequential => (!exchanges.is_empty()).then_some(0),
+        };
+        position
+            .and_then(|position| exchanges.remove(position))
+            .ok_or_else(|| {
+                Error::ReplayMismatch(format!(
+                    "model '{}' version '{}', id '{}'",
+                    request.model_name, request.model_version, request.id
+                ))
+            })
+    }
+
+    /// Same contract as `InferenceServerClient::infer`.
+    pub async fn infer(&self, request: impl Into<ModelInferRequest>) -> Result<ModelOutput> {
+        let exchange = self.next_exchange(&request.into())?;
+        ModelOutput::new(exchange.result()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    // Writer shared with the test, failing every write once `fail` is set.
+    #[derive(Clone, Default)]
+    struct SharedWriter {
+        data: Arc<Mutex<Vec<u8>>>,
+        fail: bool,
+    }
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.fail {
+                return Err(io::Error::other("disk full"));
+            }
+            self.data.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn exchange(id: &str, result: std::result::Result<(), Status>) -> RecordedExchange {
+        let request = ModelInferRequest {
+            model_name: "model".to_string(),
+            id: id.to_string(),
+            raw_input_contents: vec![id.as_bytes().to_vec()],
+            ..Default::default()
+        };
+        let response = ModelInferResponse {
+            model_name: "model".to_string(),
+            id: id.to_string(),
+            ..Default::default()
+        };
+        let result = result.as_ref().map(|_| &response);
+        RecordedExchange::new(request, result, SystemTime::now(), Duration::from_millis(3))
+    }
+
+    #[test]
+    fn records_and_decodes_exchanges() {
+        let writer = SharedWriter::default();
+        let recorder = Recorder::new(writer.clone());
+        let exchanges = vec![
+            exchange("a", Ok(())),
+            exchange("b", Err(Status::unavailable("down"))),
+        ];
+        for exchange in &exchanges {
+            recorder.record(exchange).unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let data = writer.data.lock().unwrap().clone();
+        let decoded = decode_recording(&data).unwrap();
+        assert_eq!(decoded, exchanges);
+        assert_eq!(decoded[0].latency(), Duration::from_millis(3));
+        let Err(Error::ResponseError { status }) = decoded[1].result() else {
+            panic!("the failed call is not replayed as a response error");
+        };
+        assert_eq!(
+            (status.code(), status.message()),
+            (Code::Unavailable, "down")
+        );
+    }
+
+    #[test]
+    fn drops_a_truncated_last_frame() {
+        let first = exchange("a", Ok(())).encode_length_delimited_to_vec();
+        let second = exchange("b", Ok(())).encode_length_delimited_to_vec();
+        let data = [first.as_slice(), second.as_slice()].concat();
+        for cut in first.len()..data.len() {
+            let decoded = decode_recording(&data[..cut]).unwrap();
+            assert_eq!(decoded.len(), 1, "cut at {cut}");
+        }
+        assert_eq!(decode_recording(&data).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let first = exchange("a", Ok(())).encode_length_delimited_to_vec();
+        assert!(decode_recording(&[0xff; 16]).is_err());
+        assert!(decode_recording(b"hello").is_err());
+        assert!(decode_recording(&first[..first.len() - 1]).is_err());
+        // A length prefix cut short is still a crash while recording
+        assert!(decode_recording(&[0x80, 0x80]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn keeps_the_first_write_failure() {
+        let writer = SharedWriter {
+            fail: true,
+            ..SharedWriter::default()
+        };
+        let recorder = Recorder::new(writer);
+        recorder.record(&exchange("a", Ok(()))).unwrap();
+        assert!(matches!(recorder.flush(), Err(Error::IoError(_))));
+        assert!(recorder.error().is_some());
+        assert!(recorder.record(&exchange("b", Ok(()))).is_err());
+    }
+
+    #[test]
+    fn replays_each_exchange_once() {
+        let replay = ReplayClient::new(
+            vec![exchange("a", Ok(())), exchange("b", Ok(()))],
+            ReplayMode::Matching,
+        );
+        let mut request = exchange("b", Ok(())).request.unwrap();
+        // Matching ignores the request id
+        request.id = "other".to_string();
+        assert_eq!(
+            replay.next_exchange(&request).unwrap().response.unwrap().id,
+            "b"
+        );
+        assert!(matches!(
+            replay.next_exchange(&request),
+            Err(Error::ReplayMismatch(_))
+        ));
+        assert_eq!(replay.remaining(), 1);
+
+        let sequential = ReplayClient::new(vec![exchange("a", Ok(()))], ReplayMode::Sequential);
+        assert_eq!(
+            sequential
+                .next_exchange(&request)
+                .unwrap()
+                .response
+                .unwrap()
+                .id,
+            "a"
+        );
+        assert_eq!(sequential.remaining(), 0);
+    }
+}
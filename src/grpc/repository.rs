@@ -0,0 +1,475 @@
+use super::client::{Error, Result};
+use super::pb::model_config::SchedulingChoice;
+use super::pb::model_repository_parameter::ParameterChoice;
+use super::pb::model_version_policy::{PolicyChoice, Specific};
+use super::pb::{self, ModelRepositoryParameter};
+use super::pbtxt::{json_error, model_config_from_json, model_config_to_json};
+use crate::types::Bytes;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// `repository_model_load` with a config override and model files sent along the request.
+#[derive(Clone, Debug, Default)]
+pub struct ModelLoadRequest {
+    repository_name: String,
+    model_name: String,
+    config: Option<pb::ModelConfig>,
+    // path inside the model directory, e.g. `1/model.onnx` -> content
+    files: BTreeMap<String, Bytes>,
+}
+
+impl ModelLoadRequest {
+    pub fn new<T: ToString>(model_name: T) -> Self {
+        Self {
+            model_name: model_name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_repository_name<T: ToString>(&mut self, repository_name: T) {
+        self.repository_name = repository_name.to_string();
+    }
+
+    pub fn repository_name<T: ToString>(mut self, repository_name: T) -> Self {
+        self.set_repository_name(repository_name);
+        self
+    }
+
+    /// Used instead of the `config.pbtxt` of the repository, required when files are given
+    pub fn set_config(&mut self, config: pb::ModelConfig) {
+        self.config = Some(config);
+    }
+
+    pub fn config(mut self, config: pb::ModelConfig) -> Self {
+        self.set_config(config);
+        self
+    }
+
+    /// `path` is relative to the model directory and starts with the version, e.g. `1/model.onnx`
+    pub fn set_file<T: ToString>(&mut self, path: T, content: Bytes) {
+        self.files.insert(path.to_string(), content);
+    }
+
+    pub fn file<T: ToString>(mut self, path: T, content: Bytes) -> Self {
+        self.set_file(path, content);
+        self
+    }
+
+    /// Add every file under `dir` laid out as a model directory (`<version>/<name>`).
+    /// A `config.pbtxt` at the root is skipped, pass the config with [`Self::config`] instead.
+    /// Symlinks are followed, a directory reached twice is only read once.
+    pub fn set_files_from_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        let mut pending = vec![dir.to_path_buf()];
+        // Canonical paths, so a symlink cycle ends instead of being walked forever
+        let mut visited = HashSet::from([fs::canonicalize(dir)?]);
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    if visited.insert(fs::canonicalize(&path)?) {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(dir)
+                    .map_err(|e| Error::IoError(e.to_string()))?
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if relative == "config.pbtxt" {
+                    continue;
+                }
+                self.files.insert(relative, fs::read(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn files_from_dir<P: AsRef<Path>>(mut self, dir: P) -> Result<Self> {
+        self.set_files_from_dir(dir)?;
+        Ok(self)
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (&str, &Bytes)> {
+        self.files
+            .iter()
+            .map(|(path, content)| (path.as_str(), content))
+    }
+
+    fn config_json(&self) -> Option<String> {
+        self.config
+            .as_ref()
+            .map(|config| model_config_to_json(config).to_string())
+    }
+
+    /// Bytes of config JSON and file contents the request will carry.
+    pub fn payload_size(&self) -> usize {
+        self.config_json().map_or(0, |json| json.len())
+            + self.files.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// The `config` and `file:<path>` parameters of the request.
+    pub fn parameters(&self) -> Result<HashMap<String, ModelRepositoryParameter>> {
+        let config = self.config_json();
+        if config.is_none() && !self.files.is_empty() {
+            return Err(Error::ConversionError(format!(
+                "Model `{}` files can only be loaded together with a config",
+                self.model_name
+            )));
+        }
+        let mut parameters = HashMap::new();
+        if let Some(config) = config {
+            parameters.insert(
+                "config".to_string(),
+                ModelRepositoryParameter {
+                    parameter_choice: Some(ParameterChoice::StringParam(config)),
+                },
+            );
+        }
+        for (path, content) in &self.files {
+            parameters.insert(
+                format!("file:{path}"),
+                ModelRepositoryParameter {
+                    parameter_choice: Some(ParameterChoice::BytesParam(content.clone())),
+                },
+            );
+        }
+        Ok(parameters)
+    }
+}
+
+impl TryFrom<ModelLoadRequest> for pb::RepositoryModelLoadRequest {
+    type Error = Error;
+
+    fn try_from(request: ModelLoadRequest) -> Result<Self> {
+        Ok(Self {
+            parameters: request.parameters()?,
+            repository_name: request.repository_name,
+            model_name: request.model_name,
+        })
+    }
+}
+
+/// A model as it should be served.
+#[derive(Clone, Debug, Default)]
+pub struct ModelSpec {
+    pub name: String,
+    /// Versions that must be ready, empty to accept whatever the version policy serves.
+    /// Versions are pinned through the version policy of `config`, so pinning needs a config
+    pub versions: Vec<i64>,
+    /// Config override sent along the load request
+    pub config: Option<pb::ModelConfig>,
+    /// Models to load before this one, ensemble steps of `config` are added automatically
+    pub depends_on: Vec<String>,
+}
+
+impl ModelSpec {
+    pub fn new<T: ToString>(name: T) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_versions(&mut self, versions: Vec<i64>) {
+        self.versions = versions;
+    }
+
+    pub fn versions(mut self, versions: Vec<i64>) -> Self {
+        self.set_versions(versions);
+        self
+    }
+
+    pub fn set_config(&mut self, config: pb::ModelConfig) {
+        self.config = Some(config);
+    }
+
+    pub fn config(mut self, config: pb::ModelConfig) -> Self {
+        self.set_config(config);
+        self
+    }
+
+    pub fn set_depends_on(&mut self, depends_on: Vec<String>) {
+        self.depends_on = depends_on;
+    }
+
+    pub fn depends_on(mut self, depends_on: Vec<String>) -> Self {
+        self.set_depends_on(depends_on);
+        self
+    }
+
+    /// Explicit dependencies followed by the models used by ensemble steps.
+    pub fn dependencies(&self) -> Vec<String> {
+        let mut dependencies = self.depends_on.clone();
+        if let Some(config) = &self.config {
+            for model in ensemble_steps(config) {
+                if !dependencies.contains(&model) {
+                    dependencies.push(model);
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// Load request for the spec, with the versions pinned in the config override.
+    pub fn load_request(&self, repository_name: &str) -> ModelLoadRequest {
+        let mut request = ModelLoadRequest::new(&self.name).repository_name(repository_name);
+        if let Some(config) = &self.config {
+            let mut config = config.clone();
+            if !self.versions.is_empty() {
+                config.version_policy = Some(pb::ModelVersionPolicy {
+                    policy_choice: Some(PolicyChoice::Specific(Specific {
+                        versions: self.versions.clone(),
+                    })),
+                });
+            }
+            request.set_config(config);
+        }
+        request
+    }
+}
+
+/// Models used by the steps of an ensemble config.
+pub fn ensemble_steps(config: &pb::ModelConfig) -> Vec<String> {
+    match &config.scheduling_choice {
+        Some(SchedulingChoice::EnsembleScheduling(ensemble)) => {
+            let mut models = vec![];
+            for step in &ensemble.step {
+                if !models.contains(&step.model_name) {
+                    models.push(step.model_name.clone());
+                }
+            }
+            models
+        }
+        _ => vec![],
+    }
+}
+
+/// The models a server should serve from one repository.
+#[derive(Clone, Debug, Default)]
+pub struct RepositoryManifest {
+    pub repository_name: String,
+    pub models: Vec<ModelSpec>,
+    /// Unload ready models that are not in the manifest, off by default
+    pub unload_unlisted: bool,
+}
+
+fn manifest_error(message: String) -> Error {
+    Error::ConversionError(format!("Invalid manifest: {message}"))
+}
+
+fn json_strings(value: Option<&Value>, field: &str) -> Result<Vec<String>> {
+    match value {
+        None => Ok(vec![]),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| manifest_error(format!("`{field}` must hold strings")))
+            })
+            .collect(),
+        Some(_) => Err(manifest_error(format!("`{field}` must be an array"))),
+    }
+}
+
+impl RepositoryManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_repository_name<T: ToString>(&mut self, repository_name: T) {
+        self.repository_name = repository_name.to_string();
+    }
+
+    pub fn repository_name<T: ToString>(mut self, repository_name: T) -> Self {
+        self.set_repository_name(repository_name);
+        self
+    }
+
+    /// Add `model`, replacing the model of the same name if there is one.
+    pub fn set_model(&mut self, model: ModelSpec) {
+        match self.models.iter_mut().find(|m| m.name == model.name) {
+            Some(existing) => *existing = model,
+            None => self.models.push(model),
+        }
+    }
+
+    pub fn model(mut self, model: ModelSpec) -> Self {
+        self.set_model(model);
+        self
+    }
+
+    pub fn set_unload_unlisted(&mut self, unload_unlisted: bool) {
+        self.unload_unlisted = unload_unlisted;
+    }
+
+    pub fn unload_unlisted(mut self, unload_unlisted: bool) -> Self {
+        self.set_unload_unlisted(unload_unlisted);
+        self
+    }
+
+    pub fn get(&self, model_name: &str) -> Option<&ModelSpec> {
+        self.models.iter().find(|model| model.name == model_name)
+    }
+
+    /// Parse a JSON manifest:
+    /// `{"repository": "", "unload_unlisted": true, "models": [{"name": "m", "versions": [1],
+    /// "depends_on": ["n"], "config": {..Triton config JSON..}}]}`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value = serde_json::from_str::<Value>(json).map_err(json_error)?;
+        let mut manifest = Self::new();
+        if let Some(repository) = value.get("repository") {
+            manifest.repository_name = repository
+                .as_str()
+                .ok_or_else(|| manifest_error("`repository` must be a string".to_string()))?
+                .to_string();
+        }
+        if let Some(unload_unlisted) = value.get("unload_unlisted") {
+            manifest.unload_unlisted = unload_unlisted
+                .as_bool()
+                .ok_or_else(|| manifest_error("`unload_unlisted` must be a boolean".to_string()))?;
+        }
+        let models = match value.get("models") {
+            Some(Value::Array(models)) => models.as_slice(),
+            None => &[],
+            Some(_) => return Err(manifest_error("`models` must be an array".to_string())),
+        };
+        for model in models {
+            let name = model
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| manifest_error("every model needs a `name`".to_string()))?;
+            let mut spec = ModelSpec::new(name);
+            if let Some(versions) = model.get("versions") {
+                spec.versions = versions
+                    .as_array()
+                    .and_then(|versions| versions.iter().map(Value::as_i64).collect())
+                    .ok_or_else(|| {
+                        manifest_error(format!("`versions` of `{name}` must hold integers"))
+                    })?;
+            }
+            spec.depends_on = json_strings(model.get("depends_on"), "depends_on")?;
+            if let Some(config) = model.get("config") {
+                spec.config = Some(model_config_from_json(&config.to_string())?);
+            }
+            manifest.set_model(spec);
+        }
+        Ok(manifest)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::pbtxt::model_config_from_json;
+    use std::path::PathBuf;
+
+    // An empty directory of the system temp dir, unique to the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tritonclient-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config() -> pb::ModelConfig {
+        pb::ModelConfig {
+            name: "model".to_string(),
+            backend: "onnxruntime".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn model_dir(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        fs::create_dir_all(dir.join("1/weights")).unwrap();
+        fs::write(dir.join("config.pbtxt"), "name: \"model\"").unwrap();
+        fs::write(dir.join("1/model.onnx"), b"onnx").unwrap();
+        fs::write(dir.join("1/weights/a.bin"), b"weights").unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_file_parameters_by_their_path() {
+        let dir = model_dir("load-files");
+        let request = ModelLoadRequest::new("model")
+            .config(config())
+            .files_from_dir(&dir)
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let parameters = request.parameters().unwrap();
+        let mut names = parameters.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            ["config", "file:1/model.onnx", "file:1/weights/a.bin"]
+        );
+        assert_eq!(
+            parameters["file:1/model.onnx"].parameter_choice,
+            Some(ParameterChoice::BytesParam(b"onnx".to_vec()))
+        );
+        let Some(ParameterChoice::StringParam(json)) = &parameters["config"].parameter_choice
+        else {
+            panic!("the config is not sent as JSON");
+        };
+        assert_eq!(model_config_from_json(json).unwrap(), config());
+        assert_eq!(
+            request.payload_size(),
+            json.len() + "onnx".len() + "weights".len()
+        );
+    }
+
+    #[test]
+    fn files_need_a_config() {
+        let request = ModelLoadRequest::new("model").file("1/model.onnx", b"onnx".to_vec());
+        assert!(request.parameters().is_err());
+        assert!(pb::RepositoryModelLoadRequest::try_from(request).is_err());
+
+        let request = ModelLoadRequest::new("model");
+        assert!(request.parameters().unwrap().is_empty());
+        assert_eq!(request.payload_size(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_a_directory_behind_a_symlink_cycle_once() {
+        let dir = model_dir("load-cycle");
+        std::os::unix::fs::symlink(&dir, dir.join("1/weights/loop")).unwrap();
+        let request = ModelLoadRequest::new("model").files_from_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let request = request.unwrap();
+        let files = request.files().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(files, ["1/model.onnx", "1/weights/a.bin"]);
+    }
+
+    #[test]
+    fn set_model_replaces_a_model_of_the_same_name() {
+        let manifest = RepositoryManifest::new()
+            .model(ModelSpec::new("a"))
+            .model(ModelSpec::new("b"))
+            .model(ModelSpec::new("a").versions(vec![2]));
+        let names = manifest
+            .models
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(manifest.get("a").unwrap().versions, vec![2]);
+    }
+}
@@ -0,0 +1,419 @@
+use super::macros::generate_trait_transform_raw_contents;
+use super::output::{ArrayOutputOneOf, ModelOutput};
+use super::pb::{InferOutputTensor, InferParameter, InferTensorContents, ModelInferResponse};
+use crate::types::{Bytes, TritonDataTypes};
+use ndarray::ArrayD;
+use std::collections::HashMap;
+
+/// Serialization of an ndarray into the raw output contents of a response.
+pub trait TransformRawContents: Sized + 'static {
+    fn datatype() -> TritonDataTypes;
+
+    fn transform(array: ArrayD<Self>) -> Bytes;
+}
+
+impl TransformRawContents for bool {
+    fn datatype() -> TritonDataTypes {
+        TritonDataTypes::BOOL
+    }
+
+    fn transform(array: ArrayD<Self>) -> Bytes {
+        array.iter().map(|v| *v as u8).collect()
+    }
+}
+
+impl TransformRawContents for Bytes {
+    fn datatype() -> TritonDataTypes {
+        TritonDataTypes::BYTES
+    }
+
+    fn transform(array: ArrayD<Self>) -> Bytes {
+        bytes_to_raw(array.iter())
+    }
+}
+
+generate_trait_transform_raw_contents!(i8, INT8);
+generate_trait_transform_raw_contents!(i16, INT16);
+generate_trait_transform_raw_contents!(i32, INT32);
+generate_trait_transform_raw_contents!(i64, INT64);
+generate_trait_transform_raw_contents!(u8, UINT8);
+generate_trait_transform_raw_contents!(u16, UINT16);
+generate_trait_transform_raw_contents!(u32, UINT32);
+generate_trait_transform_raw_contents!(u64, UINT64);
+generate_trait_transform_raw_contents!(f32, FP32);
+generate_trait_transform_raw_contents!(f64, FP64);
+
+/// Elements prefixed by their little-endian u32 length, the layout of Triton `BYTES` tensors.
+fn bytes_to_raw<'a>(elements: impl Iterator<Item = &'a Bytes>) -> Bytes {
+    let mut raw = vec![];
+    for element in elements {
+        raw.extend_from_slice(&(element.len() as u32).to_le_bytes());
+        raw.extend_from_slice(element);
+    }
+    raw
+}
+
+/// Serialize typed tensor contents of a request into raw contents, as Triton does for its outputs.
+pub fn raw_from_tensor_contents(
+    datatype: &TritonDataTypes,
+    contents: &InferTensorContents,
+) -> Option<Bytes> {
+    fn le<T, const N: usize>(values: &[T], f: impl Fn(&T) -> [u8; N]) -> Bytes {
+        values.iter().flat_map(f).collect()
+    }
+
+    let raw = match datatype {
+        TritonDataTypes::BOOL => contents.bool_contents.iter().map(|v| *v as u8).collect(),
+        TritonDataTypes::INT8 => le(&contents.int_contents, |v| (*v as i8).to_le_bytes()),
+        TritonDataTypes::INT16 => le(&contents.int_contents, |v| (*v as i16).to_le_bytes()),
+        TritonDataTypes::INT32 => le(&contents.int_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::INT64 => le(&contents.int64_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::UINT8 => le(&contents.uint_contents, |v| (*v as u8).to_le_bytes()),
+        TritonDataTypes::UINT16 => le(&contents.uint_contents, |v| (*v as u16).to_le_bytes()),
+        TritonDataTypes::UINT32 => le(&contents.uint_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::UINT64 => le(&contents.uint64_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::FP32 => le(&contents.fp32_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::FP64 => le(&contents.fp64_contents, |v| v.to_le_bytes()),
+        TritonDataTypes::BYTES => bytes_to_raw(contents.bytes_contents.iter()),
+        TritonDataTypes::FP16 | TritonDataTypes::BF16 => return None,
+    };
+    Some(raw)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InferOutput {
+    inner: InferOutputTensor,
+    raw: Bytes,
+}
+
+impl InferOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.inner.name = name;
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.set_name(name);
+        self
+    }
+
+    pub fn set_parameters(&mut self, parameters: HashMap<String, InferParameter>) {
+        self.inner.parameters = parameters;
+    }
+
+    pub fn parameters(mut self, parameters: HashMap<String, InferParameter>) -> Self {
+        self.set_parameters(parameters);
+        self
+    }
+
+    /// Set datatype, shape and raw contents from the array.
+    pub fn set_data_from_ndarray<T>(&mut self, array: ArrayD<T>)
+    where
+        T: TransformRawContents,
+    {
+        self.inner.datatype = T::datatype().to_string();
+        self.inner.shape = array.shape().iter().map(|v| *v as i64).collect();
+        self.raw = TransformRawContents::transform(array);
+    }
+
+    pub fn data_from_ndarray<T>(mut self, array: ArrayD<T>) -> Self
+    where
+        T: TransformRawContents,
+    {
+        self.set_data_from_ndarray(array);
+        self
+    }
+
+    pub fn set_data_from_array_output(&mut self, array: ArrayOutputOneOf) {
+        match array {
+            ArrayOutputOneOf::BOOL(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::INT8(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::INT16(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::INT32(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::INT64(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::UINT8(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::UINT16(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::UINT32(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::UINT64(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::FP32(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::FP64(array) => self.set_data_from_ndarray(array),
+            ArrayOutputOneOf::BYTES(array) => self.set_data_from_ndarray(array),
+        }
+    }
+
+    pub fn data_from_array_output(mut self, array: ArrayOutputOneOf) -> Self {
+        self.set_data_from_array_output(array);
+        self
+    }
+
+    /// Already serialized contents, e.g. FP16 data. No need to call it after
+    /// (set_)data_from_ndarray
+    pub fn set_raw(&mut self, datatype: TritonDataTypes, shape: Vec<i64>, raw: Bytes) {
+        self.inner.datatype = datatype.to_string();
+        self.inner.shape = shape;
+        self.raw = raw;
+    }
+
+    pub fn raw(mut self, datatype: TritonDataTypes, shape: Vec<i64>, raw: Bytes) -> Self {
+        self.set_raw(datatype, shape, raw);
+        self
+    }
+
+    pub(crate) fn build(self) -> (InferOutputTensor, Bytes) {
+        (self.inner, self.raw)
+    }
+}
+
+/// Builder of the `ModelInferResponse` a Triton-compatible service sends back.
+#[derive(Clone, Debug, Default)]
+pub struct ModelResponse {
+    inner: ModelInferResponse,
+}
+
+impl ModelResponse {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_model_name(&mut self, model_name: String) {
+        self.inner.model_name = model_name;
+    }
+
+    pub fn model_name(mut self, model_name: String) -> Self {
+        self.set_model_name(model_name);
+        self
+    }
+
+    pub fn set_model_version(&mut self, model_version: String) {
+        self.inner.model_version = model_version;
+    }
+
+    pub fn model_version(mut self, model_version: String) -> Self {
+        self.set_model_version(model_version);
+        self
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.inner.id = id;
+    }
+
+    pub fn id(mut self, id: String) -> Self {
+        self.set_id(id);
+        self
+    }
+
+    pub fn set_parameters(&mut self, parameters: HashMap<String, InferParameter>) {
+        self.inner.parameters = parameters;
+    }
+
+    pub fn parameters(mut self, parameters: HashMap<String, InferParameter>) -> Self {
+        self.set_parameters(parameters);
+        self
+    }
+
+    pub fn set_output(&mut self, output: InferOutput) {
+        let (tensor, raw) = output.build();
+        self.inner.outputs.push(tensor);
+        self.inner.raw_output_contents.push(raw);
+    }
+
+    pub fn output(mut self, output: InferOutput) -> Self {
+        self.set_output(output);
+        self
+    }
+
+    pub fn set_outputs(&mut self, outputs: Vec<InferOutput>) {
+        self.inner.outputs.clear();
+        self.inner.raw_output_contents.clear();
+        for output in outputs {
+            self.set_output(output);
+        }
+    }
+
+    pub fn outputs(mut self, outputs: Vec<InferOutput>) -> Self {
+        self.set_outputs(outputs);
+        self
+    }
+
+    /// Outputs are added sorted by name so that the response is deterministic.
+    pub fn set_model_output(&mut self, output: ModelOutput) {
+        let mut arrays = output.into_inner().into_iter().collect::<Vec<_>>();
+        arrays.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, array) in arrays {
+            self.set_output(InferOutput::new().name(name).data_from_array_output(array));
+        }
+    }
+
+    pub fn model_output(mut self, output: ModelOutput) -> Self {
+        self.set_model_output(output);
+        self
+    }
+
+    pub(crate) fn build(self) -> ModelInferResponse {
+        self.inner
+    }
+}
+
+impl From<ModelResponse> for ModelInferResponse {
+    fn from(value: ModelResponse) -> Self {
+        value.build()
+    }
+}
+
+impl From<ModelOutput> for ModelInferResponse {
+    fn from(value: ModelOutput) -> Self {
+        ModelResponse::new().model_output(value).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(output: &ModelOutput, name: &str) -> Vec<i32> {
+        match output.as_ndarray(name) {
+            Some(ArrayOutputOneOf::INT32(array)) => array.iter().copied().collect(),
+            other => panic!("unexpected output {other:?}"),
+        }
+    }
+
+    #[test]
+    fn builds_outputs_from_ndarrays() {
+        let array = ArrayD::from_shape_vec(vec![2, 1], vec![1i32, -2]).unwrap();
+        let (tensor, raw) = InferOutput::new()
+            .name("x".to_string())
+            .data_from_ndarray(array)
+            .build();
+        assert_eq!(tensor.name, "x");
+        assert_eq!(tensor.datatype, "INT32");
+        assert_eq!(tensor.shape, vec![2, 1]);
+        assert_eq!(raw, vec![1, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff]);
+
+        let array = ArrayD::from_shape_vec(vec![3], vec![true, false, true]).unwrap();
+        let (tensor, raw) = InferOutput::new().data_from_ndarray(array).build();
+        assert_eq!(tensor.datatype, "BOOL");
+        assert_eq!(raw, vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn replaces_outputs_and_keeps_raw_contents_aligned() {
+        let output = |name: &str, value: i32| {
+            InferOutput::new()
+                .name(name.to_string())
+                .data_from_ndarray(ArrayD::from_shape_vec(vec![1], vec![value]).unwrap())
+        };
+        let response = ModelResponse::new()
+            .model_name("model".to_string())
+            .model_version("2".to_string())
+            .id("7".to_string())
+            .output(output("a", 1))
+            .outputs(vec![output("b", 2), output("c", 3)])
+            .build();
+        assert_eq!(response.model_name, "model");
+        assert_eq!(response.model_version, "2");
+        assert_eq!(response.id, "7");
+        let names = response
+            .outputs
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["b", "c"]);
+        assert_eq!(response.raw_output_contents.len(), 2);
+
+        let output = ModelOutput::new(response).unwrap();
+        assert_eq!(ints(&output, "b"), vec![2]);
+        assert_eq!(ints(&output, "c"), vec![3]);
+    }
+
+    #[test]
+    fn round_trips_model_outputs_sorted_by_name() {
+        let ints_array = ArrayD::from_shape_vec(vec![2, 2], vec![1i32, 2, 3, 4]).unwrap();
+        let names = vec![b"first".to_vec(), vec![]];
+        let names_array = ArrayD::from_shape_vec(vec![2], names.clone()).unwrap();
+        let response = ModelResponse::new()
+            .output(
+                InferOutput::new()
+                    .name("z".to_string())
+                    .data_from_ndarray(ints_array),
+            )
+            .output(
+                InferOutput::new()
+                    .name("a".to_string())
+                    .data_from_ndarray(names_array),
+            )
+            .build();
+        let output = ModelOutput::new(response).unwrap();
+
+        let response = ModelInferResponse::from(output);
+        let order = response
+            .outputs
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["a", "z"]);
+        let output = ModelOutput::new(response).unwrap();
+        assert_eq!(ints(&output, "z"), vec![1, 2, 3, 4]);
+        match output.as_ndarray("a") {
+            Some(ArrayOutputOneOf::BYTES(array)) => {
+                assert_eq!(array.iter().cloned().collect::<Vec<_>>(), names)
+            }
+            other => panic!("unexpected output {other:?}"),
+        }
+    }
+
+    #[test]
+    fn serializes_typed_contents_like_triton() {
+        let contents = InferTensorContents {
+            int_contents: vec![1, -1],
+            ..Default::default()
+        };
+        let raw = |datatype| raw_from_tensor_contents(&datatype, &contents);
+        assert_eq!(raw(TritonDataTypes::INT8), Some(vec![1, 0xff]));
+        assert_eq!(raw(TritonDataTypes::INT16), Some(vec![1, 0, 0xff, 0xff]));
+        assert_eq!(raw(TritonDataTypes::INT64), Some(vec![]));
+        assert_eq!(raw(TritonDataTypes::FP16), None);
+        assert_eq!(raw(TritonDataTypes::BF16), None);
+
+        let contents = InferTensorContents {
+            fp32_contents: vec![1.5],
+            bool_contents: vec![true],
+            ..Default::default()
+        };
+        let raw = |datatype| raw_from_tensor_contents(&datatype, &contents);
+        assert_eq!(
+            raw(TritonDataTypes::FP32),
+            Some(1.5f32.to_le_bytes().to_vec())
+        );
+        assert_eq!(raw(TritonDataTypes::BOOL), Some(vec![1]));
+    }
+
+    // Triton sends each BYTES element after its little-endian u32 length.
+    #[test]
+    fn bytes_length_prefixes_are_little_endian() {
+        let mut raw = b"\x03\x00\x00\x00abc\x00\x00\x00\x00\x01\x01\x00\x00".to_vec();
+        raw.extend([b'x'; 257]);
+        let response = ModelInferResponse {
+            outputs: vec![InferOutputTensor {
+                name: "text".to_string(),
+                datatype: "BYTES".to_string(),
+                shape: vec![3],
+                ..Default::default()
+            }],
+            raw_output_contents: vec![raw.clone()],
+            ..Default::default()
+        };
+        let output = ModelOutput::new(response).unwrap();
+        let elements = match output.as_ndarray("text") {
+            Some(ArrayOutputOneOf::BYTES(array)) => array.iter().cloned().collect::<Vec<_>>(),
+            other => panic!("unexpected output {other:?}"),
+        };
+        assert_eq!(elements, vec![b"abc".to_vec(), vec![], vec![b'x'; 257]]);
+
+        let array = ArrayD::from_shape_vec(vec![3], elements).unwrap();
+        let (_, written) = InferOutput::new().data_from_ndarray(array).build();
+        assert_eq!(written, raw);
+    }
+}
@@ -0,0 +1,467 @@
+use super::client::{Error, Result};
+use super::pb::log_settings_request::setting_value::ParameterChoice as LogRequestChoice;
+use super::pb::log_settings_response::setting_value::ParameterChoice as LogResponseChoice;
+use super::pb::{self, LogSettingValue, TraceSettingValue};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceLevel {
+    Off,
+    Timestamps,
+    Tensors,
+}
+
+impl Display for TraceLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            Self::Off => "OFF",
+            Self::Timestamps => "TIMESTAMPS",
+            Self::Tensors => "TENSORS",
+        };
+        write!(f, "{level}")
+    }
+}
+
+impl FromStr for TraceLevel {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self> {
+        match level.to_uppercase().as_str() {
+            "OFF" => Ok(Self::Off),
+            "TIMESTAMPS" => Ok(Self::Timestamps),
+            "TENSORS" => Ok(Self::Tensors),
+            _ => Err(Error::ConversionError(format!(
+                "Unknown trace level `{level}`"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceMode {
+    Triton,
+    OpenTelemetry,
+}
+
+impl Display for TraceMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mode = match self {
+            Self::Triton => "triton",
+            Self::OpenTelemetry => "opentelemetry",
+        };
+        write!(f, "{mode}")
+    }
+}
+
+impl FromStr for TraceMode {
+    type Err = Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode.to_lowercase().as_str() {
+            "triton" => Ok(Self::Triton),
+            "opentelemetry" => Ok(Self::OpenTelemetry),
+            _ => Err(Error::ConversionError(format!(
+                "Unknown trace mode `{mode}`"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceSetting {
+    Level,
+    Rate,
+    Count,
+    LogFrequency,
+    File,
+    Mode,
+}
+
+impl TraceSetting {
+    pub const ALL: [Self; 6] = [
+        Self::Level,
+        Self::Rate,
+        Self::Count,
+        Self::LogFrequency,
+        Self::File,
+        Self::Mode,
+    ];
+
+    /// Key of the setting in `trace_setting` requests
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Level => "trace_level",
+            Self::Rate => "trace_rate",
+            Self::Count => "trace_count",
+            Self::LogFrequency => "log_frequency",
+            Self::File => "trace_file",
+            Self::Mode => "trace_mode",
+        }
+    }
+}
+
+/// Changes to the trace settings of the server, or of one model on top of them.
+#[derive(Clone, Debug, Default)]
+pub struct TraceSettingsUpdate {
+    model_name: String,
+    settings: HashMap<String, TraceSettingValue>,
+}
+
+impl TraceSettingsUpdate {
+    /// Change the server-wide settings.
+    pub fn global() -> Self {
+        Self::default()
+    }
+
+    /// Change the settings of one model, the ones left untouched follow the global settings.
+    pub fn for_model<T: ToString>(model_name: T) -> Self {
+        Self {
+            model_name: model_name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn set(&mut self, setting: TraceSetting, value: Vec<String>) {
+        self.settings
+            .insert(setting.key().to_string(), TraceSettingValue { value });
+    }
+
+    pub fn set_level(&mut self, levels: &[TraceLevel]) {
+        let levels = levels.iter().map(TraceLevel::to_string).collect();
+        self.set(TraceSetting::Level, levels);
+    }
+
+    pub fn level(mut self, levels: &[TraceLevel]) -> Self {
+        self.set_level(levels);
+        self
+    }
+
+    /// Trace one request out of `rate`
+    pub fn set_rate(&mut self, rate: u32) {
+        self.set(TraceSetting::Rate, vec![rate.to_string()]);
+    }
+
+    pub fn rate(mut self, rate: u32) -> Self {
+        self.set_rate(rate);
+        self
+    }
+
+    /// Stop tracing after `count` traces, -1 for no limit
+    pub fn set_count(&mut self, count: i32) {
+        self.set(TraceSetting::Count, vec![count.to_string()]);
+    }
+
+    pub fn count(mut self, count: i32) -> Self {
+        self.set_count(count);
+        self
+    }
+
+    /// Write the trace file every `log_frequency` traces, 0 to write it at shutdown only
+    pub fn set_log_frequency(&mut self, log_frequency: u32) {
+        self.set(TraceSetting::LogFrequency, vec![log_frequency.to_string()]);
+    }
+
+    pub fn log_frequency(mut self, log_frequency: u32) -> Self {
+        self.set_log_frequency(log_frequency);
+        self
+    }
+
+    pub fn set_file<T: ToString>(&mut self, file: T) {
+        self.set(TraceSetting::File, vec![file.to_string()]);
+    }
+
+    pub fn file<T: ToString>(mut self, file: T) -> Self {
+        self.set_file(file);
+        self
+    }
+
+    pub fn set_mode(&mut self, mode: TraceMode) {
+        self.set(TraceSetting::Mode, vec![mode.to_string()]);
+    }
+
+    pub fn mode(mut self, mode: TraceMode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    /// Drop the model override of `setting` so that it follows the global one again.
+    pub fn clear_setting(&mut self, setting: TraceSetting) {
+        self.set(setting, vec![]);
+    }
+
+    pub fn clear(mut self, setting: TraceSetting) -> Self {
+        self.clear_setting(setting);
+        self
+    }
+
+    /// Drop every model override.
+    pub fn clear_all(mut self) -> Self {
+        for setting in TraceSetting::ALL {
+            self.clear_setting(setting);
+        }
+        self
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+}
+
+impl From<TraceSettingsUpdate> for pb::TraceSettingRequest {
+    fn from(update: TraceSettingsUpdate) -> Self {
+        Self {
+            settings: update.settings,
+            model_name: update.model_name,
+        }
+    }
+}
+
+/// Trace settings in effect, for the server or a model.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceSettings {
+    pub level: Vec<TraceLevel>,
+    pub rate: Option<u32>,
+    pub count: Option<i32>,
+    pub log_frequency: Option<u32>,
+    pub file: Option<String>,
+    pub mode: Option<TraceMode>,
+    /// Settings without a typed field, e.g. `opentelemetry` configuration
+    pub other: BTreeMap<String, Vec<String>>,
+}
+
+fn single<'a>(key: &str, values: &'a [String]) -> Result<Option<&'a str>> {
+    match values {
+        [] => Ok(None),
+        [value] => Ok(Some(value)),
+        _ => Err(Error::ConversionError(format!(
+            "Trace setting `{key}` has several values {values:?}"
+        ))),
+    }
+}
+
+fn parse_single<T: FromStr>(key: &str, values: &[String]) -> Result<Option<T>> {
+    single(key, values)?
+        .map(|value| {
+            value.parse::<T>().map_err(|_| {
+                Error::ConversionError(format!("Invalid value `{value}` of trace setting `{key}`"))
+            })
+        })
+        .transpose()
+}
+
+impl TryFrom<pb::TraceSettingResponse> for TraceSettings {
+    type Error = Error;
+
+    fn try_from(response: pb::TraceSettingResponse) -> Result<Self> {
+        let mut settings = Self::default();
+        for (key, value) in response.settings {
+            let values = value.value;
+            match key.as_str() {
+                "trace_level" => {
+                    settings.level = values
+                        .iter()
+                        .map(|level| level.parse())
+                        .collect::<Result<_>>()?
+                }
+                "trace_rate" => settings.rate = parse_single(&key, &values)?,
+                "trace_count" => settings.count = parse_single(&key, &values)?,
+                "log_frequency" => settings.log_frequency = parse_single(&key, &values)?,
+                "trace_file" => settings.file = single(&key, &values)?.map(str::to_string),
+                "trace_mode" => {
+                    settings.mode = single(&key, &values)?.map(str::parse).transpose()?
+                }
+                _ => {
+                    settings.other.insert(key, values);
+                }
+            }
+        }
+        Ok(settings)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LogFormat {
+    Default,
+    Iso8601,
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = match self {
+            Self::Default => "default",
+            Self::Iso8601 => "ISO8601",
+        };
+        write!(f, "{format}")
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "iso8601" => Ok(Self::Iso8601),
+            _ => Err(Error::ConversionError(format!(
+                "Unknown log format `{format}`"
+            ))),
+        }
+    }
+}
+
+/// Changes to the log settings. Logging is configured for the whole server, the protocol has
+/// no model scope for it.
+#[derive(Clone, Debug, Default)]
+pub struct LogSettingsUpdate {
+    settings: HashMap<String, LogSettingValue>,
+}
+
+impl LogSettingsUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, key: &str, value: LogRequestChoice) {
+        self.settings.insert(
+            key.to_string(),
+            LogSettingValue {
+                parameter_choice: Some(value),
+            },
+        );
+    }
+
+    pub fn set_file<T: ToString>(&mut self, file: T) {
+        self.set("log_file", LogRequestChoice::StringParam(file.to_string()));
+    }
+
+    pub fn file<T: ToString>(mut self, file: T) -> Self {
+        self.set_file(file);
+        self
+    }
+
+    pub fn set_info(&mut self, info: bool) {
+        self.set("log_info", LogRequestChoice::BoolParam(info));
+    }
+
+    pub fn info(mut self, info: bool) -> Self {
+        self.set_info(info);
+        self
+    }
+
+    pub fn set_warning(&mut self, warning: bool) {
+        self.set("log_warning", LogRequestChoice::BoolParam(warning));
+    }
+
+    pub fn warning(mut self, warning: bool) -> Self {
+        self.set_warning(warning);
+        self
+    }
+
+    pub fn set_error(&mut self, error: bool) {
+        self.set("log_error", LogRequestChoice::BoolParam(error));
+    }
+
+    pub fn error(mut self, error: bool) -> Self {
+        self.set_error(error);
+        self
+    }
+
+    /// 0 disables verbose logging
+    pub fn set_verbose_level(&mut self, verbose_level: u32) {
+        self.set(
+            "log_verbose_level",
+            LogRequestChoice::Uint32Param(verbose_level),
+        );
+    }
+
+    pub fn verbose_level(mut self, verbose_level: u32) -> Self {
+        self.set_verbose_level(verbose_level);
+        self
+    }
+
+    pub fn set_format(&mut self, format: LogFormat) {
+        self.set(
+            "log_format",
+            LogRequestChoice::StringParam(format.to_string()),
+        );
+    }
+
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.set_format(format);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+}
+
+impl From<LogSettingsUpdate> for pb::LogSettingsRequest {
+    fn from(update: LogSettingsUpdate) -> Self {
+        Self {
+            settings: update.settings,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogSettings {
+    pub file: Option<String>,
+    pub info: Option<bool>,
+    pub warning: Option<bool>,
+    pub error: Option<bool>,
+    pub verbose_level: Option<u32>,
+    pub format: Option<LogFormat>,
+    /// Settings without a typed field
+    pub other: BTreeMap<String, LogResponseChoice>,
+}
+
+fn log_type_error(key: &str, value: &LogResponseChoice) -> Error {
+    Error::ConversionError(format!("Unexpected value {value:?} of log setting `{key}`"))
+}
+
+impl TryFrom<pb::LogSettingsResponse> for LogSettings {
+    type Error = Error;
+
+    fn try_from(response: pb::LogSettingsResponse) -> Result<Self> {
+        let mut settings = Self::default();
+        for (key, value) in response.settings {
+            let Some(value) = value.parameter_choice else {
+                continue;
+            };
+            let flag = |value: &LogResponseChoice| match value {
+                LogResponseChoice::BoolParam(flag) => Ok(*flag),
+                _ => Err(log_type_error(&key, value)),
+            };
+            match key.as_str() {
+                "log_file" => match value {
+                    LogResponseChoice::StringParam(file) => settings.file = Some(file),
+                    _ => return Err(log_type_error(&key, &value)),
+                },
+                "log_info" => settings.info = Some(flag(&value)?),
+                "log_warning" => settings.warning = Some(flag(&value)?),
+                "log_error" => settings.error = Some(flag(&value)?),
+                "log_verbose_level" => match value {
+                    LogResponseChoice::Uint32Param(level) => settings.verbose_level = Some(level),
+                    _ => return Err(log_type_error(&key, &value)),
+                },
+                "log_format" => match &value {
+                    LogResponseChoice::StringParam(format) => {
+                        settings.format = Some(format.parse()?)
+                    }
+                    _ => return Err(log_type_error(&key, &value)),
+                },
+                _ => {
+                    settings.other.insert(key, value);
+                }
+            }
+        }
+        Ok(settings)
+    }
+}
@@ -0,0 +1,399 @@
+use super::pb::{self, StatisticDuration};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Cumulative count and total duration of one kind of event.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DurationStat {
+    pub count: u64,
+    pub total: Duration,
+}
+
+impl DurationStat {
+    pub fn average(&self) -> Option<Duration> {
+        (self.count > 0)
+            .then(|| Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64))
+    }
+
+    /// Events between `earlier` and `self`, all of `self` when the counters were reset in
+    /// between (server restart, model reload).
+    pub fn since(&self, earlier: &Self, restarted: bool) -> Self {
+        if restarted {
+            *self
+        } else {
+            Self {
+                count: self.count.saturating_sub(earlier.count),
+                total: self.total.saturating_sub(earlier.total),
+            }
+        }
+    }
+}
+
+impl From<Option<&StatisticDuration>> for DurationStat {
+    fn from(stat: Option<&StatisticDuration>) -> Self {
+        stat.map(|stat| Self {
+            count: stat.count,
+            total: Duration::from_nanos(stat.ns),
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// Executions of one batch size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchStat {
+    pub compute_input: DurationStat,
+    pub compute_infer: DurationStat,
+    pub compute_output: DurationStat,
+}
+
+impl BatchStat {
+    pub fn executions(&self) -> u64 {
+        self.compute_infer.count
+    }
+
+    pub fn since(&self, earlier: &Self, restarted: bool) -> Self {
+        Self {
+            compute_input: self.compute_input.since(&earlier.compute_input, restarted),
+            compute_infer: self.compute_infer.since(&earlier.compute_infer, restarted),
+            compute_output: self
+                .compute_output
+                .since(&earlier.compute_output, restarted),
+        }
+    }
+}
+
+/// Typed `pb::ModelStatistics` of one model version.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelStats {
+    pub name: String,
+    pub version: String,
+    pub last_inference: Option<SystemTime>,
+    pub inference_count: u64,
+    pub execution_count: u64,
+    pub success: DurationStat,
+    pub fail: DurationStat,
+    pub queue: DurationStat,
+    pub compute_input: DurationStat,
+    pub compute_infer: DurationStat,
+    pub compute_output: DurationStat,
+    pub cache_hit: DurationStat,
+    pub cache_miss: DurationStat,
+    /// Batch size -> executions with that size
+    pub batches: BTreeMap<u64, BatchStat>,
+}
+
+impl ModelStats {
+    /// Successful and failed requests
+    pub fn requests(&self) -> u64 {
+        self.success.count + self.fail.count
+    }
+
+    pub fn average_queue(&self) -> Option<Duration> {
+        self.queue.average()
+    }
+
+    pub fn average_compute_input(&self) -> Option<Duration> {
+        self.compute_input.average()
+    }
+
+    pub fn average_compute_infer(&self) -> Option<Duration> {
+        self.compute_infer.average()
+    }
+
+    pub fn average_compute_output(&self) -> Option<Duration> {
+        self.compute_output.average()
+    }
+
+    /// Share of cache lookups that hit, `None` when the response cache was not used.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let lookups = self.cache_hit.count + self.cache_miss.count;
+        (lookups > 0).then(|| self.cache_hit.count as f64 / lookups as f64)
+    }
+
+    /// Batch size -> number of executions.
+    pub fn batch_histogram(&self) -> BTreeMap<u64, u64> {
+        self.batches
+            .iter()
+            .map(|(size, stat)| (*size, stat.executions()))
+            .collect()
+    }
+
+    /// Whether the counters went backwards since `earlier`, as after a server restart or a
+    /// model reload.
+    pub fn restarted_since(&self, earlier: &Self) -> bool {
+        self.inference_count < earlier.inference_count
+            || self.execution_count < earlier.execution_count
+            || self.requests() < earlier.requests()
+    }
+
+    /// Counters accumulated between `earlier` and `self`, all of them are taken from zero when
+    /// the model restarted in between.
+    pub fn since(&self, earlier: &Self) -> Self {
+        let restarted = self.restarted_since(earlier);
+        let count = |now: u64, then: u64| {
+            if restarted {
+                now
+            } else {
+                now.saturating_sub(then)
+            }
+        };
+        let since = |now: &DurationStat, then: &DurationStat| now.since(then, restarted);
+        Self {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            last_inference: self.last_inference,
+            inference_count: count(self.inference_count, earlier.inference_count),
+            execution_count: count(self.execution_count, earlier.execution_count),
+            success: since(&self.success, &earlier.success),
+            fail: since(&self.fail, &earlier.fail),
+            queue: since(&self.queue, &earlier.queue),
+            compute_input: since(&self.compute_input, &earlier.compute_input),
+            compute_infer: since(&self.compute_infer, &earlier.compute_infer),
+            compute_output: since(&self.compute_output, &earlier.compute_output),
+            cache_hit: since(&self.cache_hit, &earlier.cache_hit),
+            cache_miss: since(&self.cache_miss, &earlier.cache_miss),
+            batches: self
+                .batches
+                .iter()
+                .map(|(size, stat)| {
+                    let stat = match earlier.batches.get(size) {
+                        Some(then) => stat.since(then, restarted),
+                        None => *stat,
+                    };
+                    (*size, stat)
+                })
+                .filter(|(_, stat)| stat.executions() > 0)
+                .collect(),
+        }
+    }
+}
+
+impl From<&pb::ModelStatistics> for ModelStats {
+    fn from(stats: &pb::ModelStatistics) -> Self {
+        let inference = stats.inference_stats.as_ref();
+        let stat = |f: fn(&pb::InferStatistics) -> Option<&StatisticDuration>| {
+            DurationStat::from(inference.and_then(f))
+        };
+        Self {
+            name: stats.name.clone(),
+            version: stats.version.clone(),
+            last_inference: (stats.last_inference > 0)
+                .then(|| UNIX_EPOCH + Duration::from_millis(stats.last_inference)),
+            inference_count: stats.inference_count,
+            execution_count: stats.execution_count,
+            success: stat(|s| s.success.as_ref()),
+            fail: stat(|s| s.fail.as_ref()),
+            queue: stat(|s| s.queue.as_ref()),
+            compute_input: stat(|s| s.compute_input.as_ref()),
+            compute_infer: stat(|s| s.compute_infer.as_ref()),
+            compute_output: stat(|s| s.compute_output.as_ref()),
+            cache_hit: stat(|s| s.cache_hit.as_ref()),
+            cache_miss: stat(|s| s.cache_miss.as_ref()),
+            batches: stats
+                .batch_stats
+                .iter()
+                .map(|batch| {
+                    let stat = BatchStat {
+                        compute_input: batch.compute_input.as_ref().into(),
+                        compute_infer: batch.compute_infer.as_ref().into(),
+                        compute_output: batch.compute_output.as_ref().into(),
+                    };
+                    (batch.batch_size, stat)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Statistics of every model version at one point in time.
+#[derive(Clone, Debug)]
+pub struct StatisticsSnapshot {
+    pub taken_at: Instant,
+    pub models: Vec<ModelStats>,
+}
+
+impl StatisticsSnapshot {
+    pub fn new(response: &pb::ModelStatisticsResponse) -> Self {
+        Self {
+            taken_at: Instant::now(),
+            models: response.model_stats.iter().map(ModelStats::from).collect(),
+        }
+    }
+
+    pub fn get(&self, model_name: &str, version: &str) -> Option<&ModelStats> {
+        self.models
+            .iter()
+            .find(|stats| stats.name == model_name && stats.version == version)
+    }
+
+    /// What happened between `earlier` and `self`, per model version present in `self`.
+    pub fn since(&self, earlier: &Self) -> StatisticsDelta {
+        let interval = self.taken_at.saturating_duration_since(earlier.taken_at);
+        StatisticsDelta {
+            interval,
+            models: self
+                .models
+                .iter()
+                .map(|stats| ModelStatsDelta {
+                    interval,
+                    stats: match earlier.get(&stats.name, &stats.version) {
+                        Some(then) => stats.since(then),
+                        None => stats.clone(),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<pb::ModelStatisticsResponse> for StatisticsSnapshot {
+    fn from(response: pb::ModelStatisticsResponse) -> Self {
+        Self::new(&response)
+    }
+}
+
+/// Statistics of one model version over an interval.
+#[derive(Clone, Debug)]
+pub struct ModelStatsDelta {
+    pub interval: Duration,
+    pub stats: ModelStats,
+}
+
+impl ModelStatsDelta {
+    fn per_second(&self, count: u64) -> f64 {
+        match self.interval.as_secs_f64() {
+            seconds if seconds > 0.0 => count as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    /// Requests per second, failed ones included
+    pub fn request_rate(&self) -> f64 {
+        self.per_second(self.stats.requests())
+    }
+
+    /// Inferences (batch rows) per second
+    pub fn inference_rate(&self) -> f64 {
+        self.per_second(self.stats.inference_count)
+    }
+
+    pub fn execution_rate(&self) -> f64 {
+        self.per_second(self.stats.execution_count)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatisticsDelta {
+    pub interval: Duration,
+    pub models: Vec<ModelStatsDelta>,
+}
+
+impl StatisticsDelta {
+    pub fn get(&self, model_name: &str, version: &str) -> Option<&ModelStatsDelta> {
+        self.models
+            .iter()
+            .find(|delta| delta.stats.name == model_name && delta.stats.version == version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duration(count: u64, ms: u64) -> Option<StatisticDuration> {
+        Some(StatisticDuration {
+            count,
+            ns: ms * 1_000_000,
+        })
+    }
+
+    fn stats(requests: u64, batches: &[(u64, u64)]) -> pb::ModelStatistics {
+        pb::ModelStatistics {
+            name: "model".to_string(),
+            version: "1".to_string(),
+            inference_count: 4 * requests,
+            execution_count: requests,
+            inference_stats: Some(pb::InferStatistics {
+                success: duration(requests, 10 * requests),
+                queue: duration(requests, 2 * requests),
+                compute_infer: duration(requests, 6 * requests),
+                ..Default::default()
+            }),
+            batch_stats: batches
+                .iter()
+                .map(|(batch_size, executions)| pb::InferBatchStatistics {
+                    batch_size: *batch_size,
+                    compute_infer: duration(*executions, *executions),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(stats: pb::ModelStatistics, taken_at: Instant) -> StatisticsSnapshot {
+        StatisticsSnapshot {
+            taken_at,
+            models: vec![ModelStats::from(&stats)],
+        }
+    }
+
+    #[test]
+    fn computes_deltas_and_rates() {
+        let start = Instant::now();
+        let earlier = snapshot(stats(10, &[(4, 10)]), start);
+        let later = snapshot(
+            stats(30, &[(4, 10), (8, 20)]),
+            start + Duration::from_secs(2),
+        );
+
+        let delta = later.since(&earlier);
+        assert_eq!(delta.interval, Duration::from_secs(2));
+        let model = delta.get("model", "1").unwrap();
+        assert_eq!(model.stats.requests(), 20);
+        assert_eq!(model.stats.inference_count, 80);
+        assert_eq!(model.stats.average_queue(), Some(Duration::from_millis(2)));
+        assert_eq!(model.stats.batch_histogram(), BTreeMap::from([(8, 20)]));
+        assert_eq!(model.request_rate(), 10.0);
+        assert_eq!(model.inference_rate(), 40.0);
+    }
+
+    #[test]
+    fn restarted_counters_count_from_zero() {
+        let start = Instant::now();
+        let earlier = snapshot(stats(30, &[(4, 30)]), start);
+        let later = snapshot(stats(5, &[(4, 5)]), start + Duration::from_secs(1));
+
+        let model = later.since(&earlier).models.remove(0);
+        assert_eq!(model.stats.requests(), 5);
+        assert_eq!(model.stats.inference_count, 20);
+        assert_eq!(model.stats.batch_histogram(), BTreeMap::from([(4, 5)]));
+    }
+
+    #[test]
+    fn a_restart_resets_every_counter_of_the_model() {
+        let start = Instant::now();
+        let earlier = snapshot(stats(30, &[(4, 30)]), start);
+        // More executions of batch size 4 after the restart than before it
+        let later = snapshot(stats(5, &[(4, 50)]), start + Duration::from_secs(1));
+
+        let model = later.since(&earlier).models.remove(0);
+        assert_eq!(model.stats.requests(), 5);
+        assert_eq!(model.stats.batch_histogram(), BTreeMap::from([(4, 50)]));
+    }
+
+    #[test]
+    fn models_new_since_the_earlier_snapshot_are_kept_whole() {
+        let start = Instant::now();
+        let earlier = StatisticsSnapshot {
+            taken_at: start,
+            models: vec![],
+        };
+        let later = snapshot(stats(3, &[]), start);
+
+        let model = later.since(&earlier).models.remove(0);
+        assert_eq!(model.stats.requests(), 3);
+        assert_eq!(model.request_rate(), 0.0);
+    }
+}
@@ -0,0 +1,382 @@
+mod model;
+pub use model::*;
+mod service;
+
+use crate::grpc::client::{InferenceServerClient, InferenceServerClientConfig, Result};
+use crate::grpc::pb::{self, GrpcInferenceServiceServer, HealthServer};
+use crate::grpc::record::RecordedExchange;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use tokio::io::DuplexStream;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::codegen::http;
+use tonic::metadata::MetadataMap;
+use tonic::server::NamedService;
+use tonic::transport::{Channel, Endpoint, Server, Uri};
+
+const IN_MEMORY_URI: &str = "http://in-memory.mock";
+const DUPLEX_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+pub(crate) struct MockState {
+    pub(crate) live: AtomicBool,
+    pub(crate) ready: watch::Sender<bool>,
+    pub(crate) metadata: pb::ServerMetadataResponse,
+    pub(crate) models: RwLock<HashMap<String, MockModel>>,
+    pub(crate) statistics: Mutex<HashMap<String, pb::ModelStatistics>>,
+    pub(crate) requests: Mutex<Vec<pb::ModelInferRequest>>,
+    // gRPC method name -> calls received
+    pub(crate) calls: Mutex<HashMap<String, usize>>,
+    // gRPC method name -> metadata of its last call
+    pub(crate) metadata_by_method: Mutex<HashMap<String, MetadataMap>>,
+    pub(crate) system_shared_memory:
+        Mutex<HashMap<String, pb::system_shared_memory_status_response::RegionStatus>>,
+    pub(crate) cuda_shared_memory:
+        Mutex<HashMap<String, pb::cuda_shared_memory_status_response::RegionStatus>>,
+    pub(crate) trace_settings: Mutex<HashMap<String, HashMap<String, Vec<String>>>>,
+    pub(crate) log_settings:
+        Mutex<HashMap<String, pb::log_settings_response::setting_value::ParameterChoice>>,
+}
+
+/// Builder for an in-process server speaking the Triton gRPC protocol.
+pub struct MockServer {
+    live: bool,
+    ready: bool,
+    metadata: pb::ServerMetadataResponse,
+    models: HashMap<String, MockModel>,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self {
+            live: true,
+            ready: true,
+            metadata: pb::ServerMetadataResponse {
+                name: "triton".to_string(),
+                version: "mock".to_string(),
+                extensions: vec![],
+            },
+            models: HashMap::new(),
+        }
+    }
+
+    /// A server exposing every model found in a recording, each answering with
+    /// [`InferHandler::replay`].
+    pub fn from_recording(exchanges: Vec<RecordedExchange>) -> Self {
+        let mut names = exchanges
+            .iter()
+            .filter_map(|exchange| exchange.request.as_ref())
+            .map(|request| request.model_name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let mut server = Self::new();
+        for name in names {
+            let recorded = exchanges
+                .iter()
+                .filter(|e| e.request.as_ref().is_some_and(|r| r.model_name == name))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut versions = recorded
+                .iter()
+                .filter_map(|e| e.request.as_ref())
+                .map(|r| r.model_version.clone())
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<_>>();
+            versions.sort();
+            versions.dedup();
+            let mut model = MockModel::new(name).handler(InferHandler::replay(recorded));
+            if !versions.is_empty() {
+                model.set_versions(&versions);
+            }
+            server.set_model(model);
+        }
+        server
+    }
+
+    pub fn set_model(&mut self, model: MockModel) {
+        self.models.insert(model.name().to_string(), model);
+    }
+
+    pub fn model(mut self, model: MockModel) -> Self {
+        self.set_model(model);
+        self
+    }
+
+    pub fn set_live(&mut self, live: bool) {
+        self.live = live;
+    }
+
+    pub fn live(mut self, live: bool) -> Self {
+        self.set_live(live);
+        self
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+    }
+
+    pub fn ready(mut self, ready: bool) -> Self {
+        self.set_ready(ready);
+        self
+    }
+
+    pub fn set_metadata(&mut self, metadata: pb::ServerMetadataResponse) {
+        self.metadata = metadata;
+    }
+
+    pub fn metadata(mut self, metadata: pb::ServerMetadataResponse) -> Self {
+        self.set_metadata(metadata);
+        self
+    }
+
+    /// Serve on an ephemeral port of the loopback interface.
+    pub async fn serve(self) -> Result<MockServerHandle> {
+        self.serve_at("127.0.0.1:0").await
+    }
+
+    pub async fn serve_at<A: ToSocketAddrs>(self, addr: A) -> Result<MockServerHandle> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = self.into_state();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(router(state.clone()).serve_with_incoming_shutdown(
+            TcpListenerStream::new(listener),
+            async {
+                signal.await.ok();
+            },
+        ));
+        Ok(MockServerHandle {
+            state,
+            transport: MockTransport::Tcp(addr),
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Serve over in-memory duplex streams, no socket is opened.
+    pub async fn serve_in_memory(self) -> Result<MockServerHandle> {
+        let state = self.into_state();
+        let (connections, incoming) = mpsc::unbounded_channel::<std::io::Result<DuplexStream>>();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(router(state.clone()).serve_with_incoming_shutdown(
+            UnboundedReceiverStream::new(incoming),
+            async {
+                signal.await.ok();
+            },
+        ));
+        let channel = Endpoint::from_static(IN_MEMORY_URI).connect_with_connector_lazy(
+            tower::service_fn(move |_: Uri| {
+                let connections = connections.clone();
+                async move {
+                    let (client, server) = tokio::io::duplex(DUPLEX_BUFFER_SIZE);
+                    connections.send(Ok(server)).map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::BrokenPipe,
+                            "mock server is shut down",
+                        )
+                    })?;
+                    Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(client))
+                }
+            }),
+        );
+        Ok(MockServerHandle {
+            state,
+            transport: MockTransport::InMemory(channel),
+            shutdown: Some(shutdown),
+        })
+    }
+
+    fn into_state(self) -> Arc<MockState> {
+        let (ready, _) = watch::channel(self.ready);
+        Arc::new(MockState {
+            live: AtomicBool::new(self.live),
+            ready,
+            metadata: self.metadata,
+            models: RwLock::new(self.models),
+            statistics: Mutex::new(HashMap::new()),
+            requests: Mutex::new(vec![]),
+            calls: Mutex::new(HashMap::new()),
+            metadata_by_method: Mutex::new(HashMap::new()),
+            system_shared_memory: Mutex::new(HashMap::new()),
+            cuda_shared_memory: Mutex::new(HashMap::new()),
+            trace_settings: Mutex::new(HashMap::new()),
+            log_settings: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn router(state: Arc<MockState>) -> tonic::transport::server::Router {
+    let service = service::MockService {
+        state: state.clone(),
+    };
+    Server::builder()
+        .add_service(CountCalls {
+            inner: GrpcInferenceServiceServer::new(service.clone())
+                .max_decoding_message_size(usize::MAX)
+                .max_encoding_message_size(usize::MAX),
+            state: state.clone(),
+        })
+        .add_service(CountCalls {
+            inner: HealthServer::new(service),
+            state,
+        })
+}
+
+// Counts the calls of every gRPC method and keeps the metadata of the last one before handing
+// them to `inner`.
+#[derive(Clone)]
+struct CountCalls<S> {
+    inner: S,
+    state: Arc<MockState>,
+}
+
+impl<S: NamedService> NamedService for CountCalls<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> tower::Service<http::Request<B>> for CountCalls<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if let Some((_, method)) = request.uri().path().rsplit_once('/') {
+            let mut calls = self.state.calls.lock().unwrap();
+            *calls.entry(method.to_string()).or_default() += 1;
+            let metadata = MetadataMap::from_headers(request.headers().clone());
+            let mut metadata_by_method = self.state.metadata_by_method.lock().unwrap();
+            metadata_by_method.insert(method.to_string(), metadata);
+        }
+        self.inner.call(request)
+    }
+}
+
+enum MockTransport {
+    Tcp(SocketAddr),
+    InMemory(Channel),
+}
+
+/// A running [`MockServer`]. The server is shut down when the handle is dropped.
+pub struct MockServerHandle {
+    state: Arc<MockState>,
+    transport: MockTransport,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServerHandle {
+    /// Bound address, `None` for in-memory servers.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        match &self.transport {
+            MockTransport::Tcp(addr) => Some(*addr),
+            MockTransport::InMemory(_) => None,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match &self.transport {
+            MockTransport::Tcp(addr) => format!("http://{addr}"),
+            MockTransport::InMemory(_) => IN_MEMORY_URI.to_string(),
+        }
+    }
+
+    pub fn client_config(&self) -> InferenceServerClientConfig {
+        InferenceServerClientConfig::from_uri(self.uri()).unwrap()
+    }
+
+    pub fn client(&self) -> InferenceServerClient {
+        self.client_with_config(self.client_config())
+    }
+
+    /// Build a client connected to this server, `config.uri` is overridden.
+    pub fn client_with_config(
+        &self,
+        mut config: InferenceServerClientConfig,
+    ) -> InferenceServerClient {
+        config.uri = self.client_config().uri;
+        match &self.transport {
+            MockTransport::Tcp(_) => InferenceServerClient::new(config),
+            MockTransport::InMemory(channel) => {
+                InferenceServerClient::from_channel(config, channel.clone())
+            }
+        }
+    }
+
+    pub fn set_live(&self, live: bool) {
+        self.state
+            .live
+            .store(live, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.state.ready.send_replace(ready);
+    }
+
+    /// Add or replace a model while the server is running.
+    pub fn set_model(&self, model: MockModel) {
+        let mut models = self.state.models.write().unwrap();
+        models.insert(model.name().to_string(), model);
+    }
+
+    pub fn remove_model(&self, name: &str) -> Option<MockModel> {
+        self.state.models.write().unwrap().remove(name)
+    }
+
+    pub fn set_model_ready(&self, name: &str, ready: bool) {
+        if let Some(model) = self.state.models.write().unwrap().get_mut(name) {
+            model.ready = ready;
+        }
+    }
+
+    /// Every `ModelInfer`/`ModelStreamInfer` request received so far, in arrival order.
+    pub fn infer_requests(&self) -> Vec<pb::ModelInferRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    pub fn clear_infer_requests(&self) {
+        self.state.requests.lock().unwrap().clear();
+    }
+
+    /// Calls of the gRPC method received so far, by its name in the protocol, e.g.
+    /// `"ModelConfig"`.
+    pub fn calls(&self, method: &str) -> usize {
+        let calls = self.state.calls.lock().unwrap();
+        calls.get(method).copied().unwrap_or_default()
+    }
+
+    /// Metadata of the last call of the gRPC method, e.g. to check the propagated trace context.
+    pub fn last_metadata(&self, method: &str) -> Option<MetadataMap> {
+        let metadata_by_method = self.state.metadata_by_method.lock().unwrap();
+        metadata_by_method.get(method).cloned()
+    }
+
+    pub fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl Drop for MockServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::grpc::client::Error;
+use crate::grpc::pb::model_metadata_response::TensorMetadata;
+use crate::grpc::pb::{self, DataType};
+use crate::grpc::record::{RecordedExchange, ReplayClient, ReplayMode};
+use crate::grpc::response::raw_from_tensor_contents;
+use crate::types::TritonDataTypes;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Status;
+
+pub type InferFn =
+    dyn Fn(&pb::ModelInferRequest) -> Result<pb::ModelInferResponse, Error> + Send + Sync;
+
+/// How a [`MockModel`] answers `ModelInfer` requests.
+#[derive(Clone)]
+pub enum InferHandler {
+    /// Return every input as an output with the same name, datatype and shape.
+    Echo,
+    /// Always return the same response, `id`/`model_name`/`model_version` are filled from the
+    /// request.
+    Fixed(pb::ModelInferResponse),
+    /// Always fail with the given status.
+    Error(Status),
+    /// Answer with a function, an [`Error::ResponseError`] is returned with its status and any
+    /// other error as `INTERNAL`.
+    Custom(Arc<InferFn>),
+}
+
+impl InferHandler {
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&pb::ModelInferRequest) -> Result<pb::ModelInferResponse, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::Custom(Arc::new(f))
+    }
+
+    /// Answer from a recording, each request consumes the first unused exchange it matches, as
+    /// [`ReplayMode::Matching`] does.
+    pub fn replay(exchanges: Vec<RecordedExchange>) -> Self {
+        let replay = ReplayClient::new(exchanges, ReplayMode::Matching);
+        Self::custom(move |request| match replay.next_exchange(request) {
+            Ok(exchange) => exchange.result(),
+            Err(e) => Err(Status::not_found(e.to_string()).into()),
+        })
+    }
+
+    pub(crate) fn handle(
+        &self,
+        request: &pb::ModelInferRequest,
+    ) -> Result<pb::ModelInferResponse, Error> {
+        match self {
+            Self::Echo => echo(request),
+            Self::Fixed(response) => Ok(pb::ModelInferResponse {
+                model_name: request.model_name.clone(),
+                model_version: request.model_version.clone(),
+                id: request.id.clone(),
+                ..response.clone()
+            }),
+            Self::Error(status) => Err(status.clone().into()),
+            Self::Custom(f) => f(request),
+        }
+    }
+}
+
+impl Debug for InferHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Echo => write!(f, "Echo"),
+            Self::Fixed(response) => f.debug_tuple("Fixed").field(response).finish(),
+            Self::Error(status) => f.debug_tuple("Error").field(status).finish(),
+            Self::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// A model served by [`super::MockServer`].
+#[derive(Clone, Debug)]
+pub struct MockModel {
+    pub(crate) metadata: pb::ModelMetadataResponse,
+    pub(crate) config: pb::ModelConfig,
+    // Set by `set_metadata`, the tensors are no longer derived from the config
+    pub(crate) custom_metadata: bool,
+    pub(crate) ready: bool,
+    pub(crate) handler: InferHandler,
+    pub(crate) latency: Duration,
+    pub(crate) transient_errors: (usize, Status),
+}
+
+impl MockModel {
+    pub fn new<T: ToString>(name: T) -> Self {
+        let name = name.to_string();
+        Self {
+            metadata: pb::ModelMetadataResponse {
+                name: name.clone(),
+                versions: vec!["1".to_string()],
+                platform: "mock".to_string(),
+                ..Default::default()
+            },
+            config: pb::ModelConfig {
+                name,
+                platform: "mock".to_string(),
+                ..Default::default()
+            },
+            custom_metadata: false,
+            ready: true,
+            handler: InferHandler::Echo,
+            latency: Duration::ZERO,
+            transient_errors: (0, Status::unavailable("")),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    pub fn set_versions<T: ToString>(&mut self, versions: &[T]) {
+        self.metadata.versions = versions.iter().map(|v| v.to_string()).collect();
+    }
+
+    pub fn versions<T: ToString>(mut self, versions: &[T]) -> Self {
+        self.set_versions(versions);
+        self
+    }
+
+    pub fn set_platform<T: ToString>(&mut self, platform: T) {
+        self.metadata.platform = platform.to_string();
+        self.config.platform = platform.to_string();
+    }
+
+    pub fn platform<T: ToString>(mut self, platform: T) -> Self {
+        self.set_platform(platform);
+        self
+    }
+
+    pub fn set_max_batch_size(&mut self, max_batch_size: i32) {
+        self.config.max_batch_size = max_batch_size;
+    }
+
+    pub fn max_batch_size(mut self, max_batch_size: i32) -> Self {
+        self.set_max_batch_size(max_batch_size);
+        self
+    }
+
+    /// Declare an input of the config, served in the metadata too. `dims` excludes the batch
+    /// dimension.
+    pub fn set_input<T: ToString>(&mut self, name: T, datatype: TritonDataTypes, dims: &[i64]) {
+        self.config.input.push(pb::ModelInput {
+            name: name.to_string(),
+            data_type: DataType::from(datatype.clone()) as i32,
+            dims: dims.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    pub fn input<T: ToString>(mut self, name: T, datatype: TritonDataTypes, dims: &[i64]) -> Self {
+        self.set_input(name, datatype, dims);
+        self
+    }
+
+    /// Declare an output of the config, served in the metadata too. `dims` excludes the batch
+    /// dimension.
+    pub fn set_output<T: ToString>(&mut self, name: T, datatype: TritonDataTypes, dims: &[i64]) {
+        self.config.output.push(pb::ModelOutput {
+            name: name.to_string(),
+            data_type: DataType::from(datatype.clone()) as i32,
+            dims: dims.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    pub fn output<T: ToString>(mut self, name: T, datatype: TritonDataTypes, dims: &[i64]) -> Self {
+        self.set_output(name, datatype, dims);
+        self
+    }
+
+    /// Replace the generated metadata, the model name is kept in sync with the config.
+    pub fn set_metadata(&mut self, metadata: pb::ModelMetadataResponse) {
+        self.config.name = metadata.name.clone();
+        self.metadata = metadata;
+        self.custom_metadata = true;
+    }
+
+    pub fn metadata(mut self, metadata: pb::ModelMetadataResponse) -> Self {
+        self.set_metadata(metadata);
+        self
+    }
+
+    /// Replace the generated config, the model name is kept in sync with the metadata.
+    pub fn set_config(&mut self, config: pb::ModelConfig) {
+        self.metadata.name = config.name.clone();
+        self.config = config;
+    }
+
+    pub fn config(mut self, config: pb::ModelConfig) -> Self {
+        self.set_config(config);
+        self
+    }
+
+    // Metadata as served. Unless replaced, its tensors follow the config as it is when serving,
+    // with the batch dimension of its `max_batch_size`.
+    pub(crate) fn served_metadata(&self) -> pb::ModelMetadataResponse {
+        if self.custom_metadata {
+            return self.metadata.clone();
+        }
+        let max_batch_size = self.config.max_batch_size;
+        pb::ModelMetadataResponse {
+            inputs: self
+                .config
+                .input
+                .iter()
+                .map(|input| {
+                    tensor_metadata(max_batch_size, &input.name, input.data_type, &input.dims)
+                })
+                .collect(),
+            outputs: self
+                .config
+                .output
+                .iter()
+                .map(|output| {
+                    tensor_metadata(max_batch_size, &output.name, output.data_type, &output.dims)
+                })
+                .collect(),
+            ..self.metadata.clone()
+        }
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+    }
+
+    pub fn ready(mut self, ready: bool) -> Self {
+        self.set_ready(ready);
+        self
+    }
+
+    pub fn set_handler(&mut self, handler: InferHandler) {
+        self.handler = handler;
+    }
+
+    pub fn handler(mut self, handler: InferHandler) -> Self {
+        self.set_handler(handler);
+        self
+    }
+
+    /// Delay applied before every inference answer.
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    /// Fail the next `count` inferences with `status` before falling back to the handler.
+    pub fn set_transient_errors(&mut self, count: usize, status: Status) {
+        self.transient_errors = (count, status);
+    }
+
+    pub fn transient_errors(mut self, count: usize, status: Status) -> Self {
+        self.set_transient_errors(count, status);
+        self
+    }
+}
+
+fn tensor_metadata(
+    max_batch_size: i32,
+    name: &str,
+    data_type: i32,
+    dims: &[i64],
+) -> TensorMetadata {
+    let datatype = DataType::try_from(data_type)
+        .ok()
+        .and_then(|data_type| TritonDataTypes::try_from(data_type).ok())
+        .map(|datatype| datatype.to_string())
+        .unwrap_or_default();
+    let mut shape = dims.to_vec();
+    if max_batch_size > 0 {
+        shape.insert(0, -1);
+    }
+    TensorMetadata {
+        name: name.to_string(),
+        datatype,
+        shape,
+    }
+}
+
+fn echo(request: &pb::ModelInferRequest) -> Result<pb::ModelInferResponse, Error> {
+    let mut outputs = Vec::with_capacity(request.inputs.len());
+    let mut raw_output_contents = Vec::with_capacity(request.inputs.len());
+    for (index, input) in request.inputs.iter().enumerate() {
+        let raw = match request.raw_input_contents.get(index) {
+            Some(raw) => raw.clone(),
+            None => {
+                let datatype = input
+                    .datatype
+                    .parse::<TritonDataTypes>()
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                input
+                    .contents
+                    .as_ref()
+                    .and_then(|contents| raw_from_tensor_contents(&datatype, contents))
+                    .ok_or_else(|| {
+                        Status::invalid_argument(format!(
+                            "input '{}' has no data usable as {datatype}",
+                            input.name
+                        ))
+                    })?
+            }
+        };
+        outputs.push(pb::InferOutputTensor {
+            name: input.name.clone(),
+            datatype: input.datatype.clone(),
+            shape: input.shape.clone(),
+            ..Default::default()
+        });
+        raw_output_contents.push(raw);
+    }
+    Ok(pb::ModelInferResponse {
+        model_name: request.model_name.clone(),
+        model_version: request.model_version.clone(),
+        id: request.id.clone(),
+        outputs,
+        raw_output_contents,
+        ..Default::default()
+    })
+}
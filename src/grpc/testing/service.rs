@@ -0,0 +1,559 @@
+use super::MockState;
+use crate::grpc::client::{Error, Result as ClientResult};
+use crate::grpc::pb::health_check_response::ServingStatus;
+use crate::grpc::pb::log_settings_request::setting_value::ParameterChoice as LogRequestChoice;
+use crate::grpc::pb::log_settings_response::setting_value::ParameterChoice as LogResponseChoice;
+use crate::grpc::pb::repository_index_response::ModelIndex;
+use crate::grpc::pb::{self, GrpcInferenceService, Health};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio_stream::wrappers::{ReceiverStream, WatchStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+
+const INFERENCE_SERVICE_NAME: &str = "inference.GRPCInferenceService";
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+#[derive(Clone)]
+pub(crate) struct MockService {
+    pub(crate) state: Arc<MockState>,
+}
+
+impl MockService {
+    fn is_ready(&self) -> bool {
+        *self.state.ready.borrow()
+    }
+
+    fn with_model<T>(
+        &self,
+        name: &str,
+        version: &str,
+        f: impl FnOnce(&super::MockModel) -> T,
+    ) -> ClientResult<T> {
+        let models = self.state.models.read().unwrap();
+        let model = models
+            .get(name)
+            .ok_or_else(|| Status::not_found(format!("Request for unknown model: '{name}'")))?;
+        if !version.is_empty() && !model.metadata.versions.iter().any(|v| v == version) {
+            return Err(Status::not_found(format!(
+                "Request for unknown model: '{name}' version {version} is not found"
+            ))
+            .into());
+        }
+        Ok(f(model))
+    }
+
+    async fn infer(
+        &self,
+        request: pb::ModelInferRequest,
+    ) -> Result<pb::ModelInferResponse, Status> {
+        self.state.requests.lock().unwrap().push(request.clone());
+        let (handler, latency, injected) = {
+            let mut models = self.state.models.write().unwrap();
+            let model = models.get_mut(&request.model_name).ok_or_else(|| {
+                Status::not_found(format!(
+                    "Request for unknown model: '{}' is not found",
+                    request.model_name
+                ))
+            })?;
+            if !model.ready {
+                return Err(Status::unavailable(format!(
+                    "Request for unknown model: '{}' is not at ready state",
+                    request.model_name
+                )));
+            }
+            let injected = if model.transient_errors.0 > 0 {
+                model.transient_errors.0 -= 1;
+                Some(model.transient_errors.1.clone())
+            } else {
+                None
+            };
+            (model.handler.clone(), model.latency, injected)
+        };
+
+        let start = Instant::now();
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        let result = match injected {
+            Some(status) => Err(status),
+            None => handler.handle(&request).map_err(into_status),
+        };
+        self.record_statistics(&request, start.elapsed().as_nanos() as u64, result.is_ok());
+        result
+    }
+
+    fn record_statistics(&self, request: &pb::ModelInferRequest, ns: u64, success: bool) {
+        let mut statistics = self.state.statistics.lock().unwrap();
+        let entry = statistics
+            .entry(request.model_name.clone())
+            .or_insert_with(|| pb::ModelStatistics {
+                name: request.model_name.clone(),
+                version: request.model_version.clone(),
+                inference_stats: Some(pb::InferStatistics::default()),
+                ..Default::default()
+            });
+        let inference_stats = entry.inference_stats.get_or_insert_with(Default::default);
+        let duration = if success {
+            entry.inference_count += 1;
+            entry.execution_count += 1;
+            entry.last_inference = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            for stat in [
+                &mut inference_stats.compute_infer,
+                &mut inference_stats.queue,
+                &mut inference_stats.compute_input,
+                &mut inference_stats.compute_output,
+            ] {
+                stat.get_or_insert_with(Default::default).count += 1;
+            }
+            inference_stats
+                .compute_infer
+                .get_or_insert_with(Default::default)
+                .ns += ns;
+            inference_stats.success.get_or_insert_with(Default::default)
+        } else {
+            inference_stats.fail.get_or_insert_with(Default::default)
+        };
+        duration.count += 1;
+        duration.ns += ns;
+    }
+}
+
+#[tonic::async_trait]
+impl GrpcInferenceService for MockService {
+    async fn server_live(
+        &self,
+        _request: Request<pb::ServerLiveRequest>,
+    ) -> Result<Response<pb::ServerLiveResponse>, Status> {
+        Ok(Response::new(pb::ServerLiveResponse {
+            live: self.state.live.load(Ordering::SeqCst),
+        }))
+    }
+
+    async fn server_ready(
+        &self,
+        _request: Request<pb::ServerReadyRequest>,
+    ) -> Result<Response<pb::ServerReadyResponse>, Status> {
+        Ok(Response::new(pb::ServerReadyResponse {
+            ready: self.is_ready(),
+        }))
+    }
+
+    async fn model_ready(
+        &self,
+        request: Request<pb::ModelReadyRequest>,
+    ) -> Result<Response<pb::ModelReadyResponse>, Status> {
+        let request = request.into_inner();
+        let ready = self
+            .with_model(&request.name, &request.version, |model| model.ready)
+            .unwrap_or(false);
+        Ok(Response::new(pb::ModelReadyResponse { ready }))
+    }
+
+    async fn server_metadata(
+        &self,
+        _request: Request<pb::ServerMetadataRequest>,
+    ) -> Result<Response<pb::ServerMetadataResponse>, Status> {
+        Ok(Response::new(self.state.metadata.clone()))
+    }
+
+    async fn model_metadata(
+        &self,
+        request: Request<pb::ModelMetadataRequest>,
+    ) -> Result<Response<pb::ModelMetadataResponse>, Status> {
+        let request = request.into_inner();
+        let metadata = self
+            .with_model(&request.name, &request.version, |model| {
+                model.served_metadata()
+            })
+            .map_err(into_status)?;
+        Ok(Response::new(metadata))
+    }
+
+    async fn model_infer(
+        &self,
+        request: Request<pb::ModelInferRequest>,
+    ) -> Result<Response<pb::ModelInferResponse>, Status> {
+        let response = self.infer(request.into_inner()).await?;
+        Ok(Response::new(response))
+    }
+
+    type ModelStreamInferStream = ResponseStream<pb::ModelStreamInferResponse>;
+
+    async fn model_stream_infer(
+        &self,
+        request: Request<Streaming<pb::ModelInferRequest>>,
+    ) -> Result<Response<Self::ModelStreamInferStream>, Status> {
+        let mut requests = request.into_inner();
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        let service = self.clone();
+        tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let response = match request {
+                    Ok(request) => match service.infer(request).await {
+                        Ok(response) => pb::ModelStreamInferResponse {
+                            error_message: String::new(),
+                            infer_response: Some(response),
+                        },
+                        Err(status) => pb::ModelStreamInferResponse {
+                            error_message: status.message().to_string(),
+                            infer_response: None,
+                        },
+                    },
+                    Err(status) => {
+                        let _ = sender.send(Err(status)).await;
+                        break;
+                    }
+                };
+                if sender.send(Ok(response)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn model_config(
+        &self,
+        request: Request<pb::ModelConfigRequest>,
+    ) -> Result<Response<pb::ModelConfigResponse>, Status> {
+        let request = request.into_inner();
+        let config = self
+            .with_model(&request.name, &request.version, |model| {
+                model.config.clone()
+            })
+            .map_err(into_status)?;
+        Ok(Response::new(pb::ModelConfigResponse {
+            config: Some(config),
+        }))
+    }
+
+    async fn model_statistics(
+        &self,
+        request: Request<pb::ModelStatisticsRequest>,
+    ) -> Result<Response<pb::ModelStatisticsResponse>, Status> {
+        let request = request.into_inner();
+        if !request.name.is_empty() {
+            self.with_model(&request.name, &request.version, |_| ())
+                .map_err(into_status)?;
+        }
+        let statistics = self.state.statistics.lock().unwrap();
+        let model_stats = self
+            .state
+            .models
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| request.name.is_empty() || **name == request.name)
+            .map(|name| {
+                statistics
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| pb::ModelStatistics {
+                        name: name.clone(),
+                        version: request.version.clone(),
+                        inference_stats: Some(pb::InferStatistics::default()),
+                        ..Default::default()
+                    })
+            })
+            .collect();
+        Ok(Response::new(pb::ModelStatisticsResponse { model_stats }))
+    }
+
+    async fn repository_index(
+        &self,
+        request: Request<pb::RepositoryIndexRequest>,
+    ) -> Result<Response<pb::RepositoryIndexResponse>, Status> {
+        let request = request.into_inner();
+        let models = self.state.models.read().unwrap();
+        let mut index = models
+            .values()
+            .filter(|model| model.ready || !request.ready)
+            .flat_map(|model| {
+                model.metadata.versions.iter().map(|version| ModelIndex {
+                    name: model.metadata.name.clone(),
+                    version: version.clone(),
+                    state: if model.ready { "READY" } else { "UNAVAILABLE" }.to_string(),
+                    reason: if model.ready { "" } else { "unloaded" }.to_string(),
+                })
+            })
+            .collect::<Vec<_>>();
+        index.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Ok(Response::new(pb::RepositoryIndexResponse { models: index }))
+    }
+
+    async fn repository_model_load(
+        &self,
+        request: Request<pb::RepositoryModelLoadRequest>,
+    ) -> Result<Response<pb::RepositoryModelLoadResponse>, Status> {
+        let request = request.into_inner();
+        let mut models = self.state.models.write().unwrap();
+        let model = models.get_mut(&request.model_name).ok_or_else(|| {
+            Status::invalid_argument(format!(
+                "failed to load '{}', failed to poll from model repository",
+                request.model_name
+            ))
+        })?;
+        model.ready = true;
+        Ok(Response::new(pb::RepositoryModelLoadResponse {}))
+    }
+
+    async fn repository_model_unload(
+        &self,
+        request: Request<pb::RepositoryModelUnloadRequest>,
+    ) -> Result<Response<pb::RepositoryModelUnloadResponse>, Status> {
+        let request = request.into_inner();
+        if let Some(model) = self
+            .state
+            .models
+            .write()
+            .unwrap()
+            .get_mut(&request.model_name)
+        {
+            model.ready = false;
+        }
+        Ok(Response::new(pb::RepositoryModelUnloadResponse {}))
+    }
+
+    async fn system_shared_memory_status(
+        &self,
+        request: Request<pb::SystemSharedMemoryStatusRequest>,
+    ) -> Result<Response<pb::SystemSharedMemoryStatusResponse>, Status> {
+        let request = request.into_inner();
+        let regions = self.state.system_shared_memory.lock().unwrap();
+        if !request.name.is_empty() && !regions.contains_key(&request.name) {
+            return Err(Status::not_found(format!(
+                "Unable to find system shared memory region: '{}'",
+                request.name
+            )));
+        }
+        let regions = regions
+            .iter()
+            .filter(|(name, _)| request.name.is_empty() || **name == request.name)
+            .map(|(name, region)| (name.clone(), region.clone()))
+            .collect();
+        Ok(Response::new(pb::SystemSharedMemoryStatusResponse {
+            regions,
+        }))
+    }
+
+    async fn system_shared_memory_register(
+        &self,
+        request: Request<pb::SystemSharedMemoryRegisterRequest>,
+    ) -> Result<Response<pb::SystemSharedMemoryRegisterResponse>, Status> {
+        let request = request.into_inner();
+        let mut regions = self.state.system_shared_memory.lock().unwrap();
+        if regions.contains_key(&request.name) {
+            return Err(Status::already_exists(format!(
+                "shared memory region '{}' already in manager",
+                request.name
+            )));
+        }
+        regions.insert(
+            request.name.clone(),
+            pb::system_shared_memory_status_response::RegionStatus {
+                name: request.name,
+                key: request.key,
+                offset: request.offset,
+                byte_size: request.byte_size,
+            },
+        );
+        Ok(Response::new(pb::SystemSharedMemoryRegisterResponse {}))
+    }
+
+    async fn system_shared_memory_unregister(
+        &self,
+        request: Request<pb::SystemSharedMemoryUnregisterRequest>,
+    ) -> Result<Response<pb::SystemSharedMemoryUnregisterResponse>, Status> {
+        let request = request.into_inner();
+        let mut regions = self.state.system_shared_memory.lock().unwrap();
+        if request.name.is_empty() {
+            regions.clear();
+        } else {
+            regions.remove(&request.name);
+        }
+        Ok(Response::new(pb::SystemSharedMemoryUnregisterResponse {}))
+    }
+
+    async fn cuda_shared_memory_status(
+        &self,
+        request: Request<pb::CudaSharedMemoryStatusRequest>,
+    ) -> Result<Response<pb::CudaSharedMemoryStatusResponse>, Status> {
+        let request = request.into_inner();
+        let regions = self.state.cuda_shared_memory.lock().unwrap();
+        if !request.name.is_empty() && !regions.contains_key(&request.name) {
+            return Err(Status::not_found(format!(
+                "Unable to find cuda shared memory region: '{}'",
+                request.name
+            )));
+        }
+        let regions = regions
+            .iter()
+            .filter(|(name, _)| request.name.is_empty() || **name == request.name)
+            .map(|(name, region)| (name.clone(), region.clone()))
+            .collect();
+        Ok(Response::new(pb::CudaSharedMemoryStatusResponse {
+            regions,
+        }))
+    }
+
+    async fn cuda_shared_memory_register(
+        &self,
+        request: Request<pb::CudaSharedMemoryRegisterRequest>,
+    ) -> Result<Response<pb::CudaSharedMemoryRegisterResponse>, Status> {
+        let request = request.into_inner();
+        let mut regions = self.state.cuda_shared_memory.lock().unwrap();
+        if regions.contains_key(&request.name) {
+            return Err(Status::already_exists(format!(
+                "shared memory region '{}' already in manager",
+                request.name
+            )));
+        }
+        regions.insert(
+            request.name.clone(),
+            pb::cuda_shared_memory_status_response::RegionStatus {
+                name: request.name,
+                device_id: request.device_id as u64,
+                byte_size: request.byte_size,
+            },
+        );
+        Ok(Response::new(pb::CudaSharedMemoryRegisterResponse {}))
+    }
+
+    async fn cuda_shared_memory_unregister(
+        &self,
+        request: Request<pb::CudaSharedMemoryUnregisterRequest>,
+    ) -> Result<Response<pb::CudaSharedMemoryUnregisterResponse>, Status> {
+        let request = request.into_inner();
+        let mut regions = self.state.cuda_shared_memory.lock().unwrap();
+        if request.name.is_empty() {
+            regions.clear();
+        } else {
+            regions.remove(&request.name);
+        }
+        Ok(Response::new(pb::CudaSharedMemoryUnregisterResponse {}))
+    }
+
+    async fn trace_setting(
+        &self,
+        request: Request<pb::TraceSettingRequest>,
+    ) -> Result<Response<pb::TraceSettingResponse>, Status> {
+        let request = request.into_inner();
+        let mut scopes = self.state.trace_settings.lock().unwrap();
+        let scope = scopes.entry(request.model_name.clone()).or_default();
+        for (key, value) in request.settings {
+            if value.value.is_empty() {
+                scope.remove(&key);
+            } else {
+                scope.insert(key, value.value);
+            }
+        }
+        // Model scoped settings fall back to the global ones.
+        let mut settings: HashMap<String, Vec<String>> =
+            scopes.get("").cloned().unwrap_or_default();
+        if !request.model_name.is_empty() {
+            settings.extend(scopes.get(&request.model_name).cloned().unwrap_or_default());
+        }
+        let settings = settings
+            .into_iter()
+            .map(|(key, value)| (key, pb::trace_setting_response::SettingValue { value }))
+            .collect();
+        Ok(Response::new(pb::TraceSettingResponse { settings }))
+    }
+
+    async fn log_settings(
+        &self,
+        request: Request<pb::LogSettingsRequest>,
+    ) -> Result<Response<pb::LogSettingsResponse>, Status> {
+        let request = request.into_inner();
+        let mut current = self.state.log_settings.lock().unwrap();
+        for (key, value) in request.settings {
+            let value = match value.parameter_choice {
+                Some(LogRequestChoice::BoolParam(v)) => LogResponseChoice::BoolParam(v),
+                Some(LogRequestChoice::Uint32Param(v)) => LogResponseChoice::Uint32Param(v),
+                Some(LogRequestChoice::StringParam(v)) => LogResponseChoice::StringParam(v),
+                None => continue,
+            };
+            current.insert(key, value);
+        }
+        let settings = current
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.clone(),
+                    pb::log_settings_response::SettingValue {
+                        parameter_choice: Some(value.clone()),
+                    },
+                )
+            })
+            .collect();
+        Ok(Response::new(pb::LogSettingsResponse { settings }))
+    }
+}
+
+#[tonic::async_trait]
+impl Health for MockService {
+    async fn check(
+        &self,
+        request: Request<pb::HealthCheckRequest>,
+    ) -> Result<Response<pb::HealthCheckResponse>, Status> {
+        let service = request.into_inner().service;
+        if !service.is_empty() && service != INFERENCE_SERVICE_NAME {
+            return Err(Status::not_found(format!("unknown service '{service}'")));
+        }
+        Ok(Response::new(pb::HealthCheckResponse {
+            status: serving_status(self.is_ready()) as i32,
+        }))
+    }
+
+    type WatchStream = ResponseStream<pb::HealthCheckResponse>;
+
+    // The readiness of the server, resent on every change of `set_ready`.
+    async fn watch(
+        &self,
+        request: Request<pb::HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = request.into_inner().service;
+        if !service.is_empty() && service != INFERENCE_SERVICE_NAME {
+            // Unknown services stay watched, in case they get registered later
+            let unknown = pb::HealthCheckResponse {
+                status: ServingStatus::ServiceUnknown as i32,
+            };
+            let stream = tokio_stream::once(Ok(unknown)).chain(tokio_stream::pending());
+            return Ok(Response::new(Box::pin(stream)));
+        }
+        let mut last = None;
+        let stream = WatchStream::new(self.state.ready.subscribe()).filter_map(move |ready| {
+            let changed = last.replace(ready) != Some(ready);
+            changed
+                .then(|| pb::HealthCheckResponse {
+                    status: serving_status(ready) as i32,
+                })
+                .map(Ok)
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+// The status a handler failed with, errors that carry none are reported as `INTERNAL`.
+fn into_status(error: Error) -> Status {
+    match error {
+        Error::ResponseError { status } => *status,
+        error => Status::internal(error.to_string()),
+    }
+}
+
+fn serving_status(ready: bool) -> ServingStatus {
+    if ready {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    }
+}
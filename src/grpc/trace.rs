@@ -0,0 +1,417 @@
+use super::client::{Error, Result};
+use super::pbtxt::json_error;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// A tensor logged by `TENSORS` level tracing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceTensor {
+    /// e.g. `TENSOR_QUEUE_INPUT`, `TENSOR_BACKEND_OUTPUT`
+    pub activity: String,
+    pub name: String,
+    pub datatype: String,
+    pub shape: String,
+    pub data: String,
+}
+
+/// The timeline of one traced request, assembled from every entry sharing its id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestTrace {
+    pub id: u64,
+    /// Id of the ensemble request this one is a step of
+    pub parent_id: Option<u64>,
+    pub model_name: String,
+    pub model_version: String,
+    pub request_id: String,
+    /// Activity name, e.g. `QUEUE_START`, to nanoseconds since an arbitrary epoch, in file order
+    pub timestamps: Vec<(String, u64)>,
+    pub tensors: Vec<TraceTensor>,
+}
+
+impl RequestTrace {
+    pub fn timestamp(&self, name: &str) -> Option<u64> {
+        self.timestamps
+            .iter()
+            .find(|(activity, _)| activity == name)
+            .map(|(_, ns)| *ns)
+    }
+
+    /// Time from the first of the `from` activities to the first of the `to` ones.
+    fn span(&self, from: &[&str], to: &[&str]) -> Option<Duration> {
+        let start = from.iter().find_map(|name| self.timestamp(name))?;
+        let end = to.iter().find_map(|name| self.timestamp(name))?;
+        end.checked_sub(start).map(Duration::from_nanos)
+    }
+
+    /// REQUEST_START to REQUEST_END, the time spent in the server core
+    pub fn request(&self) -> Option<Duration> {
+        self.span(&["REQUEST_START"], &["REQUEST_END"])
+    }
+
+    pub fn queue(&self) -> Option<Duration> {
+        self.span(&["QUEUE_START"], &["COMPUTE_START"])
+    }
+
+    pub fn compute(&self) -> Option<Duration> {
+        self.span(&["COMPUTE_START"], &["COMPUTE_END"])
+    }
+
+    pub fn compute_input(&self) -> Option<Duration> {
+        self.span(&["COMPUTE_START"], &["COMPUTE_INPUT_END"])
+    }
+
+    pub fn compute_infer(&self) -> Option<Duration> {
+        self.span(&["COMPUTE_INPUT_END"], &["COMPUTE_OUTPUT_START"])
+    }
+
+    pub fn compute_output(&self) -> Option<Duration> {
+        self.span(&["COMPUTE_OUTPUT_START"], &["COMPUTE_END"])
+    }
+
+    /// Reading the request from and writing the response to the HTTP or gRPC frontend
+    pub fn network(&self) -> Option<Duration> {
+        let receive = self.span(
+            &["HTTP_RECV_START", "GRPC_WAITREAD_START"],
+            &["HTTP_RECV_END", "GRPC_WAITREAD_END"],
+        );
+        let send = self.span(
+            &["HTTP_SEND_START", "GRPC_SEND_START"],
+            &["HTTP_SEND_END", "GRPC_SEND_END"],
+        );
+        match (receive, send) {
+            (None, None) => None,
+            (receive, send) => Some(receive.unwrap_or_default() + send.unwrap_or_default()),
+        }
+    }
+
+    /// First to last timestamp
+    pub fn total(&self) -> Option<Duration> {
+        let start = self.timestamps.iter().map(|(_, ns)| *ns).min()?;
+        let end = self.timestamps.iter().map(|(_, ns)| *ns).max()?;
+        Some(Duration::from_nanos(end - start))
+    }
+}
+
+fn trace_error(message: String) -> Error {
+    Error::ConversionError(format!("Invalid trace file: {message}"))
+}
+
+fn string_field(entry: &Map<String, Value>, key: &str) -> Option<String> {
+    entry.get(key).map(|value| match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    })
+}
+
+fn parse_entries(json: &str) -> Result<Vec<Value>> {
+    match serde_json::from_str::<Vec<Value>>(json) {
+        Ok(entries) => Ok(entries),
+        // Triton appends to the array while running, the closing bracket comes at shutdown.
+        Err(e) if e.is_eof() => {
+            let body = json.trim_end().trim_end_matches(',');
+            serde_json::from_str(&format!("{body}]")).map_err(|_| json_error(e))
+        }
+        Err(e) => Err(json_error(e)),
+    }
+}
+
+/// Parse a trace file written in `triton` trace mode into one timeline per request, ordered
+/// by id.
+pub fn parse_traces(json: &str) -> Result<Vec<RequestTrace>> {
+    let mut traces: BTreeMap<u64, RequestTrace> = BTreeMap::new();
+    for entry in parse_entries(json)? {
+        let Value::Object(entry) = entry else {
+            return Err(trace_error(format!("entry {entry} is not an object")));
+        };
+        let id = entry
+            .get("id")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| trace_error(format!("entry {entry:?} has no `id`")))?;
+        let trace = traces.entry(id).or_insert_with(|| RequestTrace {
+            id,
+            ..RequestTrace::default()
+        });
+        if let Some(model_name) = string_field(&entry, "model_name") {
+            trace.model_name = model_name;
+        }
+        if let Some(model_version) = string_field(&entry, "model_version") {
+            trace.model_version = model_version;
+        }
+        if let Some(request_id) = string_field(&entry, "request_id") {
+            trace.request_id = request_id;
+        }
+        if let Some(parent_id) = entry.get("parent_id").and_then(Value::as_u64) {
+            trace.parent_id = Some(parent_id);
+        }
+        if let Some(timestamps) = entry.get("timestamps").and_then(Value::as_array) {
+            for timestamp in timestamps {
+                let name = timestamp.get("name").and_then(Value::as_str);
+                let ns = timestamp.get("ns").and_then(Value::as_u64);
+                match (name, ns) {
+                    (Some(name), Some(ns)) => trace.timestamps.push((name.to_string(), ns)),
+                    _ => {
+                        return Err(trace_error(format!(
+                            "timestamp {timestamp} of trace {id} needs `name` and `ns`"
+                        )))
+                    }
+                }
+            }
+        }
+        if let Some(Value::Object(tensor)) = entry.get("tensor") {
+            trace.tensors.push(TraceTensor {
+                activity: string_field(&entry, "activity").unwrap_or_default(),
+                name: string_field(tensor, "name").unwrap_or_default(),
+                datatype: string_field(tensor, "dtype").unwrap_or_default(),
+                shape: string_field(tensor, "shape").unwrap_or_default(),
+                data: string_field(tensor, "data").unwrap_or_default(),
+            });
+        }
+    }
+    Ok(traces.into_values().collect())
+}
+
+/// Read and parse a single trace file, see [`parse_traces`]. Triton writes one file per
+/// `log_frequency` interval (`trace.json.0`, `trace.json.1`, ..), read each of them separately.
+pub fn read_traces<P: AsRef<Path>>(path: P) -> Result<Vec<RequestTrace>> {
+    parse_traces(&fs::read_to_string(path)?)
+}
+
+/// Distribution of one duration over many requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub mean: Duration,
+    pub min: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// `None` without samples.
+    pub fn new(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        // nearest rank
+        let rank = |p: f64| {
+            let index = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+            samples[index.clamp(1, samples.len()) - 1]
+        };
+        let total = samples.iter().sum::<Duration>();
+        Some(Self {
+            count: samples.len(),
+            mean: total / samples.len() as u32,
+            min: samples[0],
+            p50: rank(50.0),
+            p90: rank(90.0),
+            p95: rank(95.0),
+            p99: rank(99.0),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// Where the time of the traced requests of one model version went.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelTraceSummary {
+    pub model_name: String,
+    pub model_version: String,
+    pub requests: usize,
+    pub request: Option<Percentiles>,
+    pub queue: Option<Percentiles>,
+    pub compute: Option<Percentiles>,
+    pub compute_input: Option<Percentiles>,
+    pub compute_infer: Option<Percentiles>,
+    pub compute_output: Option<Percentiles>,
+    pub network: Option<Percentiles>,
+}
+
+/// Per model version breakdown of `traces`, ordered by model name and version.
+pub fn summarize_traces(traces: &[RequestTrace]) -> Vec<ModelTraceSummary> {
+    let mut models: BTreeMap<(&str, &str), Vec<&RequestTrace>> = BTreeMap::new();
+    for trace in traces.iter().filter(|trace| !trace.model_name.is_empty()) {
+        models
+            .entry((&trace.model_name, &trace.model_version))
+            .or_default()
+            .push(trace);
+    }
+    models
+        .into_iter()
+        .map(|((model_name, model_version), traces)| {
+            let stat = |f: fn(&RequestTrace) -> Option<Duration>| {
+                Percentiles::new(traces.iter().filter_map(|trace| f(trace)).collect())
+            };
+            ModelTraceSummary {
+                model_name: model_name.to_string(),
+                model_version: model_version.to_string(),
+                requests: traces.len(),
+                request: stat(RequestTrace::request),
+                queue: stat(RequestTrace::queue),
+                compute: stat(RequestTrace::compute),
+                compute_input: stat(RequestTrace::compute_input),
+                compute_infer: stat(RequestTrace::compute_infer),
+                compute_output: stat(RequestTrace::compute_output),
+                network: stat(RequestTrace::network),
+            }
+        })
+        .collect()
+}
+
+// Phases drawn for every request, `(name, start activities, end activities)`.
+const CHROME_PHASES: [(&str, &[&str], &[&str]); 7] = [
+    (
+        "receive",
+        &["HTTP_RECV_START", "GRPC_WAITREAD_START"],
+        &["HTTP_RECV_END", "GRPC_WAITREAD_END"],
+    ),
+    ("request", &["REQUEST_START"], &["REQUEST_END"]),
+    ("queue", &["QUEUE_START"], &["COMPUTE_START"]),
+    ("compute_input", &["COMPUTE_START"], &["COMPUTE_INPUT_END"]),
+    (
+        "compute_infer",
+        &["COMPUTE_INPUT_END"],
+        &["COMPUTE_OUTPUT_START"],
+    ),
+    (
+        "compute_output",
+        &["COMPUTE_OUTPUT_START"],
+        &["COMPUTE_END"],
+    ),
+    (
+        "send",
+        &["HTTP_SEND_START", "GRPC_SEND_START"],
+        &["HTTP_SEND_END", "GRPC_SEND_END"],
+    ),
+];
+
+/// Chrome trace-event JSON of `traces`, to open in `chrome://tracing` or Perfetto.
+/// Every model version is a process and every request a thread.
+pub fn to_chrome_trace(traces: &[RequestTrace]) -> Value {
+    let mut processes: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    let mut events = vec![];
+    for trace in traces {
+        let next = processes.len() + 1;
+        let pid = *processes
+            .entry((&trace.model_name, &trace.model_version))
+            .or_insert(next);
+        for (name, from, to) in CHROME_PHASES {
+            let start = from.iter().find_map(|activity| trace.timestamp(activity));
+            let end = to.iter().find_map(|activity| trace.timestamp(activity));
+            let (Some(start), Some(end)) = (start, end) else {
+                continue;
+            };
+            events.push(json!({
+                "name": name,
+                "cat": "triton",
+                "ph": "X",
+                "ts": start as f64 / 1000.0,
+                "dur": end.saturating_sub(start) as f64 / 1000.0,
+                "pid": pid,
+                "tid": trace.id,
+                "args": {
+                    "request_id": trace.request_id,
+                    "parent_id": trace.parent_id,
+                },
+            }));
+        }
+    }
+    for ((model_name, model_version), pid) in processes {
+        let name = match model_version {
+            "" => model_name.to_string(),
+            version => format!("{model_name} v{version}"),
+        };
+        events.push(json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": pid,
+            "args": { "name": name },
+        }));
+    }
+    json!({ "traceEvents": events, "displayTimeUnit": "ns" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // As written by Triton while still running: several entries per id and no closing bracket.
+    const TRACE: &str = r#"[
+{"id":2,"model_name":"resnet","model_version":1,"request_id":"b"},
+{"id":1,"model_name":"resnet","model_version":1,"request_id":"a","parent_id":7},
+{"id":1,"timestamps":[{"name":"REQUEST_START","ns":1000},{"name":"QUEUE_START","ns":1100}]},
+{"id":1,"timestamps":[{"name":"COMPUTE_START","ns":1500},{"name":"COMPUTE_END","ns":4500}]},
+{"id":1,"activity":"TENSOR_QUEUE_INPUT",
+ "tensor":{"name":"x","data":"1,2","shape":"2","dtype":"INT32"}},
+{"id":1,"timestamps":[{"name":"REQUEST_END","ns":5000}]},
+{"id":2,"timestamps":[{"name":"QUEUE_START","ns":2000},{"name":"COMPUTE_START","ns":2100}]},
+"#;
+
+    #[test]
+    fn merges_entries_by_id() {
+        let traces = parse_traces(TRACE).unwrap();
+        assert_eq!(traces.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2]);
+
+        let first = &traces[0];
+        assert_eq!(first.model_version, "1");
+        assert_eq!(first.request_id, "a");
+        assert_eq!(first.parent_id, Some(7));
+        assert_eq!(first.timestamps.len(), 5);
+        assert_eq!(first.request(), Some(Duration::from_nanos(4000)));
+        assert_eq!(first.queue(), Some(Duration::from_nanos(400)));
+        assert_eq!(first.compute(), Some(Duration::from_nanos(3000)));
+        assert_eq!(first.network(), None);
+        assert_eq!(
+            first.tensors,
+            vec![TraceTensor {
+                activity: "TENSOR_QUEUE_INPUT".to_string(),
+                name: "x".to_string(),
+                datatype: "INT32".to_string(),
+                shape: "2".to_string(),
+                data: "1,2".to_string(),
+            }]
+        );
+        assert_eq!(traces[1].queue(), Some(Duration::from_nanos(100)));
+        assert_eq!(traces[1].request(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(parse_traces(r#"[{"model_name":"resnet"}]"#).is_err());
+        assert!(parse_traces(r#"[{"id":1,"timestamps":[{"name":"QUEUE_START"}]}]"#).is_err());
+        assert!(parse_traces(r#"[{"id":1} {"id":2}]"#).is_err());
+    }
+
+    #[test]
+    fn computes_nearest_rank_percentiles() {
+        let samples = (1..=100).rev().map(Duration::from_millis).collect();
+        let percentiles = Percentiles::new(samples).unwrap();
+        assert_eq!(percentiles.count, 100);
+        assert_eq!(percentiles.min, Duration::from_millis(1));
+        assert_eq!(percentiles.p50, Duration::from_millis(50));
+        assert_eq!(percentiles.p90, Duration::from_millis(90));
+        assert_eq!(percentiles.p99, Duration::from_millis(99));
+        assert_eq!(percentiles.max, Duration::from_millis(100));
+        assert_eq!(percentiles.mean, Duration::from_micros(50_500));
+
+        let single = Percentiles::new(vec![Duration::from_millis(3)]).unwrap();
+        assert_eq!((single.p50, single.p99), (single.min, single.max));
+        assert_eq!(Percentiles::new(vec![]), None);
+    }
+
+    #[test]
+    fn summarizes_per_model_version() {
+        let summary = summarize_traces(&parse_traces(TRACE).unwrap());
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].requests, 2);
+        assert_eq!(summary[0].queue.map(|queue| queue.count), Some(2));
+        assert_eq!(summary[0].request.map(|request| request.count), Some(1));
+        assert_eq!(summary[0].network, None);
+    }
+}
@@ -0,0 +1,466 @@
+use super::model::{ModelConfigView, TensorSpec};
+use super::pb::{InferInputTensor, InferTensorContents, ModelInferRequest};
+use crate::types::TritonDataTypes;
+use std::fmt::{Display, Formatter};
+
+/// A single reason for which the server would reject a request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationProblem {
+    UnknownInput {
+        name: String,
+    },
+    MissingInput {
+        name: String,
+    },
+    DuplicateInput {
+        name: String,
+    },
+    DatatypeMismatch {
+        name: String,
+        expected: TritonDataTypes,
+        actual: String,
+    },
+    RankMismatch {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    DimMismatch {
+        name: String,
+        index: usize,
+        expected: i64,
+        actual: i64,
+    },
+    /// Batch size is 0 or above `max_batch_size`
+    InvalidBatchSize {
+        name: String,
+        max_batch_size: i32,
+        actual: i64,
+    },
+    InconsistentBatchSize {
+        name: String,
+        expected: i64,
+        actual: i64,
+    },
+    /// Amount of data does not match the shape
+    DataSizeMismatch {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    UnknownOutput {
+        name: String,
+    },
+}
+
+impl Display for ValidationProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownInput { name } => write!(f, "unexpected input `{name}`"),
+            Self::MissingInput { name } => write!(f, "missing required input `{name}`"),
+            Self::DuplicateInput { name } => write!(f, "input `{name}` is given more than once"),
+            Self::DatatypeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(f, "input `{name}` expects {expected}, got `{actual}`"),
+            Self::RankMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(f, "input `{name}` expects {expected} dims, got {actual}"),
+            Self::DimMismatch {
+                name,
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "input `{name}` expects {expected} at dim {index}, got {actual}"
+            ),
+            Self::InvalidBatchSize {
+                name,
+                max_batch_size,
+                actual,
+            } => write!(
+                f,
+                "input `{name}` has batch size {actual}, expected 1..={max_batch_size}"
+            ),
+            Self::InconsistentBatchSize {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "input `{name}` has batch size {actual} while other inputs have {expected}"
+            ),
+            Self::DataSizeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "input `{name}` shape needs {expected} elements/bytes, got {actual}"
+            ),
+            Self::UnknownOutput { name } => write!(f, "unexpected requested output `{name}`"),
+        }
+    }
+}
+
+/// Check a request against the model configuration, every problem found is returned.
+pub fn validate_request(
+    request: &ModelInferRequest,
+    config: &ModelConfigView,
+) -> Result<(), Vec<ValidationProblem>> {
+    let mut problems = vec![];
+    let mut batch_size = None;
+
+    for (index, input) in request.inputs.iter().enumerate() {
+        if request.inputs[..index].iter().any(|i| i.name == input.name) {
+            problems.push(ValidationProblem::DuplicateInput {
+                name: input.name.clone(),
+            });
+            continue;
+        }
+        let Some(spec) = config.input(&input.name) else {
+            problems.push(ValidationProblem::UnknownInput {
+                name: input.name.clone(),
+            });
+            continue;
+        };
+        if input.datatype != spec.datatype.to_string() {
+            problems.push(ValidationProblem::DatatypeMismatch {
+                name: input.name.clone(),
+                expected: spec.datatype.clone(),
+                actual: input.datatype.clone(),
+            });
+        }
+        validate_shape(
+            input,
+            spec,
+            config.max_batch_size(),
+            &mut batch_size,
+            &mut problems,
+        );
+        validate_data_size(request, index, spec, &mut problems);
+    }
+
+    for spec in config.inputs().iter().filter(|spec| !spec.optional) {
+        if !request.inputs.iter().any(|input| input.name == spec.name) {
+            problems.push(ValidationProblem::MissingInput {
+                name: spec.name.clone(),
+            });
+        }
+    }
+
+    for output in &request.outputs {
+        if config.output(&output.name).is_none() {
+            problems.push(ValidationProblem::UnknownOutput {
+                name: output.name.clone(),
+            });
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+fn validate_shape(
+    input: &InferInputTensor,
+    spec: &TensorSpec,
+    max_batch_size: i32,
+    batch_size: &mut Option<i64>,
+    problems: &mut Vec<ValidationProblem>,
+) {
+    if spec.allow_ragged_batch {
+        return;
+    }
+    let batched = max_batch_size > 0 && !spec.is_shape_tensor;
+    let expected_rank = spec.dims.len() + batched as usize;
+    if input.shape.len() != expected_rank {
+        problems.push(ValidationProblem::RankMismatch {
+            name: input.name.clone(),
+            expected: expected_rank,
+            actual: input.shape.len(),
+        });
+        return;
+    }
+
+    let mut shape = input.shape.as_slice();
+    if batched {
+        let size = shape[0];
+        shape = &shape[1..];
+        if size < 1 || size > max_batch_size as i64 {
+            problems.push(ValidationProblem::InvalidBatchSize {
+                name: input.name.clone(),
+                max_batch_size,
+                actual: size,
+            });
+        }
+        match batch_size {
+            Some(expected) if *expected != size => {
+                problems.push(ValidationProblem::InconsistentBatchSize {
+                    name: input.name.clone(),
+                    expected: *expected,
+                    actual: size,
+                });
+            }
+            Some(_) => {}
+            None => *batch_size = Some(size),
+        }
+    }
+
+    for (index, (dim, size)) in spec.dims.iter().zip(shape).enumerate() {
+        if !dim.accepts(*size) {
+            problems.push(ValidationProblem::DimMismatch {
+                name: input.name.clone(),
+                index: index + batched as usize,
+                expected: i64::from(*dim),
+                actual: *size,
+            });
+        }
+    }
+}
+
+fn validate_data_size(
+    request: &ModelInferRequest,
+    index: usize,
+    spec: &TensorSpec,
+    problems: &mut Vec<ValidationProblem>,
+) {
+    let input = &request.inputs[index];
+    if input.shape.iter().any(|size| *size < 0) {
+        return;
+    }
+    // An element count that overflows can't match any data
+    let elements = input
+        .shape
+        .iter()
+        .try_fold(1usize, |count, size| count.checked_mul(*size as usize));
+    let (expected, actual) = if let Some(raw) = request.raw_input_contents.get(index) {
+        match spec.datatype.size_of() {
+            Some(size) => (
+                elements.and_then(|elements| elements.checked_mul(size)),
+                raw.len(),
+            ),
+            None => return,
+        }
+    } else if let Some(contents) = &input.contents {
+        (elements, contents_len(contents))
+    } else {
+        // Data lives in shared memory or in parameters, nothing to compare to.
+        return;
+    };
+    if expected != Some(actual) {
+        problems.push(ValidationProblem::DataSizeMismatch {
+            name: input.name.clone(),
+            expected: expected.unwrap_or(usize::MAX),
+            actual,
+        });
+    }
+}
+
+fn contents_len(contents: &InferTensorContents) -> usize {
+    contents.bool_contents.len()
+        + contents.int_contents.len()
+        + contents.int64_contents.len()
+        + contents.uint_contents.len()
+        + contents.uint64_contents.len()
+        + contents.fp32_contents.len()
+        + contents.fp64_contents.len()
+        + contents.bytes_contents.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::pb::model_infer_request::InferRequestedOutputTensor;
+    use crate::grpc::pb::{self, DataType};
+
+    fn config(max_batch_size: i32) -> ModelConfigView {
+        let input = |name: &str, data_type: DataType, dims: &[i64]| pb::ModelInput {
+            name: name.to_string(),
+            data_type: data_type as i32,
+            dims: dims.to_vec(),
+            ..Default::default()
+        };
+        pb::ModelConfig {
+            name: "model".to_string(),
+            max_batch_size,
+            input: vec![
+                input("image", DataType::TypeFp32, &[3, -1]),
+                input("label", DataType::TypeString, &[1]),
+            ],
+            output: vec![pb::ModelOutput {
+                name: "score".to_string(),
+                data_type: DataType::TypeFp32 as i32,
+                dims: vec![1],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    fn tensor(name: &str, datatype: &str, shape: &[i64]) -> InferInputTensor {
+        InferInputTensor {
+            name: name.to_string(),
+            datatype: datatype.to_string(),
+            shape: shape.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn request(inputs: Vec<InferInputTensor>, raw: Vec<Vec<u8>>) -> ModelInferRequest {
+        ModelInferRequest {
+            model_name: "model".to_string(),
+            inputs,
+            raw_input_contents: raw,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_request() {
+        let request = request(
+            vec![
+                tensor("image", "FP32", &[2, 3, 5]),
+                tensor("label", "BYTES", &[2, 1]),
+            ],
+            vec![vec![0; 2 * 3 * 5 * 4], vec![0; 10]],
+        );
+        assert_eq!(validate_request(&request, &config(4)), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let mut request = request(
+            vec![
+                tensor("image", "INT32", &[2, 4, 5]),
+                tensor("image", "FP32", &[2, 3, 5]),
+                tensor("mask", "BOOL", &[2]),
+            ],
+            vec![],
+        );
+        request.outputs.push(InferRequestedOutputTensor {
+            name: "logits".to_string(),
+            ..Default::default()
+        });
+        let problems = validate_request(&request, &config(4)).unwrap_err();
+        assert_eq!(
+            problems,
+            vec![
+                ValidationProblem::DatatypeMismatch {
+                    name: "image".to_string(),
+                    expected: TritonDataTypes::FP32,
+                    actual: "INT32".to_string(),
+                },
+                ValidationProblem::DimMismatch {
+                    name: "image".to_string(),
+                    index: 1,
+                    expected: 3,
+                    actual: 4,
+                },
+                ValidationProblem::DuplicateInput {
+                    name: "image".to_string(),
+                },
+                ValidationProblem::UnknownInput {
+                    name: "mask".to_string(),
+                },
+                ValidationProblem::MissingInput {
+                    name: "label".to_string(),
+                },
+                ValidationProblem::UnknownOutput {
+                    name: "logits".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_batch_sizes() {
+        let request = request(
+            vec![
+                tensor("image", "FP32", &[8, 3, 5]),
+                tensor("label", "BYTES", &[2, 1]),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            validate_request(&request, &config(4)).unwrap_err(),
+            vec![
+                ValidationProblem::InvalidBatchSize {
+                    name: "image".to_string(),
+                    max_batch_size: 4,
+                    actual: 8,
+                },
+                ValidationProblem::InconsistentBatchSize {
+                    name: "label".to_string(),
+                    expected: 8,
+                    actual: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_rank_without_batching() {
+        let request = request(
+            vec![
+                tensor("image", "FP32", &[1, 3, 5]),
+                tensor("label", "BYTES", &[1]),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            validate_request(&request, &config(0)).unwrap_err(),
+            vec![ValidationProblem::RankMismatch {
+                name: "image".to_string(),
+                expected: 2,
+                actual: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn checks_raw_data_size() {
+        let request = request(
+            vec![
+                tensor("image", "FP32", &[1, 3, 5]),
+                tensor("label", "BYTES", &[1, 1]),
+            ],
+            vec![vec![0; 3 * 5], vec![0; 7]],
+        );
+        assert_eq!(
+            validate_request(&request, &config(4)).unwrap_err(),
+            vec![ValidationProblem::DataSizeMismatch {
+                name: "image".to_string(),
+                expected: 3 * 5 * 4,
+                actual: 3 * 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_an_overflowing_shape_as_a_size_mismatch() {
+        let request = request(
+            vec![
+                tensor("image", "FP32", &[1, 3, i64::MAX]),
+                tensor("label", "BYTES", &[1, 1]),
+            ],
+            vec![vec![0; 4], vec![0; 5]],
+        );
+        assert_eq!(
+            validate_request(&request, &config(4)).unwrap_err(),
+            vec![ValidationProblem::DataSizeMismatch {
+                name: "image".to_string(),
+                expected: usize::MAX,
+                actual: 4,
+            }]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use super::client::{Error, Result};
+use super::pb::model_warmup::input::InputDataType;
+use super::pb::{self, DataType, InferInputTensor, ModelInferRequest};
+use crate::types::{Bytes, TritonDataTypes};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A `model_warmup` entry turned into a request.
+#[derive(Clone, Debug)]
+pub struct WarmupSample {
+    pub name: String,
+    /// How many times the request is sent, at least once
+    pub count: u32,
+    pub request: ModelInferRequest,
+}
+
+// xorshift64*, warmup data only needs to look arbitrary.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+pub(crate) fn random_element(datatype: &TritonDataTypes, rng: &mut Rng, data: &mut Bytes) {
+    let bits = rng.next();
+    match datatype {
+        TritonDataTypes::BOOL => data.push((bits & 1) as u8),
+        TritonDataTypes::INT8 | TritonDataTypes::UINT8 => data.push(bits as u8),
+        TritonDataTypes::INT16 | TritonDataTypes::UINT16 => {
+            data.extend((bits as u16).to_le_bytes())
+        }
+        TritonDataTypes::INT32 | TritonDataTypes::UINT32 => {
+            data.extend((bits as u32).to_le_bytes())
+        }
+        TritonDataTypes::INT64 | TritonDataTypes::UINT64 => data.extend(bits.to_le_bytes()),
+        TritonDataTypes::FP16 => {
+            // [0.5, 1) with a random mantissa
+            let half = (14u16 << 10) | (bits as u16 & 0x3FF);
+            data.extend(half.to_le_bytes())
+        }
+        TritonDataTypes::BF16 => {
+            let value = rng.next_f64() as f32;
+            data.extend(((value.to_bits() >> 16) as u16).to_le_bytes())
+        }
+        TritonDataTypes::FP32 => data.extend((rng.next_f64() as f32).to_le_bytes()),
+        TritonDataTypes::FP64 => data.extend(rng.next_f64().to_le_bytes()),
+        // Random strings fall back to zero data, as on the server
+        TritonDataTypes::BYTES => data.extend(0u32.to_le_bytes()),
+    }
+}
+
+fn input_data(
+    name: &str,
+    input: &pb::model_warmup::Input,
+    datatype: &TritonDataTypes,
+    warmup_dir: Option<&Path>,
+    rng: &mut Rng,
+) -> Result<Bytes> {
+    if input.dims.iter().any(|dim| *dim < 0) {
+        return Err(Error::ConversionError(format!(
+            "Warmup input `{name}` has dynamic dims {:?}",
+            input.dims
+        )));
+    }
+    // BYTES elements are a 4-byte length followed by the (empty) content
+    let element_size = datatype.size_of().unwrap_or(4);
+    let too_large = || {
+        Error::ConversionError(format!(
+            "Warmup input `{name}` dims {:?} are too large",
+            input.dims
+        ))
+    };
+    let elements = input
+        .dims
+        .iter()
+        .try_fold(1usize, |count, dim| count.checked_mul(*dim as usize))
+        .ok_or_else(too_large)?;
+    let size = elements.checked_mul(element_size).ok_or_else(too_large)?;
+    match &input.input_data_type {
+        Some(InputDataType::InputDataFile(file)) => {
+            let dir = warmup_dir.ok_or_else(|| {
+                Error::ConversionError(format!(
+                    "Warmup input `{name}` reads `{file}` but no warmup directory was given"
+                ))
+            })?;
+            Ok(fs::read(dir.join(file))?)
+        }
+        Some(InputDataType::RandomData(_)) => {
+            let mut data = Vec::with_capacity(size);
+            for _ in 0..elements {
+                random_element(datatype, rng, &mut data);
+            }
+            Ok(data)
+        }
+        Some(InputDataType::ZeroData(_)) | None => Ok(vec![0; size]),
+    }
+}
+
+/// Requests for the `model_warmup` entries of `config`. Input data files are read from
+/// `warmup_dir`, the `warmup` directory next to the model versions.
+pub fn warmup_samples(
+    config: &pb::ModelConfig,
+    model_version: &str,
+    warmup_dir: Option<&Path>,
+) -> Result<Vec<WarmupSample>> {
+    let mut rng = Rng::new();
+    let mut samples = vec![];
+    for warmup in &config.model_warmup {
+        let batch_size = warmup.batch_size.max(1) as usize;
+        let mut names = warmup.inputs.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut request = ModelInferRequest {
+            model_name: config.name.clone(),
+            model_version: model_version.to_string(),
+            id: format!("warmup:{}", warmup.name),
+            ..Default::default()
+        };
+        for name in names {
+            let input = &warmup.inputs[name];
+            let datatype: TritonDataTypes = DataType::try_from(input.data_type)
+                .map_err(|_| {
+                    Error::ConversionError(format!(
+                        "Unknown data type {} of warmup input `{name}`",
+                        input.data_type
+                    ))
+                })?
+                .try_into()?;
+            let sample = input_data(name, input, &datatype, warmup_dir, &mut rng)?;
+
+            let mut shape = input.dims.clone();
+            let mut data = sample.clone();
+            if config.max_batch_size > 0 {
+                shape.insert(0, batch_size as i64);
+                // Every row of the batch holds the same sample, as on the server
+                for _ in 1..batch_size {
+                    data.extend_from_slice(&sample);
+                }
+            }
+            request.inputs.push(InferInputTensor {
+                name: name.clone(),
+                datatype: datatype.to_string(),
+                shape,
+                ..Default::default()
+            });
+            request.raw_input_contents.push(data);
+        }
+        samples.push(WarmupSample {
+            name: warmup.name.clone(),
+            count: warmup.count.max(1),
+            request,
+        });
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::pb::model_warmup::Input;
+    use std::collections::HashMap;
+
+    fn input(data_type: DataType, dims: Vec<i64>, data: InputDataType) -> Input {
+        Input {
+            data_type: data_type as i32,
+            dims,
+            input_data_type: Some(data),
+        }
+    }
+
+    fn config(
+        max_batch_size: i32,
+        batch_size: u32,
+        inputs: HashMap<String, Input>,
+    ) -> pb::ModelConfig {
+        pb::ModelConfig {
+            name: "model".to_string(),
+            max_batch_size,
+            model_warmup: vec![pb::ModelWarmup {
+                name: "sample".to_string(),
+                batch_size,
+                inputs,
+                count: 0,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repeats_zero_data_for_every_row_of_the_batch() {
+        let inputs = HashMap::from([
+            (
+                "b".to_string(),
+                input(DataType::TypeInt32, vec![2], InputDataType::ZeroData(true)),
+            ),
+            (
+                "a".to_string(),
+                input(DataType::TypeString, vec![3], InputDataType::ZeroData(true)),
+            ),
+        ]);
+        let samples = warmup_samples(&config(8, 3, inputs), "2", None).unwrap();
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(sample.name, "sample");
+        assert_eq!(sample.count, 1);
+        assert_eq!(sample.request.model_name, "model");
+        assert_eq!(sample.request.model_version, "2");
+        assert_eq!(sample.request.id, "warmup:sample");
+
+        // Inputs are sorted by name
+        let a = &sample.request.inputs[0];
+        assert_eq!((a.name.as_str(), a.datatype.as_str()), ("a", "BYTES"));
+        assert_eq!(a.shape, vec![3, 3]);
+        assert_eq!(sample.request.raw_input_contents[0], vec![0; 3 * 3 * 4]);
+        assert_eq!(sample.request.inputs[1].shape, vec![3, 2]);
+        assert_eq!(sample.request.raw_input_contents[1], vec![0; 3 * 2 * 4]);
+    }
+
+    #[test]
+    fn generates_random_data_of_the_input_size() {
+        let inputs = HashMap::from([(
+            "x".to_string(),
+            input(DataType::TypeFp32, vec![4], InputDataType::RandomData(true)),
+        )]);
+        let samples = warmup_samples(&config(8, 2, inputs), "", None).unwrap();
+        let data = &samples[0].request.raw_input_contents[0];
+        assert_eq!(data.len(), 2 * 4 * 4);
+        // One random sample, repeated for the second row
+        assert_eq!(data[..16], data[16..]);
+        let values = data[..16]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert!(values.iter().all(|v| (0.0..1.0).contains(v)), "{values:?}");
+    }
+
+    #[test]
+    fn reads_data_files_from_the_warmup_dir() {
+        let dir = std::env::temp_dir().join(format!("tritonclient-warmup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("x.raw"), [1u8, 2, 3, 4]).unwrap();
+        let inputs = HashMap::from([(
+            "x".to_string(),
+            input(
+                DataType::TypeUint8,
+                vec![4],
+                InputDataType::InputDataFile("x.raw".to_string()),
+            ),
+        )]);
+        let config = config(0, 4, inputs);
+
+        let samples = warmup_samples(&config, "", Some(&dir)).unwrap();
+        // Without batching no batch dimension is added, whatever the batch size
+        assert_eq!(samples[0].request.inputs[0].shape, vec![4]);
+        assert_eq!(samples[0].request.raw_input_contents[0], vec![1, 2, 3, 4]);
+        assert!(warmup_samples(&config, "", None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(warmup_samples(&config, "", Some(&dir)).is_err());
+    }
+
+    #[test]
+    fn rejects_dynamic_and_oversized_dims() {
+        for dims in [vec![-1, 2], vec![i64::MAX, 4]] {
+            let inputs = HashMap::from([(
+                "x".to_string(),
+                input(
+                    DataType::TypeInt32,
+                    dims.clone(),
+                    InputDataType::ZeroData(true),
+                ),
+            )]);
+            match warmup_samples(&config(8, 1, inputs), "", None) {
+                Err(Error::ConversionError(message)) => {
+                    assert!(message.contains("`x`"), "{message}")
+                }
+                other => panic!("unexpected result for {dims:?}: {other:?}"),
+            }
+        }
+    }
+}
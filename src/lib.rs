@@ -1,2 +1,3 @@
 pub mod grpc;
+pub mod server_metrics;
 pub mod types;
@@ -0,0 +1,572 @@
+use crate::grpc::client::{Error, Result};
+use http_body_util::{BodyExt, Empty};
+use hyper::body::Bytes;
+use hyper::Uri;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Default address of the Triton metrics endpoint.
+pub const DEFAULT_METRICS_URL: &str = "http://localhost:8002/metrics";
+
+/// One sample line of the Prometheus text exposition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+impl Sample {
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels.get(name).map(String::as_str)
+    }
+}
+
+struct Cursor<'a> {
+    line: &'a str,
+    number: usize,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn error<T>(&self, message: impl ToString) -> Result<T> {
+        Err(Error::ParseError {
+            line: self.number,
+            column: self.position + 1,
+            message: message.to_string(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.line[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while let Some(c) = self.peek().filter(|c| f(*c)) {
+            self.position += c.len_utf8();
+        }
+        &self.line[start..self.position]
+    }
+
+    fn name(&mut self) -> Result<&'a str> {
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+        match name.chars().next() {
+            Some(c) if !c.is_ascii_digit() => Ok(name),
+            _ => self.error("expected a metric or label name"),
+        }
+    }
+
+    fn label_value(&mut self) -> Result<String> {
+        if !self.eat('"') {
+            return self.error("expected `\"`");
+        }
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated label value"),
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some('n') => value.push('\n'),
+                        Some(c @ ('\\' | '"')) => value.push(c),
+                        _ => return self.error("invalid escape in label value"),
+                    }
+                    self.position += 1;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.position += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn labels(&mut self) -> Result<BTreeMap<String, String>> {
+        let mut labels = BTreeMap::new();
+        if !self.eat('{') {
+            return Ok(labels);
+        }
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                return Ok(labels);
+            }
+            let name = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.eat('=') {
+                return self.error("expected `=`");
+            }
+            self.skip_whitespace();
+            labels.insert(name, self.label_value()?);
+            self.skip_whitespace();
+            if !self.eat(',') && self.peek() != Some('}') {
+                return self.error("expected `,` or `}`");
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<f64> {
+        self.skip_whitespace();
+        let token = self.take_while(|c| c != ' ' && c != '\t');
+        match token.parse::<f64>() {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.position -= token.len();
+                self.error(format!("invalid sample value `{token}`"))
+            }
+        }
+    }
+}
+
+/// Parse the samples of a Prometheus text exposition, skipping comments and the optional
+/// timestamps.
+pub fn parse_samples(text: &str) -> Result<Vec<Sample>> {
+    let mut samples = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let mut cursor = Cursor {
+            line,
+            number: index + 1,
+            position: 0,
+        };
+        cursor.skip_whitespace();
+        if matches!(cursor.peek(), None | Some('#')) {
+            continue;
+        }
+        let name = cursor.name()?.to_string();
+        cursor.skip_whitespace();
+        let labels = cursor.labels()?;
+        let value = cursor.value()?;
+        cursor.skip_whitespace();
+        if cursor.peek().is_some() {
+            cursor.take_while(|c| c.is_ascii_digit() || c == '-');
+            cursor.skip_whitespace();
+            if cursor.peek().is_some() {
+                return cursor.error("unexpected content after the sample");
+            }
+        }
+        samples.push(Sample {
+            name,
+            labels,
+            value,
+        });
+    }
+    Ok(samples)
+}
+
+// Counters are exposed as floats, durations in microseconds.
+fn count(value: f64) -> u64 {
+    value as u64
+}
+
+fn micros(value: f64) -> Duration {
+    Duration::from_nanos((value * 1000.0) as u64)
+}
+
+/// `nv_inference_*` and per-model cache metrics of one model version, summed over GPUs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelMetrics {
+    pub name: String,
+    pub version: String,
+    pub request_success: u64,
+    /// Failed requests of every reason
+    pub request_failure: u64,
+    pub inference_count: u64,
+    pub execution_count: u64,
+    /// Cumulative durations
+    pub request_duration: Duration,
+    pub queue_duration: Duration,
+    pub compute_input_duration: Duration,
+    pub compute_infer_duration: Duration,
+    pub compute_output_duration: Duration,
+    /// Requests received but not yet executed
+    pub pending_requests: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_hit_duration: Duration,
+    pub cache_miss_duration: Duration,
+}
+
+impl ModelMetrics {
+    /// Successful and failed requests
+    pub fn requests(&self) -> u64 {
+        self.request_success + self.request_failure
+    }
+
+    pub fn average_request_duration(&self) -> Option<Duration> {
+        average(self.request_duration, self.requests())
+    }
+
+    pub fn average_queue_duration(&self) -> Option<Duration> {
+        average(self.queue_duration, self.requests())
+    }
+
+    /// Share of cache lookups that hit, `None` when the response cache was not used.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+    }
+
+    fn add(&mut self, name: &str, value: f64) -> bool {
+        match name {
+            "nv_inference_request_success" => self.request_success += count(value),
+            "nv_inference_request_failure" => self.request_failure += count(value),
+            "nv_inference_count" => self.inference_count += count(value),
+            "nv_inference_exec_count" => self.execution_count += count(value),
+            "nv_inference_request_duration_us" => self.request_duration += micros(value),
+            "nv_inference_queue_duration_us" => self.queue_duration += micros(value),
+            "nv_inference_compute_input_duration_us" => {
+                self.compute_input_duration += micros(value)
+            }
+            "nv_inference_compute_infer_duration_us" => {
+                self.compute_infer_duration += micros(value)
+            }
+            "nv_inference_compute_output_duration_us" => {
+                self.compute_output_duration += micros(value)
+            }
+            "nv_inference_pending_request_count" => self.pending_requests += count(value),
+            "nv_cache_num_hits_per_model" => self.cache_hits += count(value),
+            "nv_cache_num_misses_per_model" => self.cache_misses += count(value),
+            "nv_cache_hit_duration_per_model" => self.cache_hit_duration += micros(value),
+            "nv_cache_miss_duration_per_model" => self.cache_miss_duration += micros(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn average(total: Duration, count: u64) -> Option<Duration> {
+    (count > 0).then(|| Duration::from_nanos((total.as_nanos() / count as u128) as u64))
+}
+
+/// `nv_gpu_*` and `nv_energy_consumption` of one GPU.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuMetrics {
+    pub uuid: String,
+    /// In [0, 1]
+    pub utilization: f64,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+    /// Watts
+    pub power_usage: f64,
+    pub power_limit: f64,
+    /// Joules since the server started
+    pub energy_consumption: f64,
+}
+
+impl GpuMetrics {
+    pub fn memory_utilization(&self) -> Option<f64> {
+        (self.memory_total_bytes > 0)
+            .then(|| self.memory_used_bytes as f64 / self.memory_total_bytes as f64)
+    }
+
+    fn set(&mut self, name: &str, value: f64) -> bool {
+        match name {
+            "nv_gpu_utilization" => self.utilization = value,
+            "nv_gpu_memory_total_bytes" => self.memory_total_bytes = count(value),
+            "nv_gpu_memory_used_bytes" => self.memory_used_bytes = count(value),
+            "nv_gpu_power_usage" => self.power_usage = value,
+            "nv_gpu_power_limit" => self.power_limit = value,
+            "nv_energy_consumption" => self.energy_consumption = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Server-wide response cache metrics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheMetrics {
+    pub entries: u64,
+    pub lookups: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// In [0, 1]
+    pub utilization: f64,
+    /// Cumulative durations
+    pub lookup_duration: Duration,
+    pub insertion_duration: Duration,
+}
+
+impl CacheMetrics {
+    fn set(&mut self, name: &str, value: f64) -> bool {
+        match name {
+            "nv_cache_num_entries" => self.entries = count(value),
+            "nv_cache_num_lookups" => self.lookups = count(value),
+            "nv_cache_num_hits" => self.hits = count(value),
+            "nv_cache_num_misses" => self.misses = count(value),
+            "nv_cache_num_evictions" => self.evictions = count(value),
+            "nv_cache_util" => self.utilization = value,
+            "nv_cache_lookup_duration" => self.lookup_duration = micros(value),
+            "nv_cache_insertion_duration" => self.insertion_duration = micros(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CpuMetrics {
+    /// In [0, 1]
+    pub utilization: f64,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+}
+
+impl CpuMetrics {
+    fn set(&mut self, name: &str, value: f64) -> bool {
+        match name {
+            "nv_cpu_utilization" => self.utilization = value,
+            "nv_cpu_memory_total_bytes" => self.memory_total_bytes = count(value),
+            "nv_cpu_memory_used_bytes" => self.memory_used_bytes = count(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Typed view of one scrape of the Triton metrics endpoint.
+#[derive(Clone, Debug, Default)]
+pub struct TritonMetrics {
+    pub models: Vec<ModelMetrics>,
+    pub gpus: Vec<GpuMetrics>,
+    /// `None` when the server runs without a response cache
+    pub cache: Option<CacheMetrics>,
+    /// `None` when CPU metrics are disabled
+    pub cpu: Option<CpuMetrics>,
+    /// Every sample, including the ones without a typed field
+    pub samples: Vec<Sample>,
+}
+
+impl TritonMetrics {
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(Self::from(parse_samples(text)?))
+    }
+
+    pub fn model(&self, model_name: &str, version: &str) -> Option<&ModelMetrics> {
+        self.models
+            .iter()
+            .find(|metrics| metrics.name == model_name && metrics.version == version)
+    }
+
+    pub fn gpu(&self, uuid: &str) -> Option<&GpuMetrics> {
+        self.gpus.iter().find(|metrics| metrics.uuid == uuid)
+    }
+
+    /// Samples of the metric `name`, e.g. `nv_inference_request_summary_us`.
+    pub fn samples_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Sample> {
+        self.samples
+            .iter()
+            .filter(move |sample| sample.name == name)
+    }
+}
+
+impl From<Vec<Sample>> for TritonMetrics {
+    fn from(samples: Vec<Sample>) -> Self {
+        let mut models: BTreeMap<(String, String), ModelMetrics> = BTreeMap::new();
+        let mut gpus: BTreeMap<String, GpuMetrics> = BTreeMap::new();
+        let mut cache = CacheMetrics::default();
+        let mut cpu = CpuMetrics::default();
+        let (mut has_cache, mut has_cpu) = (false, false);
+
+        for sample in &samples {
+            let (name, value) = (sample.name.as_str(), sample.value);
+            // Inference metrics of GPU models also carry `gpu_uuid`, so the model comes first.
+            if let (Some(model), Some(version)) = (sample.label("model"), sample.label("version")) {
+                models
+                    .entry((model.to_string(), version.to_string()))
+                    .or_insert_with(|| ModelMetrics {
+                        name: model.to_string(),
+                        version: version.to_string(),
+                        ..ModelMetrics::default()
+                    })
+                    .add(name, value);
+            } else if let Some(uuid) = sample.label("gpu_uuid") {
+                let mut gpu = gpus.get(uuid).cloned().unwrap_or_else(|| GpuMetrics {
+                    uuid: uuid.to_string(),
+                    ..GpuMetrics::default()
+                });
+                if gpu.set(name, value) {
+                    gpus.insert(uuid.to_string(), gpu);
+                }
+            } else {
+                has_cache |= cache.set(name, value);
+                has_cpu |= cpu.set(name, value);
+            }
+        }
+
+        Self {
+            models: models.into_values().collect(),
+            gpus: gpus.into_values().collect(),
+            cache: has_cache.then_some(cache),
+            cpu: has_cpu.then_some(cpu),
+            samples,
+        }
+    }
+}
+
+impl FromStr for TritonMetrics {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Self::parse(text)
+    }
+}
+
+/// Scrapes the Triton metrics endpoint over plain HTTP, keeping the connection between scrapes.
+#[derive(Clone, Debug)]
+pub struct MetricsClient {
+    url: Uri,
+    client: Client<HttpConnector, Empty<Bytes>>,
+}
+
+impl MetricsClient {
+    /// `url` of the endpoint, e.g. [`DEFAULT_METRICS_URL`]
+    pub fn new(url: &str) -> Result<Self> {
+        let url = url
+            .parse::<Uri>()
+            .map_err(|e| Error::InvalidUri(e.to_string()))?;
+        Ok(Self {
+            url,
+            client: Client::builder(TokioExecutor::new()).build_http(),
+        })
+    }
+
+    pub fn url(&self) -> &Uri {
+        &self.url
+    }
+
+    /// The raw text exposition.
+    pub async fn fetch_text(&self) -> Result<String> {
+        let response = self
+            .client
+            .get(self.url.clone())
+            .await
+            .map_err(|e| Error::HttpError(format!("{}: {e}", self.url)))?;
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| Error::HttpError(format!("{}: {e}", self.url)))?
+            .to_bytes();
+        if !status.is_success() {
+            return Err(Error::HttpError(format!("{}: status {status}", self.url)));
+        }
+        String::from_utf8(body.to_vec()).map_err(|e| Error::ConversionError(e.to_string()))
+    }
+
+    pub async fn fetch(&self) -> Result<TritonMetrics> {
+        TritonMetrics::parse(&self.fetch_text().await?)
+    }
+}
+
+/// Scrape `url` once, see [`MetricsClient`] to poll.
+pub async fn fetch_metrics(url: &str) -> Result<TritonMetrics> {
+    MetricsClient::new(url)?.fetch().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRAPE: &str = r#"# HELP nv_inference_request_success Successful inference requests
+# TYPE nv_inference_request_success counter
+nv_inference_request_success{model="resnet",version="1"} 40
+nv_inference_request_failure{model="resnet",reason="REJECTED",version="1"} 2
+nv_inference_request_failure{model="resnet",reason="BACKEND",version="1"} 3
+nv_inference_request_duration_us{model="resnet",version="1",gpu_uuid="GPU-a"} 30000
+nv_inference_request_duration_us{model="resnet",version="1",gpu_uuid="GPU-b"} 60000
+nv_inference_queue_duration_us{model="resnet",version="1"} 4500 1700000000000
+nv_gpu_utilization{gpu_uuid="GPU-a"} 0.5
+nv_gpu_memory_total_bytes{gpu_uuid="GPU-a"} 1000
+nv_gpu_memory_used_bytes{gpu_uuid="GPU-a"} 250
+nv_cpu_utilization 0.25
+nv_inference_request_summary_us{model="resnet",version="1",quantile="0.5"} +Inf
+"#;
+
+    #[test]
+    fn parses_samples() {
+        let samples = parse_samples(SCRAPE).unwrap();
+        assert_eq!(samples.len(), 11);
+        assert_eq!(samples[1].name, "nv_inference_request_failure");
+        assert_eq!(samples[1].label("reason"), Some("REJECTED"));
+        assert_eq!(samples[1].value, 2.0);
+        assert_eq!(samples[5].value, 4500.0);
+        assert_eq!(samples[9].labels, BTreeMap::new());
+        assert_eq!(samples[10].value, f64::INFINITY);
+    }
+
+    #[test]
+    fn unescapes_label_values() {
+        let samples = parse_samples(r#"m{path="a\\b",text="say \"hi\"\n"} 1"#).unwrap();
+        assert_eq!(samples[0].label("path"), Some(r"a\b"));
+        assert_eq!(samples[0].label("text"), Some("say \"hi\"\n"));
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let errors = [
+            ("ok 1\nbad{model=\"a\" 1\n", 2, 15),
+            ("m{model=\"a\"} one\n", 1, 14),
+            ("m 1 2 3\n", 1, 7),
+        ];
+        for (text, line, column) in errors {
+            match parse_samples(text) {
+                Err(Error::ParseError {
+                    line: l, column: c, ..
+                }) => assert_eq!((l, c), (line, column), "{text}"),
+                other => panic!("unexpected result {other:?} for {text}"),
+            }
+        }
+    }
+
+    #[test]
+    fn sums_model_metrics_over_gpus() {
+        let metrics = TritonMetrics::parse(SCRAPE).unwrap();
+        let model = metrics.model("resnet", "1").unwrap();
+        assert_eq!(model.request_success, 40);
+        assert_eq!(model.request_failure, 5);
+        assert_eq!(model.request_duration, Duration::from_millis(90));
+        assert_eq!(
+            model.average_queue_duration(),
+            Some(Duration::from_micros(100))
+        );
+
+        let gpu = metrics.gpu("GPU-a").unwrap();
+        assert_eq!(gpu.utilization, 0.5);
+        assert_eq!(gpu.memory_utilization(), Some(0.25));
+        assert_eq!(metrics.cpu.as_ref().map(|cpu| cpu.utilization), Some(0.25));
+        assert!(metrics.cache.is_none());
+        assert_eq!(
+            metrics
+                .samples_named("nv_inference_request_summary_us")
+                .count(),
+            1
+        );
+    }
+}
@@ -1,4 +1,6 @@
+use crate::grpc::client::Error;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum TritonDataTypes {
@@ -18,6 +20,19 @@ pub enum TritonDataTypes {
     BYTES,
 }
 
+impl TritonDataTypes {
+    /// Size in bytes of one element, `None` for variable-size `BYTES`.
+    pub fn size_of(&self) -> Option<usize> {
+        match self {
+            Self::BOOL | Self::INT8 | Self::UINT8 => Some(1),
+            Self::INT16 | Self::UINT16 | Self::FP16 | Self::BF16 => Some(2),
+            Self::INT32 | Self::UINT32 | Self::FP32 => Some(4),
+            Self::INT64 | Self::UINT64 | Self::FP64 => Some(8),
+            Self::BYTES => None,
+        }
+    }
+}
+
 impl Display for TritonDataTypes {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -39,29 +54,87 @@ impl Display for TritonDataTypes {
     }
 }
 
+impl FromStr for TritonDataTypes {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "BOOL" => Ok(Self::BOOL),
+            "BYTES" => Ok(Self::BYTES),
+            "INT8" => Ok(Self::INT8),
+            "INT16" => Ok(Self::INT16),
+            "INT32" => Ok(Self::INT32),
+            "INT64" => Ok(Self::INT64),
+            "UINT8" => Ok(Self::UINT8),
+            "UINT16" => Ok(Self::UINT16),
+            "UINT32" => Ok(Self::UINT32),
+            "UINT64" => Ok(Self::UINT64),
+            "FP16" => Ok(Self::FP16),
+            "BF16" => Ok(Self::BF16),
+            "FP32" => Ok(Self::FP32),
+            "FP64" => Ok(Self::FP64),
+            _ => Err(Error::ConversionError(format!(
+                "Invalid data type `{value}`"
+            ))),
+        }
+    }
+}
+
 impl<T> From<T> for TritonDataTypes
 where
     T: AsRef<str>,
 {
     fn from(value: T) -> Self {
-        match value.as_ref() {
-            "BOOL" => Self::BOOL,
-            "BYTES" => Self::BYTES,
-            "INT8" => Self::INT8,
-            "INT16" => Self::INT16,
-            "INT32" => Self::INT32,
-            "INT64" => Self::INT64,
-            "UINT8" => Self::UINT8,
-            "UINT16" => Self::UINT16,
-            "UINT32" => Self::UINT32,
-            "UINT64" => Self::UINT64,
-            "FP16" => Self::FP16,
-            "BF16" => Self::BF16,
-            "FP32" => Self::FP32,
-            "FP64" => Self::FP64,
-            _ => panic!("Invalid data type!"),
-        }
+        value.as_ref().parse().expect("Invalid data type!")
     }
 }
 
 pub type Bytes = Vec<u8>;
+
+impl From<TritonDataTypes> for crate::grpc::pb::DataType {
+    fn from(value: TritonDataTypes) -> Self {
+        match value {
+            TritonDataTypes::BOOL => Self::TypeBool,
+            TritonDataTypes::INT8 => Self::TypeInt8,
+            TritonDataTypes::INT16 => Self::TypeInt16,
+            TritonDataTypes::INT32 => Self::TypeInt32,
+            TritonDataTypes::INT64 => Self::TypeInt64,
+            TritonDataTypes::UINT8 => Self::TypeUint8,
+            TritonDataTypes::UINT16 => Self::TypeUint16,
+            TritonDataTypes::UINT32 => Self::TypeUint32,
+            TritonDataTypes::UINT64 => Self::TypeUint64,
+            TritonDataTypes::FP16 => Self::TypeFp16,
+            TritonDataTypes::BF16 => Self::TypeBf16,
+            TritonDataTypes::FP32 => Self::TypeFp32,
+            TritonDataTypes::FP64 => Self::TypeFp64,
+            TritonDataTypes::BYTES => Self::TypeString,
+        }
+    }
+}
+
+impl TryFrom<crate::grpc::pb::DataType> for TritonDataTypes {
+    type Error = Error;
+
+    fn try_from(value: crate::grpc::pb::DataType) -> Result<Self, Self::Error> {
+        use crate::grpc::pb::DataType;
+        match value {
+            DataType::TypeBool => Ok(Self::BOOL),
+            DataType::TypeInt8 => Ok(Self::INT8),
+            DataType::TypeInt16 => Ok(Self::INT16),
+            DataType::TypeInt32 => Ok(Self::INT32),
+            DataType::TypeInt64 => Ok(Self::INT64),
+            DataType::TypeUint8 => Ok(Self::UINT8),
+            DataType::TypeUint16 => Ok(Self::UINT16),
+            DataType::TypeUint32 => Ok(Self::UINT32),
+            DataType::TypeUint64 => Ok(Self::UINT64),
+            DataType::TypeFp16 => Ok(Self::FP16),
+            DataType::TypeBf16 => Ok(Self::BF16),
+            DataType::TypeFp32 => Ok(Self::FP32),
+            DataType::TypeFp64 => Ok(Self::FP64),
+            DataType::TypeString => Ok(Self::BYTES),
+            DataType::TypeInvalid => Err(Error::ConversionError(
+                "Invalid data type `TYPE_INVALID`".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use ndarray::ArrayD;
+use tritonclient::grpc::client::Error;
+use tritonclient::grpc::input::{InferInput, ModelInput};
+use tritonclient::grpc::output::ArrayOutputOneOf;
+use tritonclient::grpc::pb;
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer, MockServerHandle};
+use tritonclient::types::TritonDataTypes;
+
+async fn server(handler: InferHandler) -> MockServerHandle {
+    let model = MockModel::new("model")
+        .max_batch_size(2)
+        .input("x", TritonDataTypes::INT32, &[2])
+        .output("x", TritonDataTypes::INT32, &[2])
+        .handler(handler);
+    MockServer::new()
+        .model(model)
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+fn samples(count: i32) -> Vec<Vec<InferInput>> {
+    (0..count)
+        .map(|i| {
+            let array = ArrayD::from_shape_vec(vec![2], vec![i, -i]).unwrap();
+            vec![InferInput::new()
+                .name("x".to_string())
+                .datatype(TritonDataTypes::INT32)
+                .data_from_ndarray(array)]
+        })
+        .collect()
+}
+
+fn template() -> ModelInput {
+    ModelInput::new().model_name("model".to_string())
+}
+
+#[tokio::test]
+async fn batches_samples_and_splits_outputs() {
+    let server = server(InferHandler::Echo).await;
+    let outputs = server
+        .client()
+        .infer_samples(template(), samples(5))
+        .await
+        .unwrap();
+
+    assert_eq!(server.infer_requests().len(), 3);
+    assert_eq!(outputs.len(), 5);
+    match outputs[3].as_ndarray("x") {
+        Some(ArrayOutputOneOf::INT32(array)) => {
+            assert_eq!(array.iter().copied().collect::<Vec<_>>(), vec![3, -3])
+        }
+        other => panic!("unexpected output {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn fails_when_a_batch_returns_fewer_outputs() {
+    // One row whatever the batch size of the request
+    let response = pb::ModelInferResponse {
+        outputs: vec![pb::InferOutputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![1, 2],
+            ..Default::default()
+        }],
+        raw_output_contents: vec![vec![0; 8]],
+        ..Default::default()
+    };
+    let server = server(InferHandler::Fixed(response)).await;
+    let error = server
+        .client()
+        .infer_samples(template(), samples(2))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidBatch(_)), "{error}");
+}
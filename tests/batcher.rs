@@ -0,0 +1,224 @@
+use futures_util::future::join_all;
+use std::time::Duration;
+use tonic::{Code, Status};
+use tritonclient::grpc::client::{DynamicBatcher, Error};
+use tritonclient::grpc::output::{ArrayOutputOneOf, ModelOutput};
+use tritonclient::grpc::pb;
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer, MockServerHandle};
+use tritonclient::types::TritonDataTypes;
+
+async fn serve(max_batch_size: i32, handler: InferHandler) -> MockServerHandle {
+    MockServer::new()
+        .model(
+            MockModel::new("model")
+                .max_batch_size(max_batch_size)
+                .input("x", TritonDataTypes::INT32, &[2])
+                .output("x", TritonDataTypes::INT32, &[2])
+                .handler(handler),
+        )
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+// One request of `values.len() / 2` rows.
+fn request(values: &[i32]) -> pb::ModelInferRequest {
+    pb::ModelInferRequest {
+        model_name: "model".to_string(),
+        inputs: vec![pb::InferInputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![values.len() as i64 / 2, 2],
+            contents: Some(pb::InferTensorContents {
+                int_contents: values.to_vec(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn ints(output: &ModelOutput) -> Vec<i32> {
+    match output.as_ndarray("x") {
+        Some(ArrayOutputOneOf::INT32(array)) => array.iter().copied().collect(),
+        other => panic!("unexpected output {other:?}"),
+    }
+}
+
+// Batch dimension of every request the server got, sorted as the groups are sent concurrently.
+fn sent_rows(server: &MockServerHandle) -> Vec<i64> {
+    let mut rows = server
+        .infer_requests()
+        .iter()
+        .map(|request| request.inputs[0].shape[0])
+        .collect::<Vec<_>>();
+    rows.sort_unstable();
+    rows
+}
+
+async fn infer_all(
+    batcher: &DynamicBatcher,
+    requests: Vec<pb::ModelInferRequest>,
+) -> Vec<Result<ModelOutput, Error>> {
+    join_all(requests.into_iter().map(|request| batcher.infer(request))).await
+}
+
+#[tokio::test]
+async fn merges_calls_within_the_delay() {
+    let server = serve(8, InferHandler::Echo).await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+    let outputs = infer_all(
+        &batcher,
+        vec![request(&[1, 2]), request(&[3, 4, 5, 6]), request(&[7, 8])],
+    )
+    .await;
+
+    assert_eq!(server.calls("ModelInfer"), 1);
+    let sent = server.infer_requests();
+    assert_eq!(sent[0].inputs[0].shape, vec![4, 2]);
+    let outputs = outputs.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(ints(&outputs[0]), vec![1, 2]);
+    assert_eq!(ints(&outputs[1]), vec![3, 4, 5, 6]);
+    assert_eq!(ints(&outputs[2]), vec![7, 8]);
+}
+
+#[tokio::test]
+async fn cuts_batches_at_the_max_batch_size() {
+    let server = serve(4, InferHandler::Echo).await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+    let requests = (0..6).map(|i| request(&[i, -i])).collect();
+    let outputs = infer_all(&batcher, requests).await;
+    assert_eq!(sent_rows(&server), vec![2, 4]);
+    for (i, output) in (0..).zip(outputs) {
+        assert_eq!(ints(&output.unwrap()), vec![i, -i]);
+    }
+
+    // The batcher limit applies below the model one.
+    server.clear_infer_requests();
+    let batcher = DynamicBatcher::new(server.client())
+        .max_queue_delay(Duration::from_millis(50))
+        .max_batch_size(3);
+    let requests = (0..6).map(|i| request(&[i, -i])).collect();
+    infer_all(&batcher, requests).await;
+    assert_eq!(sent_rows(&server), vec![3, 3]);
+}
+
+#[tokio::test]
+async fn holds_a_call_that_would_overflow_the_batch() {
+    let server = serve(4, InferHandler::Echo).await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+    let outputs = infer_all(
+        &batcher,
+        vec![request(&[1, 1, 2, 2, 3, 3]), request(&[4, 4, 5, 5])],
+    )
+    .await;
+    assert_eq!(sent_rows(&server), vec![2, 3]);
+    assert_eq!(ints(outputs[0].as_ref().unwrap()), vec![1, 1, 2, 2, 3, 3]);
+    assert_eq!(ints(outputs[1].as_ref().unwrap()), vec![4, 4, 5, 5]);
+}
+
+#[tokio::test]
+async fn sends_at_the_largest_preferred_batch_size() {
+    let server = serve(8, InferHandler::Echo).await;
+    // A long delay, only reaching the preferred size sends the first batches.
+    let batcher = DynamicBatcher::new(server.client())
+        .max_queue_delay(Duration::from_millis(200))
+        .preferred_batch_sizes(vec![1, 2, 16]);
+    let requests = (0..5).map(|i| request(&[i, i])).collect();
+    let outputs = infer_all(&batcher, requests).await;
+    assert_eq!(sent_rows(&server), vec![1, 2, 2]);
+    for (i, output) in (0..).zip(outputs) {
+        assert_eq!(ints(&output.unwrap()), vec![i, i]);
+    }
+}
+
+#[tokio::test]
+async fn sends_the_server_error_to_every_caller() {
+    let server = serve(
+        8,
+        InferHandler::Error(Status::invalid_argument("bad batch")),
+    )
+    .await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+    let requests = (0..3).map(|i| request(&[i, i])).collect();
+    let outputs = infer_all(&batcher, requests).await;
+    assert_eq!(server.calls("ModelInfer"), 1);
+    for output in outputs {
+        match output {
+            Err(Error::ResponseError { status }) => {
+                assert_eq!(status.code(), Code::InvalidArgument);
+                assert_eq!(status.message(), "bad batch");
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn keeps_incompatible_calls_apart() {
+    let server = serve(8, InferHandler::Echo).await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+
+    let mut with_output = request(&[3, 3]);
+    with_output.outputs = vec![pb::model_infer_request::InferRequestedOutputTensor {
+        name: "x".to_string(),
+        ..Default::default()
+    }];
+    let mut with_parameter = request(&[4, 4]);
+    with_parameter.parameters.insert(
+        "priority".to_string(),
+        pb::InferParameter {
+            parameter_choice: Some(pb::ParameterChoice::Int64Param(1)),
+        },
+    );
+    let mut wider = request(&[5, 5, 5]);
+    wider.inputs[0].shape = vec![1, 3];
+
+    let outputs = infer_all(
+        &batcher,
+        vec![
+            request(&[1, 1]),
+            with_output,
+            with_parameter,
+            wider,
+            request(&[2, 2]),
+        ],
+    )
+    .await;
+    assert_eq!(server.calls("ModelInfer"), 4);
+    assert_eq!(sent_rows(&server), vec![1, 1, 1, 2]);
+    let outputs = outputs.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(ints(&outputs[0]), vec![1, 1]);
+    assert_eq!(ints(&outputs[3]), vec![5, 5, 5]);
+    assert_eq!(ints(&outputs[4]), vec![2, 2]);
+}
+
+#[tokio::test]
+async fn sends_raw_and_shared_memory_calls_alone() {
+    let server = serve(8, InferHandler::Echo).await;
+    let batcher = DynamicBatcher::new(server.client()).max_queue_delay(Duration::from_millis(50));
+
+    let mut raw = request(&[]);
+    raw.inputs[0].shape = vec![1, 2];
+    raw.inputs[0].contents = None;
+    raw.raw_input_contents = vec![[1i32, 2].iter().flat_map(|v| v.to_le_bytes()).collect()];
+    let mut shared = request(&[]);
+    shared.inputs[0].shape = vec![1, 2];
+    shared.inputs[0].contents = None;
+    shared.inputs[0].parameters.insert(
+        "shared_memory_region".to_string(),
+        pb::InferParameter {
+            parameter_choice: Some(pb::ParameterChoice::StringParam("region".to_string())),
+        },
+    );
+
+    let outputs = infer_all(&batcher, vec![raw.clone(), raw.clone(), shared.clone()]).await;
+    assert_eq!(server.calls("ModelInfer"), 3);
+    let sent = server.infer_requests();
+    assert_eq!(sent.iter().filter(|request| **request == raw).count(), 2);
+    assert!(sent.contains(&shared));
+    assert_eq!(ints(outputs[0].as_ref().unwrap()), vec![1, 2]);
+    // The mock has no shared memory, the request reached it unchanged all the same.
+    assert!(outputs[2].is_err());
+}
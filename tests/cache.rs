@@ -0,0 +1,105 @@
+use std::time::Duration;
+use tritonclient::grpc::testing::{MockModel, MockServer, MockServerHandle};
+
+async fn server() -> MockServerHandle {
+    MockServer::new()
+        .model(MockModel::new("a"))
+        .model(MockModel::new("b"))
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn reuses_configs_and_metadata_until_the_ttl() {
+    let server = server().await;
+    let config = server
+        .client_config()
+        .model_cache_ttl(Duration::from_millis(200));
+    let client = server.client_with_config(config);
+
+    for _ in 0..3 {
+        client.cached_model_config("a", None).await.unwrap();
+        client.cached_model_metadata("a", None).await.unwrap();
+    }
+    assert_eq!(server.calls("ModelConfig"), 1);
+    assert_eq!(server.calls("ModelMetadata"), 1);
+    // Versions are cached apart
+    client.cached_model_config("a", Some("1")).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 2);
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_metadata("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 3);
+    assert_eq!(server.calls("ModelMetadata"), 2);
+}
+
+#[tokio::test]
+async fn a_zero_ttl_disables_the_cache() {
+    let server = server().await;
+    let config = server.client_config().model_cache_ttl(Duration::ZERO);
+    let client = server.client_with_config(config);
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 2);
+}
+
+#[tokio::test]
+async fn loads_and_unloads_invalidate_the_model() {
+    let server = server().await;
+    let client = server.client();
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("b", None).await.unwrap();
+
+    client.repository_model_load("", "a", None).await.unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("b", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 3);
+
+    client.repository_model_unload("", "b", None).await.unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    // The mock still answers for an unloaded model
+    client.cached_model_config("b", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 4);
+}
+
+#[tokio::test]
+async fn refreshes_invalidates_and_clears_on_demand() {
+    let server = server().await;
+    let client = server.client();
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("b", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 2);
+
+    client.refresh_model_config("a", None).await.unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 3);
+
+    client.invalidate_model_cache("a").unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("b", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 4);
+
+    client.cached_model_metadata("a", None).await.unwrap();
+    client.clear_model_cache().unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    client.cached_model_config("b", None).await.unwrap();
+    client.cached_model_metadata("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 6);
+    assert_eq!(server.calls("ModelMetadata"), 2);
+}
+
+#[tokio::test]
+async fn clones_share_the_cache() {
+    let server = server().await;
+    let client = server.client();
+    let clone = client.clone();
+    client.cached_model_config("a", None).await.unwrap();
+    clone.cached_model_config("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 1);
+
+    clone.invalidate_model_cache("a").unwrap();
+    client.cached_model_config("a", None).await.unwrap();
+    assert_eq!(server.calls("ModelConfig"), 2);
+}
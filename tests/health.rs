@@ -0,0 +1,58 @@
+use std::time::Duration;
+use tritonclient::grpc::health::{HealthWatch, ServingStatus, INFERENCE_SERVICE};
+use tritonclient::grpc::testing::MockServer;
+
+async fn next(watch: &mut HealthWatch) -> ServingStatus {
+    tokio::time::timeout(Duration::from_secs(5), watch.changed())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn checks_the_serving_status() {
+    let server = MockServer::new().serve_in_memory().await.unwrap();
+    let client = server.client();
+    assert_eq!(
+        client.check_health("").await.unwrap(),
+        ServingStatus::Serving
+    );
+    assert_eq!(
+        client.check_health(INFERENCE_SERVICE).await.unwrap(),
+        ServingStatus::Serving
+    );
+
+    server.set_ready(false);
+    assert_eq!(
+        client.check_health("").await.unwrap(),
+        ServingStatus::NotServing
+    );
+    assert!(!client.check_health("").await.unwrap().is_serving());
+}
+
+#[tokio::test]
+async fn unknown_services_are_reported_as_such() {
+    let server = MockServer::new().serve_in_memory().await.unwrap();
+    let client = server.client();
+    // The server answers NOT_FOUND, which is not an error for the client
+    assert_eq!(
+        client.check_health("other").await.unwrap(),
+        ServingStatus::ServiceUnknown
+    );
+    let mut watch = client.watch_health("other").await.unwrap();
+    assert_eq!(next(&mut watch).await, ServingStatus::ServiceUnknown);
+}
+
+#[tokio::test]
+async fn watch_follows_the_status() {
+    let server = MockServer::new().serve_in_memory().await.unwrap();
+    let client = server.client();
+    let mut watch = client.watch_health(INFERENCE_SERVICE).await.unwrap();
+    assert_eq!(next(&mut watch).await, ServingStatus::Serving);
+
+    server.set_ready(false);
+    assert_eq!(next(&mut watch).await, ServingStatus::NotServing);
+    server.set_ready(true);
+    assert_eq!(next(&mut watch).await, ServingStatus::Serving);
+}
@@ -0,0 +1,225 @@
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tonic::Status;
+use tritonclient::grpc::client::{
+    ERRORS, RECONNECTS, REQUEST_BYTES, REQUEST_DURATION, RESPONSE_BYTES, RETRIES,
+};
+use tritonclient::grpc::pb;
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer};
+use tritonclient::types::TritonDataTypes;
+
+// Metric name and sorted labels.
+type Labels = (String, Vec<(String, String)>);
+
+#[derive(Default)]
+struct Values {
+    counters: BTreeMap<Labels, u64>,
+    histograms: BTreeMap<Labels, Vec<f64>>,
+}
+
+// Keeps every value recorded through it.
+#[derive(Clone, Default)]
+struct TestRecorder(Arc<Mutex<Values>>);
+
+struct Handle {
+    values: Arc<Mutex<Values>>,
+    labels: Labels,
+}
+
+impl CounterFn for Handle {
+    fn increment(&self, value: u64) {
+        let mut values = self.values.lock().unwrap();
+        *values.counters.entry(self.labels.clone()).or_default() += value;
+    }
+
+    fn absolute(&self, value: u64) {
+        let mut values = self.values.lock().unwrap();
+        values.counters.insert(self.labels.clone(), value);
+    }
+}
+
+impl HistogramFn for Handle {
+    fn record(&self, value: f64) {
+        let mut values = self.values.lock().unwrap();
+        values
+            .histograms
+            .entry(self.labels.clone())
+            .or_default()
+            .push(value);
+    }
+}
+
+impl TestRecorder {
+    fn handle(&self, key: &Key) -> Arc<Handle> {
+        let mut labels = key
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect::<Vec<_>>();
+        labels.sort();
+        Arc::new(Handle {
+            values: self.0.clone(),
+            labels: (key.name().to_string(), labels),
+        })
+    }
+
+    fn counter(&self, name: &str, labels: &[(&str, &str)]) -> Option<u64> {
+        self.0
+            .lock()
+            .unwrap()
+            .counters
+            .get(&key(name, labels))
+            .copied()
+    }
+
+    fn histogram(&self, name: &str, labels: &[(&str, &str)]) -> Vec<f64> {
+        let values = self.0.lock().unwrap();
+        values
+            .histograms
+            .get(&key(name, labels))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn key(name: &str, labels: &[(&str, &str)]) -> Labels {
+    let mut labels = labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<Vec<_>>();
+    labels.sort();
+    (name.to_string(), labels)
+}
+
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.handle(key))
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.handle(key))
+    }
+}
+
+// Run `f` on a single-threaded runtime so that every task sees the local recorder.
+fn with_recorder<F: std::future::Future>(f: impl FnOnce() -> F) -> TestRecorder {
+    let recorder = TestRecorder::default();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    metrics::with_local_recorder(&recorder, || runtime.block_on(f()));
+    recorder
+}
+
+fn request(model_name: &str) -> pb::ModelInferRequest {
+    pb::ModelInferRequest {
+        model_name: model_name.to_string(),
+        inputs: vec![pb::InferInputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![2],
+            ..Default::default()
+        }],
+        raw_input_contents: vec![vec![0; 8]],
+        ..Default::default()
+    }
+}
+
+fn model(name: &str) -> MockModel {
+    MockModel::new(name)
+        .input("x", TritonDataTypes::INT32, &[2])
+        .output("x", TritonDataTypes::INT32, &[2])
+}
+
+#[test]
+fn records_latency_and_bytes_per_method_and_model() {
+    let recorder = with_recorder(|| async {
+        let server = MockServer::new()
+            .model(model("echo"))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        let client = server.client();
+        client.infer(request("echo")).await.unwrap();
+        client.infer(request("echo")).await.unwrap();
+        assert!(client.is_server_live().await.unwrap());
+    });
+
+    let labels = [("method", "model_infer"), ("model", "echo")];
+    let latencies = recorder.histogram(REQUEST_DURATION, &labels);
+    assert_eq!(latencies.len(), 2);
+    assert!(latencies.iter().all(|seconds| *seconds >= 0.0));
+    let request_bytes = prost::Message::encoded_len(&request("echo")) as u64;
+    assert_eq!(
+        recorder.counter(REQUEST_BYTES, &labels),
+        Some(2 * request_bytes)
+    );
+    assert!(recorder.counter(RESPONSE_BYTES, &labels).unwrap() > 0);
+    assert_eq!(recorder.counter(ERRORS, &labels), None);
+
+    // Calls without a model have an empty model label.
+    let labels = [("method", "server_live"), ("model", "")];
+    assert_eq!(recorder.histogram(REQUEST_DURATION, &labels).len(), 1);
+}
+
+#[test]
+fn counts_errors_by_status_code() {
+    let recorder = with_recorder(|| async {
+        let server = MockServer::new()
+            .model(model("failing").handler(InferHandler::Error(Status::invalid_argument("bad"))))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        let client = server.client();
+        assert!(client.infer(request("failing")).await.is_err());
+        assert!(client.infer(request("failing")).await.is_err());
+    });
+
+    let labels = [
+        ("method", "model_infer"),
+        ("model", "failing"),
+        ("code", "InvalidArgument"),
+    ];
+    assert_eq!(recorder.counter(ERRORS, &labels), Some(2));
+    let labels = [("method", "model_infer"), ("model", "failing")];
+    assert_eq!(recorder.counter(RESPONSE_BYTES, &labels), None);
+    assert_eq!(recorder.histogram(REQUEST_DURATION, &labels).len(), 2);
+}
+
+#[test]
+fn counts_reconnects_and_retries() {
+    let recorder = with_recorder(|| async {
+        let server = MockServer::new()
+            .model(model("flaky").transient_errors(1, Status::unavailable("restarting")))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        server.client().infer(request("flaky")).await.unwrap();
+    });
+
+    let reconnect = [("method", "model_infer"), ("code", "Unavailable")];
+    assert_eq!(recorder.counter(RECONNECTS, &reconnect), Some(1));
+    assert_eq!(
+        recorder.counter(RETRIES, &[("method", "model_infer")]),
+        Some(1)
+    );
+    // The retried call is recorded once, as a success.
+    let labels = [("method", "model_infer"), ("model", "flaky")];
+    assert_eq!(recorder.histogram(REQUEST_DURATION, &labels).len(), 1);
+    let values = recorder.0.lock().unwrap();
+    assert!(values.counters.keys().all(|(name, _)| name != ERRORS));
+}
@@ -0,0 +1,144 @@
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+use tritonclient::grpc::client::{
+    Backoff, Error, InferenceServerClient, InferenceServerClientConfig, ModelState, WaitOptions,
+};
+use tritonclient::grpc::pb::repository_index_response::ModelIndex;
+use tritonclient::grpc::repository::ModelLoadRequest;
+use tritonclient::grpc::testing::{MockModel, MockServer};
+
+fn wait(timeout: Duration) -> WaitOptions {
+    let backoff = Backoff::new()
+        .initial(Duration::from_millis(10))
+        .max(Duration::from_millis(50));
+    WaitOptions::new().timeout(timeout).backoff(backoff)
+}
+
+// A local address nothing listens on until a server is started at it
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+fn client_for(addr: SocketAddr) -> InferenceServerClient {
+    let config = InferenceServerClientConfig::from_uri(format!("http://{addr}")).unwrap();
+    InferenceServerClient::new(config)
+}
+
+fn states(entries: &[ModelIndex]) -> Vec<&str> {
+    entries.iter().map(|entry| entry.state.as_str()).collect()
+}
+
+#[tokio::test]
+async fn loads_and_unloads_then_waits() {
+    let server = MockServer::new()
+        .model(MockModel::new("model").versions(&[1, 2]).ready(false))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let client = server.client();
+    let wait = wait(Duration::from_secs(5));
+
+    let entries = client
+        .load_model_and_wait(ModelLoadRequest::new("model"), &[], &wait)
+        .await
+        .unwrap();
+    assert_eq!(states(&entries), ["READY", "READY"]);
+
+    let entries = client
+        .unload_model_and_wait("", "model", false, &["2"], &wait)
+        .await
+        .unwrap();
+    assert_eq!(states(&entries), ["UNAVAILABLE", "UNAVAILABLE"]);
+
+    // The server refuses to load a model it does not have
+    let error = client
+        .load_model_and_wait(ModelLoadRequest::new("missing"), &[], &wait)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::ResponseError { .. }), "{error}");
+}
+
+#[tokio::test]
+async fn times_out_with_the_reason_of_each_version() {
+    let server = MockServer::new()
+        .model(MockModel::new("model").versions(&[1]).ready(false))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let client = server.client();
+    let wait = wait(Duration::from_millis(100));
+
+    let error = client
+        .wait_for_model_state("", "model", &["1", "3"], ModelState::Ready, &wait)
+        .await
+        .unwrap_err();
+    let Error::ModelStateTimeout {
+        model,
+        state,
+        reason,
+    } = error
+    else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!((model.as_str(), state.as_str()), ("model", "READY"));
+    assert_eq!(
+        reason,
+        "version 1 is UNAVAILABLE: unloaded; version 3 is not in the repository"
+    );
+
+    let error = client
+        .wait_for_model_state("", "missing", &[], ModelState::Ready, &wait)
+        .await
+        .unwrap_err();
+    match &error {
+        Error::ModelStateTimeout { reason, .. } => assert_eq!(reason, "not in the repository"),
+        error => panic!("unexpected error {error}"),
+    }
+}
+
+#[tokio::test]
+async fn retries_while_the_server_is_down() {
+    let addr = free_addr();
+    let client = client_for(addr);
+    let server = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        MockServer::new()
+            .model(MockModel::new("model"))
+            .serve_at(addr)
+            .await
+            .unwrap()
+    });
+
+    let entries = client
+        .wait_for_model_state(
+            "",
+            "model",
+            &[],
+            ModelState::Ready,
+            &wait(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(states(&entries), ["READY"]);
+    drop(server.await.unwrap());
+
+    // A server that never comes up, the timeout carries the connection failure
+    let client = client_for(free_addr());
+    let error = client
+        .wait_for_model_state(
+            "",
+            "model",
+            &[],
+            ModelState::Ready,
+            &wait(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&error, Error::ModelStateTimeout { reason, .. } if !reason.is_empty()),
+        "{error}"
+    );
+}
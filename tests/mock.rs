@@ -0,0 +1,183 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::{Code, Status};
+use tritonclient::grpc::client::Error;
+use tritonclient::grpc::output::ArrayOutputOneOf;
+use tritonclient::grpc::pb;
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer, MockServerHandle};
+use tritonclient::types::TritonDataTypes;
+
+fn model(handler: InferHandler) -> MockModel {
+    MockModel::new("model")
+        .input("x", TritonDataTypes::INT32, &[2])
+        .output("x", TritonDataTypes::INT32, &[2])
+        .handler(handler)
+}
+
+fn request() -> pb::ModelInferRequest {
+    pb::ModelInferRequest {
+        model_name: "model".to_string(),
+        id: "7".to_string(),
+        inputs: vec![pb::InferInputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![2],
+            ..Default::default()
+        }],
+        raw_input_contents: vec![[3i32, -3].iter().flat_map(|v| v.to_le_bytes()).collect()],
+        ..Default::default()
+    }
+}
+
+fn ints(output: &tritonclient::grpc::output::ModelOutput) -> Vec<i32> {
+    match output.as_ndarray("x") {
+        Some(ArrayOutputOneOf::INT32(array)) => array.iter().copied().collect(),
+        other => panic!("unexpected output {other:?}"),
+    }
+}
+
+fn status_of(error: Error) -> Box<Status> {
+    match error {
+        Error::ResponseError { status } => status,
+        error => panic!("unexpected error {error}"),
+    }
+}
+
+async fn in_memory(model: MockModel) -> MockServerHandle {
+    MockServer::new()
+        .model(model)
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn echoes_the_inputs() {
+    let server = in_memory(model(InferHandler::Echo)).await;
+    let output = server.client().infer(request()).await.unwrap();
+    assert_eq!(ints(&output), vec![3, -3]);
+    assert_eq!(server.infer_requests(), vec![request()]);
+    server.clear_infer_requests();
+    assert!(server.infer_requests().is_empty());
+}
+
+#[tokio::test]
+async fn answers_with_fixed_responses_errors_and_functions() {
+    let mut fixed = request();
+    fixed.raw_input_contents = vec![[1i32, 2].iter().flat_map(|v| v.to_le_bytes()).collect()];
+    let response = pb::ModelInferResponse {
+        outputs: vec![pb::InferOutputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![2],
+            ..Default::default()
+        }],
+        raw_output_contents: fixed.raw_input_contents.clone(),
+        ..Default::default()
+    };
+    let server = in_memory(model(InferHandler::Fixed(response))).await;
+    let output = server.client().infer(request()).await.unwrap();
+    assert_eq!(ints(&output), vec![1, 2]);
+
+    let handler = InferHandler::Error(Status::resource_exhausted("full"));
+    let server = in_memory(model(handler)).await;
+    let status = status_of(server.client().infer(request()).await.unwrap_err());
+    assert_eq!(
+        (status.code(), status.message()),
+        (Code::ResourceExhausted, "full")
+    );
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler = InferHandler::custom({
+        let calls = calls.clone();
+        move |request| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            match request.id.as_str() {
+                "7" => Err(Status::invalid_argument(format!("bad id {}", request.id)).into()),
+                "8" => Err(Error::InvalidBatch("no".to_string())),
+                _ => Ok(pb::ModelInferResponse::default()),
+            }
+        }
+    });
+    let server = in_memory(model(handler)).await;
+    let client = server.client();
+    let status = status_of(client.infer(request()).await.unwrap_err());
+    assert_eq!(
+        (status.code(), status.message()),
+        (Code::InvalidArgument, "bad id 7")
+    );
+    // Errors without a status are reported as INTERNAL, which the client retries once
+    let mut internal = request();
+    internal.id = "8".to_string();
+    let status = status_of(client.infer(internal).await.unwrap_err());
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("no"), "{status:?}");
+    let mut ok = request();
+    ok.id = "9".to_string();
+    client.infer(ok).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn injects_latency_and_transient_errors() {
+    let model = model(InferHandler::Echo)
+        .latency(Duration::from_millis(100))
+        .transient_errors(3, Status::unavailable("warming up"));
+    let server = in_memory(model).await;
+    let client = server.client();
+    // The client retries UNAVAILABLE once, the first call sees two injected errors
+    let status = status_of(client.infer(request()).await.unwrap_err());
+    assert_eq!(
+        (status.code(), status.message()),
+        (Code::Unavailable, "warming up")
+    );
+    let start = Instant::now();
+    client.infer(request()).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(server.calls("ModelInfer"), 4);
+}
+
+#[tokio::test]
+async fn serves_over_tcp_and_in_memory() {
+    let tcp = MockServer::new()
+        .model(model(InferHandler::Echo))
+        .serve()
+        .await
+        .unwrap();
+    let addr = tcp.addr().unwrap();
+    assert_eq!(tcp.uri(), format!("http://{addr}"));
+    let memory = in_memory(model(InferHandler::Echo)).await;
+    assert_eq!(memory.addr(), None);
+
+    for server in [&tcp, &memory] {
+        let client = server.client();
+        assert!(client.is_server_ready().await.unwrap());
+        assert!(client.is_model_ready("model", None).await.unwrap());
+        assert_eq!(ints(&client.infer(request()).await.unwrap()), vec![3, -3]);
+        assert_eq!(server.calls("ServerReady"), 1);
+        assert_eq!(server.calls("ModelReady"), 1);
+        assert_eq!(server.calls("ModelConfig"), 0);
+    }
+}
+
+#[tokio::test]
+async fn follows_runtime_changes() {
+    let server = in_memory(model(InferHandler::Echo)).await;
+    let client = server.client();
+    server.set_ready(false);
+    server.set_live(false);
+    assert!(!client.is_server_ready().await.unwrap());
+    assert!(!client.is_server_live().await.unwrap());
+
+    server.set_model_ready("model", false);
+    assert!(!client.is_model_ready("model", None).await.unwrap());
+    let status = status_of(client.infer(request()).await.unwrap_err());
+    assert_eq!(status.code(), Code::Unavailable);
+
+    server.remove_model("model");
+    let status = status_of(client.infer(request()).await.unwrap_err());
+    assert_eq!(status.code(), Code::NotFound);
+    server.set_model(model(InferHandler::Echo));
+    client.infer(request()).await.unwrap();
+}
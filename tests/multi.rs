@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tritonclient::grpc::client::{Error, InferenceServerClient, MultiServerClient};
+use tritonclient::grpc::testing::{MockModel, MockServer, MockServerHandle};
+
+async fn servers() -> (MockServerHandle, MockServerHandle) {
+    let first = MockServer::new()
+        .model(MockModel::new("shared"))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let second = MockServer::new()
+        .model(MockModel::new("shared"))
+        .model(MockModel::new("only_second").versions(&[1, 2]))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    (first, second)
+}
+
+fn index_of(multi: &MultiServerClient, client: &InferenceServerClient) -> usize {
+    multi
+        .clients()
+        .iter()
+        .position(|c| std::ptr::eq(c, client))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn routes_to_the_servers_serving_the_model() {
+    let (first, second) = servers().await;
+    let multi = MultiServerClient::new(vec![first.client(), second.client()]);
+
+    let client = multi.route("only_second", "2").await.unwrap();
+    assert_eq!(index_of(&multi, client), 1);
+    assert_eq!(multi.servers_for("shared", "").unwrap(), vec![0, 1]);
+    let mut picked = vec![];
+    for _ in 0..4 {
+        picked.push(index_of(&multi, multi.route("shared", "1").await.unwrap()));
+    }
+    picked.sort();
+    assert_eq!(picked, vec![0, 0, 1, 1]);
+    assert_eq!(
+        multi.models().unwrap()["only_second"],
+        vec!["1".to_string(), "2".to_string()]
+    );
+    assert!(matches!(
+        multi.route("only_second", "3").await,
+        Err(Error::ModelNotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn refreshes_for_unknown_models_at_most_every_min_refresh_interval() {
+    let (first, second) = servers().await;
+    let multi = MultiServerClient::new(vec![first.client(), second.client()])
+        .min_refresh_interval(Duration::from_secs(3600));
+    multi.refresh().await.unwrap();
+
+    first.set_model(MockModel::new("new"));
+    assert!(multi.route("new", "").await.is_err());
+
+    let multi = MultiServerClient::new(vec![first.client(), second.client()])
+        .min_refresh_interval(Duration::ZERO);
+    multi.refresh().await.unwrap();
+    second.set_model(MockModel::new("newer"));
+    assert_eq!(index_of(&multi, multi.route("newer", "").await.unwrap()), 1);
+}
+
+#[tokio::test]
+async fn shares_concurrent_refreshes_and_skips_unreachable_servers() {
+    let (first, second) = servers().await;
+    let multi = MultiServerClient::new(vec![first.client(), second.client()]);
+    second.shutdown();
+
+    let results = futures_util::future::join_all((0..8).map(|_| multi.refresh())).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(multi.servers_for("shared", "").unwrap(), vec![0]);
+    assert!(multi.servers_for("only_second", "").unwrap().is_empty());
+}
+
+// Poll the routing table until the model is served by `expected`, failing after a few seconds
+async fn wait_for_route(multi: &MultiServerClient, model: &str, expected: Vec<usize>) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while multi.servers_for(model, "").unwrap() != expected {
+        assert!(Instant::now() < deadline, "`{model}` is not routed to {expected:?}");
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn refreshes_in_the_background() {
+    let (first, second) = servers().await;
+    let multi = Arc::new(
+        MultiServerClient::new(vec![first.client(), second.client()])
+            .refresh_interval(Duration::from_millis(20)),
+    );
+    let refresher = multi.spawn_refresh();
+    wait_for_route(&multi, "shared", vec![0, 1]).await;
+    first.set_model(MockModel::new("new"));
+    wait_for_route(&multi, "new", vec![0]).await;
+
+    // The task ends once the client is dropped
+    drop(multi);
+    tokio::time::timeout(Duration::from_secs(5), refresher)
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Status;
+use tritonclient::grpc::model::Dim;
+use tritonclient::grpc::perf::{LoadMode, PerfOptions, PerfRunner, SyntheticInputs};
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer, MockServerHandle};
+use tritonclient::types::TritonDataTypes;
+
+const LATENCY: Duration = Duration::from_millis(10);
+
+async fn server(handler: InferHandler) -> MockServerHandle {
+    // The batch size is set after the input, the served metadata still carries the batch dim
+    let model = MockModel::new("model")
+        .input("x", TritonDataTypes::FP32, &[3])
+        .output("x", TritonDataTypes::FP32, &[3])
+        .max_batch_size(4)
+        .latency(LATENCY)
+        .handler(handler);
+    MockServer::new()
+        .model(model)
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+async fn runner(server: &MockServerHandle) -> PerfRunner {
+    let client = server.client();
+    let metadata = client.model_metadata_view("model", None).await.unwrap();
+    assert_eq!(metadata.inputs[0].dims, vec![Dim::Dynamic, Dim::Fixed(3)]);
+    let request = SyntheticInputs::new()
+        .batch_size(2)
+        .request(&metadata, "", 4)
+        .unwrap();
+    assert_eq!(request.inputs[0].shape, vec![2, 3]);
+    let options = PerfOptions::new()
+        .warmup(Duration::from_millis(50))
+        .measurement(Duration::from_millis(400))
+        .server_stats(true);
+    PerfRunner::new(Arc::new(client), request, 2).options(options)
+}
+
+#[tokio::test]
+async fn measures_client_and_server_latency() {
+    let server = server(InferHandler::Echo).await;
+    let measurement = runner(&server)
+        .await
+        .measure(LoadMode::Concurrency(2))
+        .await
+        .unwrap();
+
+    assert_eq!(measurement.errors, 0);
+    assert_eq!(measurement.batch_size, 2);
+    // 2 requests in flight for 400ms at 10ms each, give or take scheduling
+    assert!(
+        (20..=80).contains(&measurement.requests),
+        "{} requests",
+        measurement.requests
+    );
+    let latency = measurement.latency.unwrap();
+    assert_eq!(latency.count, measurement.requests);
+    assert!(latency.min >= LATENCY, "{latency:?}");
+    assert!(measurement.throughput() > 0.0);
+    let server = measurement.server.unwrap();
+    assert!(server.stats.requests() > 0);
+}
+
+#[tokio::test]
+async fn fails_when_no_request_succeeds() {
+    let server = server(InferHandler::Error(Status::internal("broken"))).await;
+    let runner = runner(&server).await;
+    let error = runner.measure(LoadMode::Concurrency(1)).await.unwrap_err();
+    assert!(error.to_string().contains("broken"), "{error}");
+    assert!(runner.measure(LoadMode::RequestRate(0.0)).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_a_shape_too_large_to_allocate() {
+    let model = MockModel::new("model")
+        .input("x", TritonDataTypes::FP32, &[-1])
+        .output("x", TritonDataTypes::FP32, &[-1]);
+    let server = MockServer::new()
+        .model(model)
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let metadata = server
+        .client()
+        .model_metadata_view("model", None)
+        .await
+        .unwrap();
+    let error = SyntheticInputs::new()
+        .shape("x", vec![i64::MAX])
+        .request(&metadata, "", 0)
+        .unwrap_err();
+    assert!(error.to_string().contains("too large"), "{error}");
+}
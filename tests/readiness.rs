@@ -0,0 +1,139 @@
+use std::net::TcpListener;
+use std::time::Duration;
+use tokio::time::Instant;
+use tritonclient::grpc::client::{
+    Backoff, Error, InferenceServerClient, InferenceServerClientConfig, WaitOptions,
+};
+use tritonclient::grpc::testing::{MockModel, MockServer};
+
+fn wait(timeout: Duration) -> WaitOptions {
+    let backoff = Backoff::new()
+        .initial(Duration::from_millis(10))
+        .max(Duration::from_millis(50));
+    WaitOptions::new().timeout(timeout).backoff(backoff)
+}
+
+// A local address nothing listens on until a server is started at it
+fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn client_for(addr: &str) -> InferenceServerClient {
+    InferenceServerClient::new(
+        InferenceServerClientConfig::from_uri(format!("http://{addr}")).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn waits_for_a_server_started_later() {
+    let addr = free_addr();
+    let client = client_for(&addr);
+    let server = tokio::spawn({
+        let addr = addr.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            MockServer::new()
+                .model(MockModel::new("model"))
+                .serve_at(addr)
+                .await
+                .unwrap()
+        }
+    });
+
+    let start = Instant::now();
+    // Connections are refused until the server is up
+    client
+        .wait_for_server_ready(&wait(Duration::from_secs(10)))
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    client
+        .wait_for_models_ready(&[("model", None)], &wait(Duration::from_secs(10)))
+        .await
+        .unwrap();
+    drop(server.await.unwrap());
+}
+
+#[tokio::test]
+async fn gives_up_at_the_deadline() {
+    let server = MockServer::new()
+        .ready(false)
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let client = server.client();
+
+    let start = Instant::now();
+    let error = client
+        .wait_for_server_ready(&wait(Duration::from_millis(200)))
+        .await
+        .unwrap_err();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    let Error::ServerNotReady { reason } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(reason, "server is live but not ready");
+
+    // A server that never answers has the connection failure as reason
+    let client = client_for(&free_addr());
+    let error = client
+        .wait_for_server_ready(&wait(Duration::from_millis(100)))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::ServerNotReady { reason } if !reason.is_empty()));
+}
+
+#[tokio::test]
+async fn lists_the_models_that_are_not_ready() {
+    let server = MockServer::new()
+        .model(MockModel::new("ready"))
+        .model(MockModel::new("loading").ready(false))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let client = server.client();
+    let wanted = [("ready", None), ("loading", None), ("missing", Some("2"))];
+    let error = client
+        .wait_for_models_ready(&wanted, &wait(Duration::from_millis(100)))
+        .await
+        .unwrap_err();
+    let Error::ModelsNotReady { models } = &error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(
+        models,
+        &[
+            ("loading".to_string(), String::new(), String::new()),
+            ("missing".to_string(), "2".to_string(), String::new()),
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "Models were not ready in time: `loading`, `missing` version 2"
+    );
+
+    // Each model keeps its own reason
+    let client = client_for(&free_addr());
+    let error = client
+        .wait_for_models_ready(&wanted[..2], &wait(Duration::from_millis(100)))
+        .await
+        .unwrap_err();
+    let Error::ModelsNotReady { models } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(models.len(), 2);
+    assert!(
+        models.iter().all(|(_, _, reason)| !reason.is_empty()),
+        "{models:?}"
+    );
+
+    server.set_model_ready("loading", true);
+    server
+        .client()
+        .wait_for_models_ready(&wanted[..2], &wait(Duration::from_secs(5)))
+        .await
+        .unwrap();
+}
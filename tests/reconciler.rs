@@ -0,0 +1,89 @@
+use tritonclient::grpc::client::{ReconcileAction, Reconciler};
+use tritonclient::grpc::pb;
+use tritonclient::grpc::repository::{ModelSpec, RepositoryManifest};
+use tritonclient::grpc::testing::{MockModel, MockServer, MockServerHandle};
+
+async fn server() -> MockServerHandle {
+    MockServer::new()
+        .model(MockModel::new("detector").max_batch_size(8))
+        .model(MockModel::new("classifier").ready(false))
+        .model(MockModel::new("legacy"))
+        .serve_in_memory()
+        .await
+        .unwrap()
+}
+
+fn config(name: &str, max_batch_size: i32) -> pb::ModelConfig {
+    pb::ModelConfig {
+        name: name.to_string(),
+        max_batch_size,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn plans_loads_and_keeps_unlisted_models_by_default() {
+    let server = server().await;
+    let manifest = RepositoryManifest::new()
+        .model(ModelSpec::new("detector").config(config("detector", 8)))
+        .model(ModelSpec::new("classifier"));
+    assert!(!manifest.unload_unlisted);
+
+    let plan = Reconciler::new(server.client(), manifest)
+        .plan()
+        .await
+        .unwrap();
+    assert_eq!(
+        plan.actions,
+        vec![ReconcileAction::Load {
+            model: "classifier".to_string(),
+            versions: vec![],
+            reason: "not ready".to_string(),
+        }]
+    );
+    assert_eq!(plan.unchanged, vec!["detector".to_string()]);
+}
+
+#[tokio::test]
+async fn reloads_models_whose_config_changed() {
+    let server = server().await;
+    let manifest = RepositoryManifest::new()
+        .model(ModelSpec::new("detector").config(config("detector", 4)))
+        .model(ModelSpec::new("legacy"));
+
+    let plan = Reconciler::new(server.client(), manifest)
+        .plan()
+        .await
+        .unwrap();
+    assert_eq!(
+        plan.actions,
+        vec![ReconcileAction::Load {
+            model: "detector".to_string(),
+            versions: vec![],
+            reason: "config differs at `max_batch_size`".to_string(),
+        }]
+    );
+    assert_eq!(plan.unchanged, vec!["legacy".to_string()]);
+}
+
+#[tokio::test]
+async fn unloads_unlisted_models_when_asked() {
+    let server = server().await;
+    let manifest = RepositoryManifest::new()
+        .model(ModelSpec::new("detector").versions(vec![1]))
+        .unload_unlisted(true);
+
+    let reconciler = Reconciler::new(server.client(), manifest);
+    let plan = reconciler.plan().await.unwrap();
+    assert_eq!(
+        plan.actions,
+        vec![ReconcileAction::Unload {
+            model: "legacy".to_string(),
+            reason: "not in the manifest".to_string(),
+        }]
+    );
+
+    let report = reconciler.apply(&plan).await;
+    assert!(report.is_success(), "{:?}", report.failed);
+    assert!(reconciler.plan().await.unwrap().is_empty());
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tritonclient::grpc::client::Error;
+use tritonclient::server_metrics::{fetch_metrics, MetricsClient};
+
+#[derive(Default)]
+struct Counts {
+    connections: AtomicUsize,
+    requests: AtomicUsize,
+}
+
+// Serves `status` and the body `respond` gives for the n-th scrape, keeping connections alive.
+async fn stub(
+    status: &'static str,
+    respond: impl Fn(usize) -> String + Send + Sync + 'static,
+) -> (String, Arc<Counts>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+    let counts = Arc::new(Counts::default());
+    let respond = Arc::new(respond);
+    let server_counts = counts.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            server_counts.connections.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(serve(
+                stream,
+                status,
+                respond.clone(),
+                server_counts.clone(),
+            ));
+        }
+    });
+    (url, counts)
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    status: &'static str,
+    respond: Arc<impl Fn(usize) -> String>,
+    counts: Arc<Counts>,
+) {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+        // GET requests have no body, the head ends the request.
+        while let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            request.drain(..end + 4);
+            let body = respond(counts.requests.fetch_add(1, Ordering::SeqCst));
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: text/plain\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn polls_the_endpoint_over_one_connection() {
+    let (url, counts) = stub("200 OK", |scrape| {
+        format!(
+            "nv_inference_request_success{{model=\"m\",version=\"1\"}} {}\n",
+            10 * (scrape + 1)
+        )
+    })
+    .await;
+    let client = MetricsClient::new(&url).unwrap();
+    assert_eq!(client.url().path(), "/metrics");
+
+    for expected in [10, 20, 30] {
+        let metrics = client.fetch().await.unwrap();
+        assert_eq!(metrics.model("m", "1").unwrap().request_success, expected);
+    }
+    assert_eq!(counts.requests.load(Ordering::SeqCst), 3);
+    assert_eq!(counts.connections.load(Ordering::SeqCst), 1);
+
+    let metrics = fetch_metrics(&url).await.unwrap();
+    assert_eq!(metrics.model("m", "1").unwrap().request_success, 40);
+    assert_eq!(
+        client.fetch_text().await.unwrap(),
+        "nv_inference_request_success{model=\"m\",version=\"1\"} 50\n"
+    );
+}
+
+#[tokio::test]
+async fn fails_on_error_statuses_and_bad_bodies() {
+    let (url, _) = stub("503 Service Unavailable", |_| "not ready".to_string()).await;
+    match fetch_metrics(&url).await {
+        Err(Error::HttpError(message)) => assert!(message.contains("503"), "{message}"),
+        other => panic!("unexpected result {other:?}"),
+    }
+
+    let (url, _) = stub("200 OK", |_| "nv_cpu_utilization{ 1\n".to_string()).await;
+    assert!(matches!(
+        fetch_metrics(&url).await,
+        Err(Error::ParseError { line: 1, .. })
+    ));
+}
+
+#[tokio::test]
+async fn fails_on_bad_urls_and_unreachable_servers() {
+    assert!(matches!(
+        MetricsClient::new("http://bad host/metrics"),
+        Err(Error::InvalidUri(_))
+    ));
+
+    // Bind then drop a listener for a port nothing listens on.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    assert!(matches!(
+        fetch_metrics(&format!("http://{addr}/metrics")).await,
+        Err(Error::HttpError(_))
+    ));
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use tritonclient::grpc::settings::{
+    LogFormat, LogSettings, LogSettingsUpdate, TraceLevel, TraceMode, TraceSetting, TraceSettings,
+    TraceSettingsUpdate,
+};
+use tritonclient::grpc::testing::{MockModel, MockServer};
+
+#[tokio::test]
+async fn trace_settings_round_trip() {
+    let server = MockServer::new()
+        .model(MockModel::new("model"))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let client = server.client();
+    assert_eq!(
+        client.current_trace_settings(None).await.unwrap(),
+        TraceSettings::default()
+    );
+
+    let global = TraceSettingsUpdate::global()
+        .level(&[TraceLevel::Timestamps, TraceLevel::Tensors])
+        .rate(100)
+        .count(-1)
+        .log_frequency(50)
+        .file("/tmp/trace.json")
+        .mode(TraceMode::Triton);
+    let expected = TraceSettings {
+        level: vec![TraceLevel::Timestamps, TraceLevel::Tensors],
+        rate: Some(100),
+        count: Some(-1),
+        log_frequency: Some(50),
+        file: Some("/tmp/trace.json".to_string()),
+        mode: Some(TraceMode::Triton),
+        other: BTreeMap::new(),
+    };
+    assert_eq!(
+        client.update_trace_settings(global).await.unwrap(),
+        expected
+    );
+    assert_eq!(client.current_trace_settings(None).await.unwrap(), expected);
+
+    // A model override sits on top of the global settings until it is cleared
+    let model = TraceSettingsUpdate::for_model("model").rate(1);
+    let overridden = client.update_trace_settings(model).await.unwrap();
+    assert_eq!(overridden.rate, Some(1));
+    assert_eq!(overridden.file, expected.file);
+    let cleared = TraceSettingsUpdate::for_model("model").clear(TraceSetting::Rate);
+    assert_eq!(
+        client.update_trace_settings(cleared).await.unwrap(),
+        expected
+    );
+
+    let model = TraceSettingsUpdate::for_model("model")
+        .count(3)
+        .mode(TraceMode::OpenTelemetry);
+    client.update_trace_settings(model).await.unwrap();
+    assert_eq!(
+        client.clear_model_trace_settings("model").await.unwrap(),
+        expected
+    );
+    assert_eq!(client.current_trace_settings(None).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn log_settings_round_trip() {
+    let server = MockServer::new().serve_in_memory().await.unwrap();
+    let client = server.client();
+    assert_eq!(
+        client.current_log_settings().await.unwrap(),
+        LogSettings::default()
+    );
+
+    let update = LogSettingsUpdate::new()
+        .file("/tmp/triton.log")
+        .info(true)
+        .warning(false)
+        .error(true)
+        .verbose_level(2)
+        .format(LogFormat::Iso8601);
+    let expected = LogSettings {
+        file: Some("/tmp/triton.log".to_string()),
+        info: Some(true),
+        warning: Some(false),
+        error: Some(true),
+        verbose_level: Some(2),
+        format: Some(LogFormat::Iso8601),
+        other: BTreeMap::new(),
+    };
+    assert_eq!(client.update_log_settings(update).await.unwrap(), expected);
+
+    let update = LogSettingsUpdate::new().verbose_level(0);
+    let settings = client.update_log_settings(update).await.unwrap();
+    assert_eq!(settings.verbose_level, Some(0));
+    assert_eq!(settings.file, expected.file);
+    assert_eq!(client.current_log_settings().await.unwrap(), settings);
+}
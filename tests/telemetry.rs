@@ -0,0 +1,249 @@
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tonic::Status;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Instrument, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use tritonclient::grpc::pb;
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer};
+use tritonclient::types::TritonDataTypes;
+
+type Fields = BTreeMap<String, String>;
+
+#[derive(Debug)]
+struct CapturedEvent {
+    span: Option<String>,
+    fields: Fields,
+}
+
+#[derive(Default)]
+struct Captured {
+    // Spans by name, in creation order
+    spans: Vec<(String, Fields)>,
+    ids: HashMap<Id, usize>,
+    events: Vec<CapturedEvent>,
+}
+
+// Keeps the fields of every span and event.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Captured>>);
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+        let mut fields = Fields::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        let mut captured = self.0.lock().unwrap();
+        let index = captured.spans.len();
+        captured
+            .spans
+            .push((attrs.metadata().name().to_string(), fields));
+        captured.ids.insert(id.clone(), index);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+        let mut captured = self.0.lock().unwrap();
+        if let Some(index) = captured.ids.get(id).copied() {
+            values.record(&mut FieldVisitor(&mut captured.spans[index].1));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let span = ctx.event_span(event).map(|span| span.name().to_string());
+        self.0
+            .lock()
+            .unwrap()
+            .events
+            .push(CapturedEvent { span, fields });
+    }
+}
+
+impl Capture {
+    fn spans(&self, name: &str) -> Vec<Fields> {
+        let captured = self.0.lock().unwrap();
+        captured
+            .spans
+            .iter()
+            .filter(|(span, _)| span == name)
+            .map(|(_, fields)| fields.clone())
+            .collect()
+    }
+
+    fn event(&self, message: &str) -> Option<(Option<String>, Fields)> {
+        let captured = self.0.lock().unwrap();
+        captured
+            .events
+            .iter()
+            .find(|event| event.fields.get("message").map(String::as_str) == Some(message))
+            .map(|event| (event.span.clone(), event.fields.clone()))
+    }
+}
+
+// Run `f` on a single-threaded runtime with spans captured and exported to OpenTelemetry.
+fn traced<F: Future>(f: impl FnOnce() -> F) -> (Capture, F::Output) {
+    let capture = Capture::default();
+    let tracer = SdkTracerProvider::builder().build().tracer("test");
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .with(capture.clone());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let output = tracing::subscriber::with_default(subscriber, || runtime.block_on(f()));
+    (capture, output)
+}
+
+fn request(model_name: &str) -> pb::ModelInferRequest {
+    pb::ModelInferRequest {
+        model_name: model_name.to_string(),
+        model_version: "1".to_string(),
+        id: "request-7".to_string(),
+        inputs: vec![pb::InferInputTensor {
+            name: "x".to_string(),
+            datatype: "INT32".to_string(),
+            shape: vec![2],
+            ..Default::default()
+        }],
+        raw_input_contents: vec![vec![0; 8]],
+        ..Default::default()
+    }
+}
+
+fn model(name: &str) -> MockModel {
+    MockModel::new(name)
+        .input("x", TritonDataTypes::INT32, &[2])
+        .output("x", TritonDataTypes::INT32, &[2])
+}
+
+#[test]
+fn injects_the_traceparent_of_the_rpc_span() {
+    let (capture, (metadata, trace_id)) = traced(|| async {
+        let server = MockServer::new()
+            .model(model("model"))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        let caller = tracing::info_span!("caller");
+        let trace_id = caller.context().span().span_context().trace_id();
+        server
+            .client()
+            .infer(request("model"))
+            .instrument(caller)
+            .await
+            .unwrap();
+        (server.last_metadata("ModelInfer").unwrap(), trace_id)
+    });
+
+    let traceparent = metadata.get("traceparent").unwrap().to_str().unwrap();
+    let parts = traceparent.split('-').collect::<Vec<_>>();
+    assert_eq!(parts.len(), 4, "{traceparent}");
+    assert_eq!(parts[0], "00");
+    // The RPC span is a child of the caller, in the same trace.
+    assert_eq!(parts[1], format!("{trace_id:032x}"));
+    assert_eq!(parts[2].len(), 16);
+    assert_eq!(parts[3], "01");
+    assert_eq!(capture.spans("triton_rpc").len(), 1);
+}
+
+#[tokio::test]
+async fn sends_no_traceparent_without_a_span() {
+    let server = MockServer::new()
+        .model(model("model"))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    server.client().infer(request("model")).await.unwrap();
+    let metadata = server.last_metadata("ModelInfer").unwrap();
+    assert!(metadata.get("traceparent").is_none());
+}
+
+#[test]
+fn records_the_documented_span_fields() {
+    let (capture, _) = traced(|| async {
+        let server = MockServer::new()
+            .model(model("model"))
+            .model(model("failing").handler(InferHandler::Error(Status::invalid_argument("bad"))))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        let client = server.client();
+        client.infer(request("model")).await.unwrap();
+        client.infer(request("failing")).await.unwrap_err();
+    });
+
+    let spans = capture.spans("triton_rpc");
+    assert_eq!(spans.len(), 2);
+    let request_bytes = prost::Message::encoded_len(&request("model")).to_string();
+    let expected = [
+        ("otel.name", "model_infer"),
+        ("otel.kind", "client"),
+        ("rpc.system", "grpc"),
+        ("rpc.method", "model_infer"),
+        ("model_name", "model"),
+        ("model_version", "1"),
+        ("request_id", "request-7"),
+        ("request_bytes", request_bytes.as_str()),
+    ];
+    for (field, value) in expected {
+        assert_eq!(
+            spans[0].get(field).map(String::as_str),
+            Some(value),
+            "{field}"
+        );
+    }
+    assert!(spans[0]["response_bytes"].parse::<usize>().unwrap() > 0);
+    assert!(!spans[0].contains_key("error"));
+    assert!(!spans[0].contains_key("otel.status_code"));
+
+    assert_eq!(spans[1]["model_name"], "failing");
+    assert_eq!(spans[1]["otel.status_code"], "ERROR");
+    assert!(spans[1]["error"].contains("bad"), "{}", spans[1]["error"]);
+    assert!(!spans[1].contains_key("response_bytes"));
+}
+
+#[test]
+fn records_reconnects_and_retries_as_span_events() {
+    let (capture, _) = traced(|| async {
+        let server = MockServer::new()
+            .model(model("flaky").transient_errors(1, Status::unavailable("restarting")))
+            .serve_in_memory()
+            .await
+            .unwrap();
+        server.client().infer(request("flaky")).await.unwrap();
+    });
+
+    let (span, fields) = capture
+        .event("channel dropped after a transport failure")
+        .unwrap();
+    assert_eq!(span.as_deref(), Some("triton_rpc"));
+    assert_eq!(fields["code"], "Unavailable");
+    assert_eq!(fields["status_message"], "restarting");
+    let (span, _) = capture
+        .event("request sent again on a new channel")
+        .unwrap();
+    assert_eq!(span.as_deref(), Some("triton_rpc"));
+    assert!(!capture.spans("triton_rpc")[0].contains_key("error"));
+}
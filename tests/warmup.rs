@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use tonic::Status;
+use tritonclient::grpc::client::Error;
+use tritonclient::grpc::pb::model_warmup::input::InputDataType;
+use tritonclient::grpc::pb::model_warmup::Input;
+use tritonclient::grpc::pb::{self, DataType};
+use tritonclient::grpc::testing::{InferHandler, MockModel, MockServer};
+
+fn config() -> pb::ModelConfig {
+    let zeros = |dims: Vec<i64>| Input {
+        data_type: DataType::TypeInt32 as i32,
+        dims,
+        input_data_type: Some(InputDataType::ZeroData(true)),
+    };
+    pb::ModelConfig {
+        name: "model".to_string(),
+        max_batch_size: 4,
+        input: vec![pb::ModelInput {
+            name: "x".to_string(),
+            data_type: DataType::TypeInt32 as i32,
+            dims: vec![2],
+            ..Default::default()
+        }],
+        model_warmup: vec![
+            pb::ModelWarmup {
+                name: "twice".to_string(),
+                batch_size: 2,
+                inputs: HashMap::from([("x".to_string(), zeros(vec![2]))]),
+                count: 2,
+            },
+            pb::ModelWarmup {
+                name: "once".to_string(),
+                batch_size: 1,
+                inputs: HashMap::from([("x".to_string(), zeros(vec![2]))]),
+                count: 0,
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn runs_every_sample_count_times() {
+    let server = MockServer::new()
+        .model(MockModel::new("model").config(config()))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let report = server
+        .client()
+        .warmup_model("model", None, None)
+        .await
+        .unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.model_name, "model");
+    let counts = report
+        .samples
+        .iter()
+        .map(|sample| (sample.name.as_str(), sample.latencies.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![("twice", 2), ("once", 1)]);
+    assert!(report.samples[0].mean_latency().is_some());
+
+    let shapes = server
+        .infer_requests()
+        .iter()
+        .map(|request| (request.id.clone(), request.inputs[0].shape.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        shapes,
+        vec![
+            ("warmup:twice".to_string(), vec![2, 2]),
+            ("warmup:twice".to_string(), vec![2, 2]),
+            ("warmup:once".to_string(), vec![1, 2]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn collects_errors_per_sample() {
+    let server = MockServer::new()
+        .model(
+            MockModel::new("model")
+                .config(config())
+                .handler(InferHandler::Error(Status::invalid_argument("no warmup"))),
+        )
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let report = server
+        .client()
+        .run_warmup(&config(), Some("1"), None)
+        .await
+        .unwrap();
+    assert!(!report.is_success());
+    assert_eq!(report.samples[0].errors.len(), 2);
+    assert_eq!(report.samples[1].errors.len(), 1);
+    assert!(report.samples[0].latencies.is_empty());
+    assert_eq!(report.samples[0].mean_latency(), None);
+    assert!(matches!(
+        &report.samples[1].errors[0],
+        Error::ResponseError { status } if status.message() == "no warmup"
+    ));
+    assert!(server
+        .infer_requests()
+        .iter()
+        .all(|request| request.model_version == "1"));
+}
+
+#[tokio::test]
+async fn fails_before_sending_on_dynamic_dims() {
+    let mut config = config();
+    for warmup in &mut config.model_warmup {
+        warmup.inputs.get_mut("x").unwrap().dims = vec![-1];
+    }
+    let server = MockServer::new()
+        .model(MockModel::new("model").config(config.clone()))
+        .serve_in_memory()
+        .await
+        .unwrap();
+    let result = server.client().run_warmup(&config, None, None).await;
+    assert!(matches!(result, Err(Error::ConversionError(_))));
+    assert_eq!(server.calls("ModelInfer"), 0);
+}